@@ -1,18 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-use crate::{collection::CollectionScope, file::FileSystem, sql::interface::{get_sql_type, SQLType}};
+use crate::{collection::CollectionScope, file::FileSystem, sql::{interface::{get_sql_type, SQLType}, timeout::{parse_duration, Timeouts}}};
 
 pub const CONFIG_FILE_NAME: &str = "config.yaml";
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_config_set(config_manager: &mut ConfigManager, name: String, conn: String, 
-    host: String, port: u16, database: String, user: String, password: Option<String>) -> Result<()> {
+    host: Option<String>, port: Option<u16>, database: String, user: Option<String>, password: Option<String>, read_only: bool, danger: bool) -> Result<()> {
     let c = get_sql_type(&conn).ok_or_else(|| {
-        anyhow::anyhow!("Unsupported SQL type: '{}'. Supported types: postgresql", conn)
+        anyhow::anyhow!("Unsupported SQL type: '{}'. Supported types: postgresql, sqlite", conn)
     })?;
 
+    // An SQLite connection is just the path to its database file
+    let (host, port, user) = match c {
+        SQLType::Postgresql => (
+            host.context("--host is required for postgresql connections")?,
+            port.context("--port is required for postgresql connections")?,
+            user.context("--user is required for postgresql connections")?,
+        ),
+        SQLType::Sqlite => (String::new(), 0, String::new()),
+    };
+
     let connection = Connection {
         name,
         conn: c,
@@ -21,6 +31,11 @@ pub async fn run_config_set(config_manager: &mut ConfigManager, name: String, co
         database,
         user,
         password,
+        read_only,
+        danger,
+        statement_timeout: None,
+        lock_timeout: None,
+        connect_timeout: None,
         server_ca: None,
         client_cert: None,
         client_key: None,
@@ -49,12 +64,31 @@ pub async fn run_config_list(config_manager: &mut ConfigManager) -> Result<()> {
 pub struct Connection {
     pub name: String,
     pub conn: SQLType,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub host: String,
+    #[serde(default, skip_serializing_if = "is_unset_port")]
     pub port: u16,
+    /// For SQLite, the path to the database file
     pub database: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub user: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+
+    /// Transactions default to read-only, so the server rejects any writes
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// Marks a production (or otherwise dangerous) database, shown in red in the TUI
+    #[serde(default, alias = "production", skip_serializing_if = "std::ops::Not::not")]
+    pub danger: bool,
+
+    // Optional timeouts, e.g. `30s` or `500ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement_timeout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_timeout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<String>,
     
     // Optional SSL configuration
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -65,6 +99,10 @@ pub struct Connection {
     pub client_key: Option<String>,
 }
 
+fn is_unset_port(port: &u16) -> bool {
+    *port == 0
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConfigFile {
     pub connections: Vec<Connection>,
@@ -101,9 +139,15 @@ impl Config {
 
 impl Connection {
     pub fn to_url(&self, password: Option<String>) -> String {
+        if let SQLType::Sqlite = self.conn {
+            // `database` is the path to the database file
+            let url = format!("sqlite://{}", self.database);
+            return if self.read_only { format!("{}?mode=ro", url) } else { url };
+        }
+
         let pwd = password.or(self.password.clone());
         
-        match pwd {
+        let url = match pwd {
             Some(pass) => format!(
                 "{}://{}:{}@{}:{}/{}",
                 self.conn, self.user, pass, self.host, self.port, self.database
@@ -112,11 +156,32 @@ impl Connection {
                 "{}://{}@{}:{}/{}",
                 self.conn, self.user, self.host, self.port, self.database
             ),
+        };
+
+        if self.read_only {
+            format!("{}?options[default_transaction_read_only]=on", url)
+        } else {
+            url
         }
     }
 
     pub fn requires_password(&self) -> bool {
-        self.password.is_none()
+        self.password.is_none() && !matches!(self.conn, SQLType::Sqlite)
+    }
+
+    pub fn timeouts(&self) -> Result<Timeouts> {
+        let parse = |field: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(parse_duration)
+                .transpose()
+                .with_context(|| format!("Invalid {} for connection '{}'", field, self.name))
+        };
+        Ok(Timeouts {
+            statement: parse("statement_timeout", &self.statement_timeout)?,
+            lock: parse("lock_timeout", &self.lock_timeout)?,
+            connect: parse("connect_timeout", &self.connect_timeout)?,
+        })
     }
 }
 
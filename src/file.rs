@@ -158,8 +158,10 @@ impl FileSystem {
             return Ok(());
         }
         
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
+        let mut entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
             let path = entry.path();
             
             if path.is_dir() {
@@ -180,6 +182,7 @@ impl FileSystem {
                     }
                 }
                 
+                files.sort();
                 collections.push(Collection { name, files, scope });
             }
         }
@@ -0,0 +1,70 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::sql::format::format_sql;
+
+/// Formats SQL files in place, searching directories for `.sql` files (for CLI usage).
+/// With `check`, nothing is written: the files that aren't formatted are listed, and
+/// it fails if there are any.
+pub fn run_fmt(paths: Vec<PathBuf>, check: bool) -> Result<()> {
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_sql_files(path, &mut files)?;
+    }
+
+    let mut unformatted = 0;
+    for file in &files {
+        let sql = fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+        let formatted = format_file(&sql);
+        if formatted == sql {
+            continue;
+        }
+        unformatted += 1;
+        if check {
+            println!("{}", file.display());
+        } else {
+            fs::write(file, formatted).with_context(|| format!("Failed to write {}", file.display()))?;
+            println!("Formatted {}", file.display());
+        }
+    }
+
+    if check && unformatted > 0 {
+        bail!("{} of {} files are not formatted; run sqli fmt to fix them", unformatted, files.len());
+    }
+    Ok(())
+}
+
+/// The formatted contents of a SQL file, ending with a newline unless it's empty
+pub fn format_file(sql: &str) -> String {
+    let formatted = format_sql(sql);
+    if formatted.is_empty() {
+        formatted
+    } else {
+        formatted + "\n"
+    }
+}
+
+fn collect_sql_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        if !path.exists() {
+            bail!("{} does not exist", path.display());
+        }
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_sql_files(&path, files)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some("sql") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,340 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    collection::CollectionScope,
+    file::FileSystem,
+    settings::UserSettings,
+    sql::{
+        result::{format_output, Format, QueryResult},
+        value::Value,
+    },
+};
+
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Most entries kept; older ones are dropped when the file is trimmed
+const MAX_ENTRIES: usize = 5000;
+
+/// The file is trimmed once it is larger than this many bytes per entry kept, on average
+const TRIM_BYTES_PER_ENTRY: u64 = 512;
+
+/// Prints the queries run so far, oldest first (for CLI usage). `conn` keeps those run on
+/// a connection, and `grep` those whose SQL contains the text, ignoring case.
+pub fn run_history(conn: Option<String>, grep: Option<String>, format: Option<String>) -> Result<()> {
+    let output_format = match format {
+        Some(fmt) => Format::new(&fmt)?,
+        None => Format::default(),
+    };
+
+    let grep = grep.map(|pattern| pattern.to_lowercase());
+    let entries: Vec<HistoryEntry> = History::from_env()?
+        .load()?
+        .into_iter()
+        .filter(|entry| conn.as_deref().is_none_or(|name| entry.ran_on(name)))
+        .filter(|entry| grep.as_deref().is_none_or(|pattern| entry.sql.to_lowercase().contains(pattern)))
+        .collect();
+
+    match output_format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        format => format_output(&[history_result(&entries)], format)?,
+    }
+    Ok(())
+}
+
+fn history_result(entries: &[HistoryEntry]) -> QueryResult {
+    let columns = ["timestamp", "connection", "duration_ms", "rows", "error", "sql"];
+    let rows = entries
+        .iter()
+        .map(|entry| vec![
+            entry.timestamp().into(),
+            entry.connection.clone().map_or(Value::Null, Value::Text),
+            Value::Int(entry.duration_ms as i64),
+            entry.rows.map_or(Value::Null, |rows| Value::Int(rows as i64)),
+            entry.error.clone().map_or(Value::Null, Value::Text),
+            entry.summary().into(),
+        ])
+        .collect();
+    QueryResult::new(columns.iter().map(|c| c.to_string()).collect(), rows, Duration::ZERO)
+}
+
+/// One run of a query, successful or not
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When the query started
+    pub timestamp: DateTime<Local>,
+    /// The configured connection it ran on; ad-hoc URLs aren't kept, as they may hold a password
+    pub connection: Option<String>,
+    pub sql: String,
+    pub duration_ms: u64,
+    /// Rows returned or affected, over all of its statements; `None` if it failed
+    pub rows: Option<u64>,
+    /// Why it failed; `None` if it succeeded
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Records how a run of `sql` that started at `started_at` and took `duration` turned out
+    pub fn new(
+        sql: String,
+        connection: Option<String>,
+        started_at: DateTime<Local>,
+        duration: Duration,
+        outcome: &Result<Vec<QueryResult>>,
+    ) -> Self {
+        let (rows, error) = match outcome {
+            Ok(results) => (Some(results.iter().map(|r| r.rows_affected.unwrap_or(r.row_count as u64)).sum()), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        Self {
+            timestamp: started_at,
+            connection,
+            sql,
+            duration_ms: duration.as_millis() as u64,
+            rows,
+            error,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Whether it ran on the named connection; names are case-insensitive
+    pub fn ran_on(&self, connection: &str) -> bool {
+        self.connection.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(connection))
+    }
+
+    /// When the query started, e.g. `2024-05-01 12:30:05`
+    pub fn timestamp(&self) -> String {
+        self.timestamp.format(TIMESTAMP_FORMAT).to_string()
+    }
+
+    /// The SQL on one line, with runs of whitespace collapsed to a space
+    pub fn summary(&self) -> String {
+        self.sql.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// The queries run from the CLI and the TUI, kept as JSON lines in the user config directory.
+/// Only the newest entries are kept, so the file doesn't grow without bound.
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+    max_entries: usize,
+}
+
+impl History {
+    pub fn new(fs: &FileSystem) -> Result<Self> {
+        Ok(Self {
+            path: fs.get_scoped_path(CollectionScope::User, HISTORY_FILE_NAME)?,
+            max_entries: MAX_ENTRIES,
+        })
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let settings = UserSettings::from_env();
+        Self::new(&FileSystem::with_paths(settings.user_dir, settings.workspace_dir)?)
+    }
+
+    /// Adds `entry` at the end, trimming the file to the newest entries once it has grown too large
+    pub fn append(&self, entry: &HistoryEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        // One write per entry, so that processes appending at the same time don't interleave
+        let len = open_private(fs::OpenOptions::new().append(true).create(true), &self.path)
+            .and_then(|mut file| {
+                file.write_all(line.as_bytes())?;
+                file.metadata()
+            })
+            .with_context(|| format!("Failed to write {}", self.path.display()))?
+            .len();
+
+        if len > self.max_entries as u64 * TRIM_BYTES_PER_ENTRY {
+            self.trim()?;
+        }
+        Ok(())
+    }
+
+    /// Keeps only the newest `max_entries` entries. The file is replaced in one step, so
+    /// readers never see it half written; an entry appended meanwhile by another process may be lost.
+    fn trim(&self) -> Result<()> {
+        let entries = self.load()?;
+        let kept = &entries[entries.len().saturating_sub(self.max_entries)..];
+        let mut contents = String::new();
+        for entry in kept {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+
+        let temp = self.path.with_extension("jsonl.tmp");
+        open_private(fs::OpenOptions::new().write(true).create(true).truncate(true), &temp)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .and_then(|_| fs::rename(&temp, &self.path))
+            .with_context(|| format!("Failed to trim {}", self.path.display()))
+    }
+
+    /// Every entry, oldest first. Lines that can't be read, like one cut short, are skipped.
+    pub fn load(&self) -> Result<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = fs::File::open(&self.path).with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Opens `path` with `options`, creating it readable by the user only
+fn open_private(options: &mut fs::OpenOptions, path: &Path) -> std::io::Result<fs::File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // The SQL may contain data that shouldn't be readable by others
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// The indexes of the entries that fuzzy-match `query`, best match first and otherwise
+/// newest first. Each query is listed once, for its latest run.
+pub fn search(entries: &[HistoryEntry], query: &str) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    let mut matches: Vec<(u32, usize)> = entries
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, entry)| seen.insert(entry.sql.trim()))
+        .filter_map(|(idx, entry)| fuzzy_score(query, &entry.sql).map(|score| (score, idx)))
+        .collect();
+    // Stable, so equally good matches stay newest first
+    matches.sort_by_key(|&(score, _)| Reverse(score));
+    matches.into_iter().map(|(_, idx)| idx).collect()
+}
+
+/// How well `text` matches `query`, if it does: every character of the query has to appear
+/// in the text in order, ignoring case and the query's whitespace. Characters matched one
+/// after another, or at the start of a word, score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+    // Matched greedily from each place the query could start, keeping the best; once one
+    // start fails, every later one does too
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .map_while(|start| match_from(&query, &text, start))
+        .max()
+}
+
+fn match_from(query: &[char], text: &[char], start: usize) -> Option<u32> {
+    let mut score = 0;
+    let mut pos = start;
+    for (i, &wanted) in query.iter().enumerate() {
+        let found = pos + text[pos..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if i > 0 && found == pos {
+            score += 2;
+        }
+        if found == 0 || !(text[found - 1].is_alphanumeric() || text[found - 1] == '_') {
+            score += 3;
+        }
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, search, History, HistoryEntry, TRIM_BYTES_PER_ENTRY};
+    use crate::file::FileSystem;
+    use chrono::{Local, TimeZone};
+
+    fn entry(sql: &str, minute: u32) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Local.with_ymd_and_hms(2024, 5, 1, 12, minute, 0).unwrap(),
+            connection: Some("local".to_string()),
+            sql: sql.to_string(),
+            duration_ms: 5,
+            rows: Some(1),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("", "SELECT 1").is_some());
+        assert!(fuzzy_score("slct", "SELECT 1").is_some());
+        assert!(fuzzy_score("sel users", "select * from users").is_some());
+        assert!(fuzzy_score("users sel", "select * from users").is_none());
+        assert!(fuzzy_score("x", "SELECT 1").is_none());
+        // Consecutive characters and word starts count for more
+        assert!(fuzzy_score("user", "select * from users") > fuzzy_score("user", "select * from u_ser"));
+        assert!(fuzzy_score("ord", "select * from orders") > fuzzy_score("ord", "select word from t"));
+    }
+
+    #[test]
+    fn test_search() {
+        let entries = vec![
+            entry("SELECT * FROM users", 0),
+            entry("SELECT * FROM orders", 1),
+            entry("  SELECT * FROM users\n", 2),
+            entry("DELETE FROM sessions", 3),
+        ];
+        let sql = |found: Vec<usize>| found.iter().map(|&idx| entries[idx].sql.trim()).collect::<Vec<_>>();
+
+        // Newest first, each query once
+        assert_eq!(search(&entries, ""), vec![3, 2, 1]);
+        assert_eq!(sql(search(&entries, "ord")), vec!["SELECT * FROM orders"]);
+        assert_eq!(sql(search(&entries, "fro se")), vec!["DELETE FROM sessions", "SELECT * FROM users"]);
+    }
+
+    #[test]
+    fn test_append_trims_to_the_newest_entries() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let fs = FileSystem::with_paths(dir.path().join("user"), dir.path().join("workspace"))?;
+        let history = History::new(&fs)?.with_max_entries(3);
+        // Each line takes exactly the bytes allowed per entry, so three fit and a fourth triggers a trim
+        let padded = |minute: u32| {
+            let sql = format!("SELECT {:02}", minute);
+            let len = serde_json::to_string(&entry(&sql, minute)).unwrap().len() + 1;
+            entry(&format!("{}{}", sql, " ".repeat(TRIM_BYTES_PER_ENTRY as usize - len)), minute)
+        };
+        let sql = |entries: Vec<HistoryEntry>| entries.iter().map(|e| e.sql.trim_end().to_string()).collect::<Vec<_>>();
+
+        for minute in 0..3 {
+            history.append(&padded(minute))?;
+        }
+        assert_eq!(sql(history.load()?), ["SELECT 00", "SELECT 01", "SELECT 02"]);
+
+        for minute in 3..20 {
+            history.append(&padded(minute))?;
+        }
+        assert_eq!(sql(history.load()?), ["SELECT 17", "SELECT 18", "SELECT 19"]);
+        Ok(())
+    }
+}
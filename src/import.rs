@@ -0,0 +1,70 @@
+use anyhow::{bail, Result};
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    query::{connection_timeouts, get_connection_url, prompt_for_password},
+    sql::import::{import_file, ImportFormat, ImportOptions},
+};
+
+/// How often the count of rows loaded so far is updated
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Loads a CSV or JSON Lines file into a table with COPY (for CLI usage). Progress is
+/// shown on stderr while loading if it is a terminal, and a summary printed to stdout.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_import(url: Option<String>, conn: Option<String>, table: String, file: PathBuf, format: Option<String>, no_header: bool, delimiter: Option<char>, columns: Vec<String>, mappings: Vec<String>, create: bool) -> Result<()> {
+    // Either `--format tsv` or a `.tsv` file means tab-separated
+    let is_tsv = format.as_deref().is_some_and(|f| f.eq_ignore_ascii_case("tsv"))
+        || file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("tsv"));
+    let format = match format {
+        Some(format) => ImportFormat::new(&format)?,
+        None => ImportFormat::from_path(&file)?,
+    };
+    let delimiter = delimiter.unwrap_or(if is_tsv { '\t' } else { ',' });
+    if !delimiter.is_ascii() {
+        bail!("The delimiter must be a single ASCII character");
+    }
+
+    let mut options = ImportOptions::new(table, format)
+        .with_header(!no_header)
+        .with_delimiter(delimiter as u8)
+        .with_create(create);
+    if !columns.is_empty() {
+        options = options.with_columns(columns);
+    }
+    for mapping in &mappings {
+        let Some((source, column)) = mapping.split_once('=') else {
+            bail!("Invalid column mapping '{}'; expected SOURCE=COLUMN", mapping);
+        };
+        options = options.with_mapping(source, column);
+    }
+
+    let password = prompt_for_password(conn.as_deref())?;
+    let connect_timeout = connection_timeouts(conn.as_deref())?.connect_or_default();
+    let url = get_connection_url(url, conn, password)?;
+
+    let show_progress = io::stderr().is_terminal();
+    let started = Instant::now();
+    let mut last_update = started;
+    let summary = import_file(&url, &file, &options, connect_timeout, |rows| {
+        if show_progress && last_update.elapsed() >= PROGRESS_INTERVAL {
+            eprint!("\rLoaded {} rows...", rows);
+            let _ = io::stderr().flush();
+            last_update = Instant::now();
+        }
+    }).await;
+    if show_progress && last_update != started {
+        eprint!("\r\x1b[K");
+    }
+    let summary = summary?;
+
+    if let Some(sql) = &summary.created {
+        println!("{}", sql);
+    }
+    println!("Imported {} rows into {} in {}ms", summary.rows, options.table, started.elapsed().as_millis());
+    Ok(())
+}
@@ -1,6 +1,11 @@
 pub mod collection;
 pub mod config;
 pub mod file;
+pub mod fmt;
+pub mod history;
+pub mod import;
+pub mod listen;
+pub mod schema;
 pub mod settings;
 pub mod sql;
 pub mod tui;
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use std::io;
+
+use crate::{
+    query::{connection_timeouts, get_connection_url, prompt_for_password},
+    sql::{listen::{ListenEvent, Listener, NotificationPrinter}, result::Format},
+};
+
+/// Listens on `channels` and prints each notification to stdout until Ctrl+C (for CLI usage).
+/// Losing the connection is reported on stderr, and listening resumes once it is back.
+pub async fn run_listen(url: Option<String>, conn: Option<String>, channels: Vec<String>, format: Option<String>) -> Result<()> {
+    let output_format = match format {
+        Some(fmt) => Format::new(&fmt)?,
+        None => Format::default(),
+    };
+
+    let password = prompt_for_password(conn.as_deref())?;
+    let connect_timeout = connection_timeouts(conn.as_deref())?.connect_or_default();
+    let url = get_connection_url(url, conn, password)?;
+
+    let mut listener = Listener::connect(&url, channels, connect_timeout).await?;
+    eprintln!("Listening on {} (Ctrl+C to stop)", listener.channels().join(", "));
+    let mut printer = NotificationPrinter::new(io::stdout(), output_format, listener.channels());
+
+    loop {
+        let event = tokio::select! {
+            event = listener.next() => event,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        match event {
+            ListenEvent::Notification(notification) => printer.print(&notification)?,
+            ListenEvent::Disconnected(reason) => eprintln!("Connection lost ({}); reconnecting...", reason),
+            ListenEvent::Reconnected => eprintln!("Reconnected; listening on {}", listener.channels().join(", ")),
+        }
+    }
+}
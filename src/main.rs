@@ -1,8 +1,15 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use sqli::config::{run_config_set, run_config_list, ConfigManager};
 use sqli::file::FileSystem;
+use sqli::fmt::run_fmt;
+use sqli::history::run_history;
+use sqli::import::run_import;
+use sqli::listen::run_listen;
+use sqli::schema::run_schema;
 use sqli::settings::UserSettings;
+use sqli::sql::error::{ErrorClass, QueryError};
 
 use sqli::tui::run::run_tui;
 use sqli::query::run_query;
@@ -22,12 +29,84 @@ enum Commands {
     /// Query a SQL database, given a connection string and SQL query
     #[clap(alias = "q")]
     Query {
-        #[arg(short, long, help = "The database connection string to connect to")]
+        #[arg(short, long, help = "The database connection string to connect to (a postgresql:// or sqlite:// URL, or a path to an SQLite database file)")]
         url: Option<String>,
         #[arg(short, long, help = "The connection name from config")]
         conn: Option<String>,
         #[arg(short, long, help = "The SQL statement(s) to execute")]
         sql: String,
+        #[arg(short, long = "param", value_name = "VALUE", help = "A value for the next $n placeholder, or name=value for :name; \\N binds NULL (repeatable)")]
+        params: Vec<String>,
+        #[arg(short, long, help = "Output format (table, json, csv, raw, wide)", default_value = "table")]
+        format: Option<String>,
+        #[arg(long, visible_alias = "limit", help = "Print at most this many rows of each result set; queries stop once they have produced them")]
+        max_rows: Option<usize>,
+        #[arg(short, long, help = "Run DELETE/UPDATE without WHERE, DROP and TRUNCATE statements without refusing")]
+        yes: bool,
+        #[arg(long, value_name = "MODE", num_args = 0..=1, require_equals = true, default_missing_value = "plan", help = "Print the query plan as a tree instead of the results; --explain=analyze runs the query to show actual times")]
+        explain: Option<String>,
+
+        #[arg(long, value_name = "DURATION", help = "Cancel the query if it runs longer than this, e.g. 30s or 500ms (overrides the connection's statement_timeout)")]
+        timeout: Option<String>,
+    },
+    /// Print notifications sent with NOTIFY on the given channels, until Ctrl+C
+    Listen {
+        #[arg(short, long, help = "The database connection string to connect to (a postgresql:// URL)")]
+        url: Option<String>,
+        #[arg(short, long, help = "The connection name from config")]
+        conn: Option<String>,
+        #[arg(required = true, value_name = "CHANNEL", help = "The channel(s) to listen on")]
+        channels: Vec<String>,
+        #[arg(short, long, help = "Output format (table, json, csv, raw, wide)", default_value = "table")]
+        format: Option<String>,
+    },
+    /// Load a CSV or JSON Lines file into a table with COPY (PostgreSQL only)
+    Import {
+        #[arg(short, long, help = "The database connection string to connect to (a postgresql:// URL)")]
+        url: Option<String>,
+        #[arg(short, long, help = "The connection name from config")]
+        conn: Option<String>,
+        #[arg(short, long, help = "The table to load into, as written in SQL (ex. public.users)")]
+        table: String,
+        #[arg(value_name = "FILE", help = "The .csv, .tsv or .jsonl file to load")]
+        file: PathBuf,
+        #[arg(short, long, help = "Input format (csv, tsv, jsonl); picked by the file extension by default. JSON files must be JSON Lines, one object per line")]
+        format: Option<String>,
+        #[arg(long, help = "The CSV file has no header row; its columns are named with --columns, or loaded into the table's columns in order")]
+        no_header: bool,
+        #[arg(short, long, value_name = "CHAR", help = "The CSV field delimiter (default: a comma, or a tab for TSV files)")]
+        delimiter: Option<char>,
+        #[arg(long, value_name = "COLUMN", value_delimiter = ',', help = "The file's columns in order, instead of the CSV header; for JSON Lines, the keys to load")]
+        columns: Vec<String>,
+        #[arg(short, long = "map", value_name = "SOURCE=COLUMN", help = "Load a file column into a table column of another name, or skip it with SOURCE= (repeatable)")]
+        mappings: Vec<String>,
+        #[arg(long, help = "Create the table first, with column types inferred from the data")]
+        create: bool,
+    },
+    /// List the database's tables, functions and enums, or describe a table
+    Schema {
+        #[arg(short, long, help = "The database connection string to connect to (a postgresql:// or sqlite:// URL, or a path to an SQLite database file)")]
+        url: Option<String>,
+        #[arg(short, long, help = "The connection name from config")]
+        conn: Option<String>,
+        #[arg(value_name = "TABLE", help = "A table or view (ex. users or public.users) to show the columns, indexes and constraints of")]
+        table: Option<String>,
+        #[arg(short, long, help = "Output format (table, json, csv, raw, wide)", default_value = "table")]
+        format: Option<String>,
+    },
+    /// Reindent SQL files and uppercase their keywords
+    Fmt {
+        #[arg(value_name = "PATH", help = "The .sql files or directories to format (default: the workspace's collections, ./.sqli)")]
+        paths: Vec<PathBuf>,
+        #[arg(long, help = "Don't write anything; list the files that aren't formatted and fail if there are any")]
+        check: bool,
+    },
+    /// List the queries run so far, from the CLI and the TUI
+    History {
+        #[arg(short, long, help = "Only list queries run on this connection")]
+        conn: Option<String>,
+        #[arg(short, long, help = "Only list queries containing this text (case-insensitive)")]
+        grep: Option<String>,
         #[arg(short, long, help = "Output format (table, json, csv, raw, wide)", default_value = "table")]
         format: Option<String>,
     },
@@ -44,18 +123,22 @@ enum ConfigAction {
     Set {
         #[arg(long, help = "The name of the connection (ex. local-db)")]
         name: String,
-        #[arg(long, help = "The type of the connection (supported types: postgresql)")]
+        #[arg(long, help = "The type of the connection (supported types: postgresql, sqlite)")]
         conn: String,
-        #[arg(long, help = "The host of the connection (ex. localhost)")]
-        host: String,
-        #[arg(long, help = "The port to connect to (ex. 5432)")]
-        port: u16,
-        #[arg(long, help = "The database name (ex. my-db)")]
+        #[arg(long, help = "The host of the connection (ex. localhost); not used for sqlite")]
+        host: Option<String>,
+        #[arg(long, help = "The port to connect to (ex. 5432); not used for sqlite")]
+        port: Option<u16>,
+        #[arg(long, help = "The database name (ex. my-db), or the path to the database file for sqlite")]
         database: String,
-        #[arg(long, help = "The user to connect as (ex. postgres)")]
-        user: String,
+        #[arg(long, help = "The user to connect as (ex. postgres); not used for sqlite")]
+        user: Option<String>,
         #[arg(long, help = "[WARNING: This will save the password in plaintext in the config file]\nIf not provided, it will be prompted for.")]
         password: Option<String>,
+        #[arg(long, help = "Make transactions read-only by default, so the server rejects writes")]
+        read_only: bool,
+        #[arg(long, visible_alias = "production", help = "Mark the connection as dangerous (e.g. production); it is shown in red in the TUI")]
+        danger: bool,
     },
     /// List all configured database connections
     List,
@@ -74,13 +157,35 @@ async fn main() -> Result<()> {
         Commands::Tui => {
             run_tui(None)?;
         },
-        Commands::Query { url, conn, sql, format } => {
-            run_query( url, conn, sql, format).await?;
+        Commands::Query { url, conn, sql, params, format, max_rows, yes, explain, timeout } => {
+            if let Err(e) = run_query(url, conn, sql, params, format, max_rows, yes, explain, timeout).await {
+                exit_with_query_error(e)?;
+            }
+        },
+        Commands::Import { url, conn, table, file, format, no_header, delimiter, columns, mappings, create } => {
+            if let Err(e) = run_import(url, conn, table, file, format, no_header, delimiter, columns, mappings, create).await {
+                exit_with_query_error(e)?;
+            }
+        },
+        Commands::Schema { url, conn, table, format } => {
+            if let Err(e) = run_schema(url, conn, table, format).await {
+                exit_with_query_error(e)?;
+            }
+        },
+        Commands::Fmt { paths, check } => {
+            let paths = if paths.is_empty() { vec![settings.workspace_dir.clone()] } else { paths };
+            run_fmt(paths, check)?;
+        },
+        Commands::History { conn, grep, format } => {
+            run_history(conn, grep, format)?;
+        },
+        Commands::Listen { url, conn, channels, format } => {
+            run_listen(url, conn, channels, format).await?;
         },
         Commands::Config { action } => {
             match action {
-                ConfigAction::Set { name, conn, host, port, database, user, password } => {
-                    run_config_set(&mut config_manager, name, conn, host, port, database, user, password).await?;
+                ConfigAction::Set { name, conn, host, port, database, user, password, read_only, danger } => {
+                    run_config_set(&mut config_manager, name, conn, host, port, database, user, password, read_only, danger).await?;
                 },
                 ConfigAction::List => {
                     run_config_list(&mut config_manager).await?;
@@ -89,4 +194,22 @@ async fn main() -> Result<()> {
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Exits with the code for the error's class, printing errors from the server like psql does.
+/// Errors that don't come from the database are returned as usual.
+fn exit_with_query_error(error: anyhow::Error) -> Result<()> {
+    let class = ErrorClass::of(&error);
+    match error.downcast_ref::<QueryError>() {
+        Some(query_error) => {
+            // Says which statement of a script failed
+            if error.chain().count() > 1 {
+                eprintln!("{}", error);
+            }
+            eprintln!("{}", query_error.report());
+        }
+        None if class != ErrorClass::Other => eprintln!("Error: {:#}", error),
+        None => return Err(error),
+    }
+    std::process::exit(class.exit_code());
+}
@@ -1,57 +1,208 @@
-use anyhow::{anyhow, Result};
-use std::path::Path;
+use anyhow::{anyhow, bail, Result};
+use chrono::Local;
+use std::{io::{self, BufRead, IsTerminal, Write}, path::Path, sync::Arc, time::Instant};
 
 use crate::{
-    config::{ConfigManager, Connection}, file::FileSystem, settings::UserSettings, sql::{factory::create_executor, interface::Executor, result::{format_output, Format, QueryResult}}
+    config::{ConfigManager, Connection}, file::FileSystem, history::{History, HistoryEntry}, settings::UserSettings, sql::{backend::{Backend, BackendRegistry, Target}, cancel::CancelHandle, explain::{explain_sql, ExplainMode, ExplainPlan}, interface::ExecutionOptions, params::QueryParams, result::{format_stream, Format, QueryResult}, schema::DatabaseSchema, statement::{destructive_statements, split_statements}, timeout::{parse_duration, Timeouts}}
 };
 
-/// Wrapper function that executes a query and prints results to stdout (for CLI usage)
-pub async fn run_query(url: Option<String>, conn: Option<String>, sql: String, format: Option<String>) -> Result<()> {
-    let password: Option<String> = if let Some(conn_name) = &conn {
-        if let Some(conn) = get_connection(conn_name)? {
-            if conn.requires_password() {
-                Some(rpassword::prompt_password("Enter database password: ")?)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+/// Wrapper function that executes a query and prints results to stdout (for CLI usage).
+/// Destructive statements are refused unless `yes` is set. With `explain`, the
+/// query plan is printed instead of the results. `timeout` overrides the
+/// connection's statement timeout. Each run is added to the query history.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_query(url: Option<String>, conn: Option<String>, sql: String, params: Vec<String>, format: Option<String>, max_rows: Option<usize>, yes: bool, explain: Option<String>, timeout: Option<String>) -> Result<()> {
+    let explain = explain.as_deref().map(ExplainMode::new).transpose()?;
+    let timeouts = Timeouts::new().with_statement(timeout.as_deref().map(parse_duration).transpose()?);
+
+    let password = prompt_for_password(conn.as_deref())?;
+
+    // Report connection problems before asking for parameter values
+    get_connection_url(url.clone(), conn.clone(), password.clone())?;
+    let sql = read_sql(sql)?;
+    // A plain EXPLAIN doesn't run the statement
+    if !yes && explain != Some(ExplainMode::Plan) {
+        refuse_destructive(&sql)?;
+    }
+    let mut params = QueryParams::from_args(&params);
+    prompt_for_params(&sql, &mut params)?;
 
     let output_format = match format {
         Some(fmt) => Format::new(&fmt)?,
         None => Format::default(),
     };
 
-    let result = execute_query(sql, url, conn, password).await?;
-    
-    if !result.columns.is_empty() {
-        format_output(&result, output_format)?;
+    let backends = BackendRegistry::with_defaults();
+    let started_at = Local::now();
+    let started = Instant::now();
+    if let Some(mode) = explain {
+        let results = execute_query(explain_sql(&sql, mode)?, url, conn.clone(), password, &backends, ExecutionOptions::new().with_params(params).with_timeouts(timeouts)).await;
+        backends.close_all().await;
+        record_history(HistoryEntry::new(sql, conn, started_at, started.elapsed(), &results));
+        print!("{}", ExplainPlan::from_results(&results?)?);
+        return Ok(());
     }
-    
-    Ok(())
+
+    let cancel = CancelHandle::new();
+    let script = split_statements(&sql).len() > 1;
+    let prepared = prepare_execution(sql.clone(), url, conn.clone(), password, &backends, ExecutionOptions::new().with_params(params).with_cancel(cancel.clone()).with_timeouts(timeouts).with_max_rows(max_rows));
+    let backend = prepared.as_ref().ok().map(|(backend, ..)| backend.clone());
+    let printed = async {
+        let (backend, target, sql, options) = prepared?;
+        let stream = backend.execute_stream(&target, sql, options)?;
+        format_stream(stream, output_format, max_rows, script).await
+    };
+    tokio::pin!(printed);
+
+    // On Ctrl+C, ask the server to stop the running statement and wait for it to wind down
+    let printed = tokio::select! {
+        printed = &mut printed => printed,
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!("Cancelling query...");
+            if let Some(backend) = &backend {
+                backend.cancel(&cancel).await?;
+            }
+            printed.await
+        }
+    };
+    backends.close_all().await;
+    record_history(HistoryEntry::new(sql.clone(), conn.clone(), started_at, started.elapsed(), &printed));
+
+    printed.map(|_| ())
 }
 
-// Core function to execute a SQL query and return the results
+/// Adds a run to the query history. Failing to doesn't fail the query, so it is only reported.
+fn record_history(entry: HistoryEntry) {
+    if let Err(e) = History::from_env().and_then(|history| history.append(&entry)) {
+        eprintln!("Warning: could not save the query to the history: {:#}", e);
+    }
+}
+
+// Core function to execute a SQL query and return the results.
+// It runs on the backend in `backends` that handles the connection's URL, which may keep
+// connections between runs, keyed by connection name (or by URL for ad-hoc queries).
+// `options` carry the parameter values, an optional `CancelHandle` to stop the query
+// while it runs, an optional session to run it in, and timeouts that take precedence
+// over the connection's own.
 pub async fn execute_query(
     sql: String,
     url: Option<String>,
     connection: Option<String>,
     password: Option<String>,
-) -> Result<QueryResult> {
-    let connection_url = get_connection_url(url, connection, password)?;
-    let sql_content = if Path::new(&sql).exists() && sql.ends_with(".sql") {
+    backends: &BackendRegistry,
+    options: ExecutionOptions,
+) -> Result<Vec<QueryResult>> {
+    let (backend, target, sql, options) = prepare_execution(sql, url, connection, password, backends, options)?;
+    backend.execute(&target, sql, options).await
+}
+
+/// Opens a connection to the database, to check that it can be reached
+pub async fn check_connection(
+    url: Option<String>,
+    connection: Option<String>,
+    password: Option<String>,
+    backends: &BackendRegistry,
+) -> Result<()> {
+    let timeouts = connection_timeouts(connection.as_deref())?;
+    let target = Target::new(pool_key(url.as_deref(), connection.as_deref()), get_connection_url(url, connection, password)?);
+    backends.backend_for(&target.url)?.connect(&target, timeouts).await
+}
+
+/// The backend that runs queries on the connection (or URL), e.g. to cancel one through it
+pub fn connection_backend(url: Option<String>, connection: Option<String>, backends: &BackendRegistry) -> Result<Arc<dyn Backend>> {
+    backends.backend_for(&get_connection_url(url, connection, None)?)
+}
+
+/// The schema of the connection's database. It is cached in `backends` per connection,
+/// and only introspected again if `refresh` is set.
+pub async fn load_schema(
+    url: Option<String>,
+    connection: Option<String>,
+    password: Option<String>,
+    backends: &BackendRegistry,
+    refresh: bool,
+) -> Result<Arc<DatabaseSchema>> {
+    let timeouts = connection_timeouts(connection.as_deref())?;
+    let target = Target::new(pool_key(url.as_deref(), connection.as_deref()), get_connection_url(url, connection, password)?);
+    if refresh {
+        backends.refresh_schema(&target, timeouts).await
+    } else {
+        backends.schema(&target, timeouts).await
+    }
+}
+
+/// Picks the backend for the connection and resolves everything it needs to run `sql`
+fn prepare_execution(
+    sql: String,
+    url: Option<String>,
+    connection: Option<String>,
+    password: Option<String>,
+    backends: &BackendRegistry,
+    mut options: ExecutionOptions,
+) -> Result<(Arc<dyn Backend>, Target, String, ExecutionOptions)> {
+    let key = pool_key(url.as_deref(), connection.as_deref());
+    options.timeouts = options.timeouts.or(connection_timeouts(connection.as_deref())?);
+    let target = Target::new(key, get_connection_url(url, connection, password)?);
+    let backend = backends.backend_for(&target.url)?;
+    Ok((backend, target, read_sql(sql)?, options))
+}
+
+/// Asks for the password of a named connection that needs one
+pub(crate) fn prompt_for_password(connection: Option<&str>) -> Result<Option<String>> {
+    match connection.map(get_connection).transpose()?.flatten() {
+        Some(conn) if conn.requires_password() => Ok(Some(rpassword::prompt_password("Enter database password: ")?)),
+        _ => Ok(None),
+    }
+}
+
+/// `sql` may be a path to a `.sql` file, in which case its contents are used
+fn read_sql(sql: String) -> Result<String> {
+    if Path::new(&sql).exists() && sql.ends_with(".sql") {
         let fs = FileSystem::new()?;
-        fs.read_file(&sql)?
+        fs.read_file(&sql)
     } else {
-        sql
-    };
+        Ok(sql)
+    }
+}
 
-    let executor = create_executor(connection_url, sql_content);
-    executor.execute().await
+fn refuse_destructive(sql: &str) -> Result<()> {
+    let destructive = destructive_statements(sql);
+    if destructive.is_empty() {
+        return Ok(());
+    }
+    let listed: Vec<String> = destructive.iter().map(|d| format!("  {}", d)).collect();
+    bail!("Refusing to run destructive statement(s) without --yes:\n{}", listed.join("\n"));
+}
+
+/// Asks for a value for every placeholder not bound with `--param`. Without a
+/// terminal to prompt on, unbound placeholders are an error.
+fn prompt_for_params(sql: &str, params: &mut QueryParams) -> Result<()> {
+    let missing = params.missing(sql);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        let names: Vec<String> = missing.iter().map(|p| p.to_string()).collect();
+        bail!("No value given for parameter(s) {}; pass them with --param", names.join(", "));
+    }
+
+    let mut stdin = io::stdin().lock();
+    for placeholder in missing {
+        eprint!("Enter value for {}: ", placeholder);
+        io::stderr().flush()?;
+        let mut value = String::new();
+        stdin.read_line(&mut value)?;
+        params.set(placeholder, value.trim_end_matches(['\r', '\n']).to_string());
+    }
+    Ok(())
+}
+
+pub fn pool_key(url: Option<&str>, connection: Option<&str>) -> String {
+    match (connection, url) {
+        (Some(conn_name), _) => conn_name.to_lowercase(),
+        (None, Some(url)) => url.to_string(),
+        (None, None) => String::new(),
+    }
 }
 
 pub fn get_connection_url(url: Option<String>, connection: Option<String>, password: Option<String>) -> Result<String> {
@@ -71,6 +222,14 @@ pub fn get_connection_url(url: Option<String>, connection: Option<String>, passw
     }
 }
 
+/// The timeouts configured for a named connection; ad-hoc URLs have none
+pub(crate) fn connection_timeouts(connection: Option<&str>) -> Result<Timeouts> {
+    match connection.map(get_connection).transpose()?.flatten() {
+        Some(conn) => conn.timeouts(),
+        None => Ok(Timeouts::new()),
+    }
+}
+
 pub fn get_connection(name: &str) -> Result<Option<Connection>> {
     let settings = UserSettings::from_env();
     let fs = FileSystem::with_paths(settings.user_dir, settings.workspace_dir)?;
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+use crate::{
+    query::{load_schema, prompt_for_password},
+    sql::{
+        backend::BackendRegistry,
+        result::{format_output, Format, QueryResult},
+        schema::{DatabaseSchema, Table},
+        value::Value,
+    },
+};
+
+/// Prints the database's tables, functions and enums, or with `table`, that table's
+/// columns, indexes and constraints (for CLI usage). JSON output is the schema model itself.
+pub async fn run_schema(url: Option<String>, conn: Option<String>, table: Option<String>, format: Option<String>) -> Result<()> {
+    let output_format = match format {
+        Some(fmt) => Format::new(&fmt)?,
+        None => Format::default(),
+    };
+
+    let password = prompt_for_password(conn.as_deref())?;
+    let backends = BackendRegistry::with_defaults();
+    let schema = load_schema(url, conn, password, &backends, false).await;
+    backends.close_all().await;
+    let schema = schema?;
+
+    match table {
+        Some(name) => {
+            let table = schema.find_table(&name).ok_or_else(|| anyhow!("Table '{}' not found", name))?;
+            match output_format {
+                Format::Json => println!("{}", serde_json::to_string_pretty(table)?),
+                format => format_output(&table_results(table), format)?,
+            }
+        }
+        None => match output_format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&*schema)?),
+            format => format_output(&schema_results(&schema), format)?,
+        },
+    }
+    Ok(())
+}
+
+fn result(columns: &[&str], rows: Vec<Vec<Value>>) -> QueryResult {
+    QueryResult::new(columns.iter().map(|c| c.to_string()).collect(), rows, Duration::ZERO)
+}
+
+/// A result listing the tables and views, followed by ones for functions and enums if there are any
+fn schema_results(schema: &DatabaseSchema) -> Vec<QueryResult> {
+    let tables = schema
+        .tables()
+        .map(|t| vec![t.schema.clone().into(), t.name.clone().into(), t.kind.to_string().into()])
+        .collect();
+    let mut results = vec![result(&["schema", "name", "type"], tables)];
+
+    let functions: Vec<Vec<Value>> = schema
+        .functions()
+        .map(|f| vec![
+            f.schema.clone().into(),
+            f.name.clone().into(),
+            f.kind.to_string().into(),
+            f.arguments.clone().into(),
+            f.result.clone().map_or(Value::Null, Value::Text),
+        ])
+        .collect();
+    if !functions.is_empty() {
+        results.push(result(&["schema", "function", "type", "arguments", "result"], functions));
+    }
+
+    let enums: Vec<Vec<Value>> = schema
+        .enums()
+        .map(|e| vec![e.schema.clone().into(), e.name.clone().into(), e.values.join(", ").into()])
+        .collect();
+    if !enums.is_empty() {
+        results.push(result(&["schema", "enum", "values"], enums));
+    }
+    results
+}
+
+/// A result with the table's columns, followed by ones for its indexes and constraints if it has any
+fn table_results(table: &Table) -> Vec<QueryResult> {
+    let columns = table
+        .columns
+        .iter()
+        .map(|c| vec![
+            c.name.clone().into(),
+            c.data_type.clone().into(),
+            Value::Bool(c.nullable),
+            c.default.clone().map_or(Value::Null, Value::Text),
+        ])
+        .collect();
+    let mut results = vec![result(&["column", "type", "nullable", "default"], columns)];
+
+    let indexes: Vec<Vec<Value>> = table
+        .indexes
+        .iter()
+        .map(|i| vec![i.name.clone().into(), i.columns.join(", ").into(), Value::Bool(i.unique), Value::Bool(i.primary)])
+        .collect();
+    if !indexes.is_empty() {
+        results.push(result(&["index", "columns", "unique", "primary"], indexes));
+    }
+
+    let constraints: Vec<Vec<Value>> = table
+        .constraints
+        .iter()
+        .map(|c| vec![c.name.clone().into(), c.kind.to_string().into(), c.definition.clone().into()])
+        .collect();
+    if !constraints.is_empty() {
+        results.push(result(&["constraint", "type", "definition"], constraints));
+    }
+    results
+}
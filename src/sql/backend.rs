@@ -0,0 +1,246 @@
+use anyhow::{bail, Result};
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+use super::{cancel::CancelHandle, interface::ExecutionOptions, postgresql::PostgresBackend, result::{QueryResult, QueryResultStream}, schema::{DatabaseSchema, SchemaCache}, sqlite::SqliteBackend, timeout::Timeouts};
+
+/// Where an execution runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// Connections kept between executions are shared by targets with the same key,
+    /// e.g. the connection name (or the URL, for ad-hoc queries)
+    pub key: String,
+    pub url: String,
+}
+
+impl Target {
+    pub fn new(key: impl Into<String>, url: impl Into<String>) -> Self {
+        Self { key: key.into(), url: url.into() }
+    }
+}
+
+/// A database the CLI and TUI can run queries against. Backends are registered
+/// with a `BackendRegistry` and picked by the URL they're given.
+///
+/// Cancelling goes through `cancel`, with the `CancelHandle` that was in the
+/// execution's `ExecutionOptions`: the backend registers an `Interrupt` with it,
+/// or checks it while running.
+pub trait Backend: Send + Sync {
+    /// Name shown in errors, e.g. `postgresql`
+    fn name(&self) -> &'static str;
+
+    /// Whether this backend serves `url`, usually judged by its scheme
+    fn handles(&self, url: &str) -> bool;
+
+    /// Opens a connection to the target, to check that it can be reached, e.g. when switching to it
+    fn connect<'a>(&'a self, target: &'a Target, timeouts: Timeouts) -> BoxFuture<'a, Result<()>>;
+
+    /// Runs every statement in `sql`, yielding rows as they arrive
+    fn execute_stream(&self, target: &Target, sql: String, options: ExecutionOptions) -> Result<QueryResultStream>;
+
+    /// Runs every statement in `sql`, returning one result per statement
+    fn execute<'a>(&'a self, target: &'a Target, sql: String, options: ExecutionOptions) -> BoxFuture<'a, Result<Vec<QueryResult>>> {
+        Box::pin(async move { self.execute_stream(target, sql, options)?.collect().await })
+    }
+
+    /// Stops the execution that was given `cancel`. Returns whether a running statement was interrupted.
+    fn cancel<'a>(&'a self, cancel: &'a CancelHandle) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(cancel.cancel())
+    }
+
+    /// Introspects the database's schemas, tables, columns, keys, indexes, functions and enums.
+    /// Not cached; `BackendRegistry::schema` keeps the result per target.
+    fn schema<'a>(&'a self, target: &'a Target, timeouts: Timeouts) -> BoxFuture<'a, Result<DatabaseSchema>>;
+
+    /// Drops any connections kept for `key`
+    fn close(&self, _key: &str) {}
+
+    /// Closes every connection kept between executions
+    fn close_all(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+}
+
+/// The backends available to run queries on, and the schemas introspected through them.
+/// Cheap to clone; clones share the backends and the schema cache.
+#[derive(Clone, Default)]
+pub struct BackendRegistry {
+    backends: Vec<Arc<dyn Backend>>,
+    schemas: SchemaCache,
+}
+
+impl BackendRegistry {
+    /// A registry without any backends
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Postgres and SQLite
+    pub fn with_defaults() -> Self {
+        Self::new()
+            .with_backend(PostgresBackend::default())
+            .with_backend(SqliteBackend)
+    }
+
+    /// Later backends take precedence over earlier ones that handle the same URLs
+    pub fn with_backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backends.push(Arc::new(backend));
+        self
+    }
+
+    pub fn backend_for(&self, url: &str) -> Result<Arc<dyn Backend>> {
+        if let Some(backend) = self.backends.iter().rev().find(|b| b.handles(url)) {
+            return Ok(backend.clone());
+        }
+        let supported: Vec<&str> = self.backends.iter().map(|b| b.name()).collect();
+        match url.split_once("://") {
+            Some((scheme, _)) => bail!("Unsupported database URL scheme '{}'. Supported schemes: {}", scheme, supported.join(", ")),
+            None => bail!("Unsupported database URL '{}'. Expected a URL for one of: {}", url, supported.join(", ")),
+        }
+    }
+
+    /// The schema of the target's database, introspected on first use and then
+    /// kept for the target's key until `refresh_schema` or `close`
+    pub async fn schema(&self, target: &Target, timeouts: Timeouts) -> Result<Arc<DatabaseSchema>> {
+        match self.schemas.get(&target.key) {
+            Some(schema) => Ok(schema),
+            None => self.refresh_schema(target, timeouts).await,
+        }
+    }
+
+    /// Introspects the target's database again, e.g. after its tables changed
+    pub async fn refresh_schema(&self, target: &Target, timeouts: Timeouts) -> Result<Arc<DatabaseSchema>> {
+        let schema = self.backend_for(&target.url)?.schema(target, timeouts).await?;
+        Ok(self.schemas.insert(&target.key, schema))
+    }
+
+    pub fn close(&self, key: &str) {
+        self.schemas.invalidate(key);
+        for backend in &self.backends {
+            backend.close(key);
+        }
+    }
+
+    pub async fn close_all(&self) {
+        self.schemas.clear();
+        for backend in &self.backends {
+            backend.close_all().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use futures::future::BoxFuture;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    use super::{Backend, BackendRegistry, Target};
+    use crate::sql::cancel::CancelHandle;
+    use crate::sql::interface::ExecutionOptions;
+    use crate::sql::result::{QueryResult, QueryResultStream, StreamEvent};
+    use crate::sql::schema::{DatabaseSchema, Schema};
+    use crate::sql::timeout::Timeouts;
+
+    /// Answers every query with one row holding the SQL it was given
+    #[derive(Default)]
+    struct FakeBackend {
+        executed: Arc<Mutex<Vec<(Target, String)>>>,
+        introspections: Arc<Mutex<usize>>,
+        connected: Arc<Mutex<Vec<Target>>>,
+        cancels: Arc<Mutex<usize>>,
+    }
+
+    impl Backend for FakeBackend {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn handles(&self, url: &str) -> bool {
+            url.starts_with("fake://") || url.starts_with("postgresql://fake")
+        }
+
+        fn connect<'a>(&'a self, target: &'a Target, _timeouts: Timeouts) -> BoxFuture<'a, Result<()>> {
+            self.connected.lock().unwrap().push(target.clone());
+            Box::pin(async { Ok(()) })
+        }
+
+        fn execute_stream(&self, target: &Target, sql: String, _options: ExecutionOptions) -> Result<QueryResultStream> {
+            self.executed.lock().unwrap().push((target.clone(), sql.clone()));
+            let (sender, receiver) = mpsc::channel(4);
+            tokio::spawn(async move {
+                let _ = sender.send(Ok(StreamEvent::Columns { names: vec!["sql".to_string()], types: vec!["TEXT".to_string()] })).await;
+                let _ = sender.send(Ok(StreamEvent::Row(vec![sql.into()]))).await;
+                let _ = sender.send(Ok(StreamEvent::Done(QueryResult::empty()))).await;
+            });
+            Ok(QueryResultStream::new(receiver))
+        }
+
+        fn cancel<'a>(&'a self, _cancel: &'a CancelHandle) -> BoxFuture<'a, Result<bool>> {
+            *self.cancels.lock().unwrap() += 1;
+            Box::pin(async { Ok(false) })
+        }
+
+        fn schema<'a>(&'a self, _target: &'a Target, _timeouts: Timeouts) -> BoxFuture<'a, Result<DatabaseSchema>> {
+            *self.introspections.lock().unwrap() += 1;
+            Box::pin(async { Ok(DatabaseSchema { schemas: vec![Schema { name: "main".to_string(), ..Schema::default() }] }) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_registry_dispatch() -> Result<()> {
+        let fake = FakeBackend::default();
+        let executed = fake.executed.clone();
+        let connected = fake.connected.clone();
+        let cancels = fake.cancels.clone();
+        let registry = BackendRegistry::with_defaults().with_backend(fake);
+
+        assert_eq!(registry.backend_for("fake://db")?.name(), "fake");
+        // A later registration takes over URLs an earlier backend also handles
+        assert_eq!(registry.backend_for("postgresql://fake/db")?.name(), "fake");
+        assert_eq!(registry.backend_for("postgresql://localhost/db")?.name(), "postgresql");
+        assert_eq!(registry.backend_for("sqlite://fixtures.db")?.name(), "sqlite");
+        assert_eq!(
+            registry.backend_for("mysql://localhost/db").err().map(|e| e.to_string()),
+            Some("Unsupported database URL scheme 'mysql'. Supported schemes: postgresql, sqlite, fake".to_string())
+        );
+
+        let target = Target::new("local", "fake://db");
+        let backend = registry.backend_for(&target.url)?;
+        let results = backend.execute(&target, "SELECT 1".to_string(), ExecutionOptions::new()).await?;
+        assert_eq!(results[0].rows, vec![vec!["SELECT 1".into()]]);
+        assert_eq!(executed.lock().unwrap().as_slice(), &[(target.clone(), "SELECT 1".to_string())]);
+        backend.connect(&target, Timeouts::new()).await?;
+        assert_eq!(*connected.lock().unwrap(), vec![target.clone()]);
+        assert!(!backend.cancel(&CancelHandle::new()).await?);
+        assert_eq!(*cancels.lock().unwrap(), 1);
+
+        assert!(BackendRegistry::new().backend_for("fake://db").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_backend_registry_caches_schemas() -> Result<()> {
+        let fake = FakeBackend::default();
+        let introspections = fake.introspections.clone();
+        let registry = BackendRegistry::new().with_backend(fake);
+        let target = Target::new("local", "fake://db");
+
+        let schema = registry.schema(&target, Timeouts::new()).await?;
+        assert_eq!(schema.schemas[0].name, "main");
+        registry.clone().schema(&target, Timeouts::new()).await?;
+        assert_eq!(*introspections.lock().unwrap(), 1);
+
+        registry.refresh_schema(&target, Timeouts::new()).await?;
+        assert_eq!(*introspections.lock().unwrap(), 2);
+        // Another key, e.g. another connection to the same database, has a schema of its own
+        registry.schema(&Target::new("other", "fake://db"), Timeouts::new()).await?;
+        assert_eq!(*introspections.lock().unwrap(), 3);
+
+        registry.close("local");
+        registry.schema(&target, Timeouts::new()).await?;
+        assert_eq!(*introspections.lock().unwrap(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Returned by an execution that was stopped through its `CancelHandle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryCancelled;
+
+impl fmt::Display for QueryCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Query cancelled")
+    }
+}
+
+impl std::error::Error for QueryCancelled {}
+
+/// Stops whatever statement an execution is running, e.g. by asking the server to
+/// abort it. Executors register one with their `CancelHandle` while they run.
+pub trait Interrupt: Send + Sync {
+    fn interrupt(&self) -> BoxFuture<'_, Result<()>>;
+}
+
+#[derive(Default)]
+struct CancelState {
+    running: Option<Arc<dyn Interrupt>>,
+    cancelled: bool,
+}
+
+/// Shared between an executor and whoever may want to stop it. The executor
+/// registers an `Interrupt` for the statements it runs, or checks `is_cancelled`
+/// while running them; `cancel` does both. Cheap to clone; clones share state.
+#[derive(Clone, Default)]
+pub struct CancelHandle {
+    state: Arc<Mutex<CancelState>>,
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    pub(crate) fn register(&self, interrupt: impl Interrupt + 'static) {
+        self.state.lock().unwrap().running = Some(Arc::new(interrupt));
+    }
+
+    pub(crate) fn finish(&self) {
+        self.state.lock().unwrap().running = None;
+    }
+
+    /// Marks the execution as cancelled and interrupts its running statement,
+    /// if it registered a way to. Returns whether it was interrupted.
+    pub async fn cancel(&self) -> Result<bool> {
+        let running = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            state.running.clone()
+        };
+
+        match running {
+            Some(interrupt) => {
+                interrupt.interrupt().await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
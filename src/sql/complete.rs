@@ -0,0 +1,322 @@
+use super::{
+    lexer::{is_keyword, tokenize, Token, TokenKind, CLAUSE_KEYWORDS},
+    schema::{DatabaseSchema, Table},
+};
+
+/// Keywords after which a table name is expected
+const TABLE_KEYWORDS: &[&str] = &["FROM", "JOIN", "UPDATE", "INTO", "TABLE"];
+
+/// Most suggestions returned; the rest are left out
+const MAX_COMPLETIONS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Schema,
+    Table,
+    Column,
+    Function,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// What replaces the word being typed
+    pub text: String,
+    pub kind: CompletionKind,
+    /// e.g. a column's type, or which table it belongs to
+    pub detail: Option<String>,
+}
+
+impl Completion {
+    fn new(text: impl Into<String>, kind: CompletionKind, detail: Option<String>) -> Self {
+        Self { text: text.into(), kind, detail }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completions {
+    /// Byte offset where the word being completed starts; the text from there to the cursor is replaced
+    pub start: usize,
+    pub items: Vec<Completion>,
+}
+
+/// A table named in the statement, and the alias it was given there
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TableReference {
+    name: String,
+    alias: Option<String>,
+}
+
+/// Suggests what could go at byte `offset` of `sql`: keywords, schemas and tables of `schema`,
+/// columns of the tables the statement refers to (by name or alias) and functions. After a
+/// `.`, only the columns of that table or alias, or the tables of that schema, are suggested.
+/// There are none inside strings and comments, or when there is no word or `.` to complete.
+pub fn complete(sql: &str, offset: usize, schema: Option<&DatabaseSchema>) -> Completions {
+    let start = sql[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let prefix = &sql[start..offset];
+    let mut completions = Completions { start, items: Vec::new() };
+
+    let tokens = tokenize(sql);
+    if tokens.iter().any(|t| is_literal(t) && t.start < start && (start < t.end() || t.end() == sql.len())) {
+        return completions;
+    }
+    let statement = statement_tokens(&tokens, start);
+    let references = table_references(&statement);
+    let before: Vec<&Token> = statement.iter().filter(|t| t.end() <= start).collect();
+    let qualifier = match before.as_slice() {
+        [.., name, dot] if dot.text == "." && dot.end() == start => Some(identifier(name)),
+        _ => None,
+    };
+    if prefix.is_empty() && qualifier.is_none() {
+        return completions;
+    }
+
+    let mut items = Vec::new();
+    match (qualifier, schema) {
+        (Some(qualifier), Some(schema)) => {
+            let table = references
+                .iter()
+                .find(|r| r.alias.as_deref() == Some(qualifier.as_str()))
+                .and_then(|r| schema.find_table(&r.name))
+                .or_else(|| schema.find_table(&qualifier));
+            match table {
+                Some(table) => items.extend(columns(table, false)),
+                None => {
+                    for s in schema.schemas.iter().filter(|s| s.name == qualifier) {
+                        items.extend(s.tables.iter().map(|t| Completion::new(&t.name, CompletionKind::Table, Some(t.kind.to_string()))));
+                        items.extend(s.functions.iter().map(|f| Completion::new(&f.name, CompletionKind::Function, f.result.clone())));
+                    }
+                }
+            }
+        }
+        (Some(_), None) => {}
+        (None, schema) => {
+            let after_table_keyword = before
+                .last()
+                .is_some_and(|t| TABLE_KEYWORDS.iter().any(|k| t.is_word(k)));
+            let tables = schema.map(tables).unwrap_or_default();
+            let schemas: Vec<Completion> = schema
+                .map(|s| s.schemas.iter().map(|s| Completion::new(&s.name, CompletionKind::Schema, Some("schema".to_string()))).collect())
+                .unwrap_or_default();
+            if after_table_keyword {
+                items.extend(tables);
+                items.extend(schemas);
+            } else {
+                if let Some(schema) = schema {
+                    let referenced = references.iter().filter_map(|r| schema.find_table(&r.name));
+                    for table in referenced {
+                        items.extend(columns(table, true));
+                    }
+                    items.extend(schema.functions().map(|f| Completion::new(&f.name, CompletionKind::Function, f.result.clone())));
+                }
+                items.extend(keywords(prefix));
+                items.extend(tables);
+                items.extend(schemas);
+            }
+        }
+    }
+
+    for item in items {
+        let matches = item.text.len() >= prefix.len()
+            && item.text.get(..prefix.len()).is_some_and(|p| p.eq_ignore_ascii_case(prefix));
+        // Nothing to suggest if the word is already complete
+        let complete = item.text == prefix;
+        if matches && !complete && !completions.items.iter().any(|c| c.text == item.text) {
+            completions.items.push(item);
+        }
+        if completions.items.len() == MAX_COMPLETIONS {
+            break;
+        }
+    }
+    completions
+}
+
+fn is_literal(token: &Token) -> bool {
+    matches!(token.kind, TokenKind::String | TokenKind::DollarString | TokenKind::QuotedIdentifier | TokenKind::LineComment | TokenKind::BlockComment)
+}
+
+/// The significant tokens of the statement around `offset`
+fn statement_tokens<'a>(tokens: &[Token<'a>], offset: usize) -> Vec<Token<'a>> {
+    let mut statement = Vec::new();
+    for token in tokens.iter().filter(|t| !t.is_trivia()) {
+        if token.text == ";" && token.kind == TokenKind::Punctuation {
+            if token.start >= offset {
+                break;
+            }
+            statement.clear();
+        } else {
+            statement.push(*token);
+        }
+    }
+    statement
+}
+
+/// An identifier as the database stores it: unquoted names fold to lowercase
+fn identifier(token: &Token) -> String {
+    match token.kind {
+        TokenKind::QuotedIdentifier => token.text.trim_matches('"').replace("\"\"", "\""),
+        _ => token.text.to_lowercase(),
+    }
+}
+
+fn is_name(token: &Token) -> bool {
+    match token.kind {
+        TokenKind::QuotedIdentifier => true,
+        TokenKind::Word => !is_keyword(token.text),
+        _ => false,
+    }
+}
+
+/// Tables named after `FROM`, `JOIN`, `UPDATE` and `INTO`, with their aliases.
+/// Tables listed after `FROM` with commas are all picked up.
+fn table_references(tokens: &[Token]) -> Vec<TableReference> {
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if !TABLE_KEYWORDS.iter().any(|k| tokens[i].is_word(k)) {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        while let Some(first) = tokens.get(i).filter(|t| is_name(t)) {
+            let mut name = identifier(first);
+            i += 1;
+            if let (Some(dot), Some(table)) = (tokens.get(i), tokens.get(i + 1)) {
+                if dot.text == "." && is_name(table) {
+                    name = format!("{}.{}", name, identifier(table));
+                    i += 2;
+                }
+            }
+            if tokens.get(i).is_some_and(|t| t.is_word("AS")) {
+                i += 1;
+            }
+            let alias = tokens.get(i).filter(|t| is_name(t)).map(identifier);
+            if alias.is_some() {
+                i += 1;
+            }
+            references.push(TableReference { name, alias });
+            if tokens.get(i).is_some_and(|t| t.text == ",") {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    references
+}
+
+/// Keywords in the case the word is being typed in
+fn keywords(prefix: &str) -> impl Iterator<Item = Completion> + '_ {
+    let lowercase = prefix.chars().next().is_some_and(char::is_lowercase);
+    CLAUSE_KEYWORDS.iter().map(move |k| {
+        let text = if lowercase { k.to_lowercase() } else { k.to_string() };
+        Completion::new(text, CompletionKind::Keyword, None)
+    })
+}
+
+/// Tables of the default schema by name, and those of other schemas qualified
+fn tables(schema: &DatabaseSchema) -> Vec<Completion> {
+    schema
+        .tables()
+        .map(|t| {
+            let text = if matches!(t.schema.as_str(), "public" | "main") { t.name.clone() } else { format!("{}.{}", t.schema, t.name) };
+            Completion::new(text, CompletionKind::Table, Some(t.kind.to_string()))
+        })
+        .collect()
+}
+
+fn columns(table: &Table, with_table: bool) -> impl Iterator<Item = Completion> + '_ {
+    table.columns.iter().map(move |c| {
+        let detail = if with_table { format!("{} ({})", c.data_type, table.name) } else { c.data_type.clone() };
+        Completion::new(&c.name, CompletionKind::Column, Some(detail))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{complete, CompletionKind};
+    use crate::sql::schema::{Column, DatabaseSchema, Function, FunctionKind, Schema, Table, TableKind};
+
+    fn table(schema: &str, name: &str, columns: &[&str]) -> Table {
+        Table {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            kind: TableKind::Table,
+            columns: columns
+                .iter()
+                .map(|c| Column { name: c.to_string(), data_type: "integer".to_string(), nullable: true, default: None })
+                .collect(),
+            constraints: Vec::new(),
+            indexes: Vec::new(),
+        }
+    }
+
+    fn schema() -> DatabaseSchema {
+        DatabaseSchema {
+            schemas: vec![
+                Schema { name: "audit".to_string(), tables: vec![table("audit", "events", &["id", "payload"])], ..Schema::default() },
+                Schema {
+                    name: "public".to_string(),
+                    tables: vec![table("public", "orders", &["id", "user_id", "amount"]), table("public", "users", &["id", "name", "email"])],
+                    functions: vec![Function {
+                        schema: "public".to_string(),
+                        name: "order_total".to_string(),
+                        kind: FunctionKind::Function,
+                        arguments: "uid integer".to_string(),
+                        result: Some("numeric".to_string()),
+                    }],
+                    enums: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    /// Completes at the `|` in `sql`
+    fn texts(sql: &str) -> Vec<String> {
+        let offset = sql.find('|').expect("a cursor");
+        let sql = sql.replace('|', "");
+        complete(&sql, offset, Some(&schema())).items.into_iter().map(|c| c.text).collect()
+    }
+
+    #[test]
+    fn test_complete_columns_of_aliases() {
+        assert_eq!(texts("SELECT u.| FROM users u JOIN orders o ON o.user_id = u.id"), ["id", "name", "email"]);
+        assert_eq!(texts("SELECT o.a| FROM users u JOIN orders AS o ON o.user_id = u.id"), ["amount"]);
+        assert_eq!(texts("SELECT users.e| FROM users"), ["email"]);
+        assert_eq!(texts("SELECT e.p| FROM public.users, audit.events e"), ["payload"]);
+        // Aliases from other statements don't count
+        assert!(texts("SELECT * FROM orders u; SELECT u.| FROM users").is_empty());
+    }
+
+    #[test]
+    fn test_complete_tables_and_keywords() {
+        assert_eq!(texts("SELECT * FROM u|"), ["users"]);
+        assert_eq!(texts("SELECT * FROM a|"), ["audit.events", "audit"]);
+        assert_eq!(texts("SELECT * FROM audit.|"), ["events"]);
+        // Columns of the statement's tables come first, in the case typed for keywords
+        assert_eq!(texts("SELECT na| FROM users"), ["name"]);
+        assert_eq!(texts("SELECT * FROM users wh|"), ["when", "where"]);
+        assert_eq!(texts("SELECT * FROM users ORDER B|"), ["BEGIN", "BETWEEN", "BY"]);
+        assert_eq!(texts("SELECT ord| FROM orders"), ["order_total", "order", "orders"]);
+
+        let completions = complete("SELECT * FROM us", 16, Some(&schema()));
+        assert_eq!(completions.start, 14);
+        assert_eq!(completions.items[0].kind, CompletionKind::Table);
+    }
+
+    #[test]
+    fn test_no_completions_in_literals_or_without_a_word() {
+        assert!(texts("SELECT 'us|' FROM users").is_empty());
+        assert!(texts("SELECT 1 -- us|").is_empty());
+        assert!(texts("SELECT |").is_empty());
+        assert!(texts("SELECT * FROM users|").is_empty());
+        // Keywords still work without a schema
+        let completions = complete("sel", 3, None);
+        assert_eq!(completions.items.iter().map(|c| c.text.as_str()).collect::<Vec<_>>(), ["select"]);
+    }
+}
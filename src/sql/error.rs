@@ -0,0 +1,274 @@
+use sqlx::postgres::{PgDatabaseError, PgErrorPosition, PgSeverity};
+use std::fmt;
+
+use super::{cancel::QueryCancelled, statement::Statement, timeout::{QueryTimedOut, TimeoutKind}};
+
+/// Where in a script the server reported an error; `line` and `column` are 0-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorPosition {
+    pub line: usize,
+    /// In characters from the start of the line
+    pub column: usize,
+    /// The line itself, for showing the position in context
+    pub line_text: String,
+}
+
+/// An error the server returned for a statement, with the fields psql shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    /// `ERROR`, or `FATAL` when the server also closed the connection
+    pub severity: String,
+    /// The SQLSTATE, e.g. `42703` for an undefined column
+    pub code: String,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    /// Where the error was raised from, e.g. `PL/pgSQL function f() line 3 at RAISE`
+    pub context: Option<String>,
+    /// The violated constraint, for integrity constraint violations
+    pub constraint: Option<String>,
+    pub position: Option<ErrorPosition>,
+}
+
+impl QueryError {
+    /// Picks the server's error for `statement` of `script` out of an execution error.
+    /// The position is only kept if the statement was sent as written, `offset` characters
+    /// into the query (e.g. behind a wrapping `SELECT`); None means it was rewritten.
+    pub(crate) fn from_error(error: &anyhow::Error, script: &str, statement: &Statement, offset: Option<usize>) -> Option<Self> {
+        let pg = pg_error(error)?;
+        let position = match (pg.position(), offset) {
+            (Some(PgErrorPosition::Original(position)), Some(offset)) => {
+                position.checked_sub(offset).and_then(|position| error_position(script, statement, position))
+            }
+            _ => None,
+        };
+        Some(Self { position, ..Self::from_pg(pg) })
+    }
+
+    /// Picks the server's error out of an error for SQL the user didn't write, e.g. a `COPY`
+    pub(crate) fn from_server(error: &anyhow::Error) -> Option<Self> {
+        pg_error(error).map(Self::from_pg)
+    }
+
+    fn from_pg(pg: &PgDatabaseError) -> Self {
+        Self {
+            severity: match pg.severity() {
+                PgSeverity::Fatal => "FATAL",
+                PgSeverity::Panic => "PANIC",
+                _ => "ERROR",
+            }.to_string(),
+            code: pg.code().to_string(),
+            message: pg.message().to_string(),
+            detail: pg.detail().map(str::to_string),
+            hint: pg.hint().map(str::to_string),
+            context: pg.r#where().map(str::to_string),
+            constraint: pg.constraint().map(str::to_string),
+            position: None,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        ErrorClass::from_sqlstate(&self.code)
+    }
+
+    /// Formatted like psql's verbose errors, with a caret under the error position:
+    ///
+    /// ```text
+    /// ERROR:  42703: column "nme" does not exist
+    /// LINE 1: SELECT nme FROM users
+    ///                ^
+    /// HINT:  Perhaps you meant to reference the column "users.name".
+    /// ```
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!("{}:  {}: {}", self.severity, self.code, self.message)];
+        if let Some(position) = &self.position {
+            let prefix = format!("LINE {}: ", position.line + 1);
+            lines.push(format!("{}{}", prefix, position.line_text));
+            lines.push(format!("{}^", " ".repeat(prefix.len() + position.column)));
+        }
+        let fields = [("DETAIL", &self.detail), ("HINT", &self.hint), ("CONTEXT", &self.context), ("CONSTRAINT", &self.constraint)];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                lines.push(format!("{}:  {}", label, value));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+fn pg_error(error: &anyhow::Error) -> Option<&PgDatabaseError> {
+    match error.downcast_ref::<sqlx::Error>()? {
+        sqlx::Error::Database(e) => e.try_downcast_ref::<PgDatabaseError>(),
+        _ => None,
+    }
+}
+
+/// Turns the server's 1-based character position within `statement` into a line and column of `script`
+fn error_position(script: &str, statement: &Statement, position: usize) -> Option<ErrorPosition> {
+    // Errors at the end of the input point just past the last character
+    let offset = statement.text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([statement.text.len()])
+        .nth(position.checked_sub(1)?)?;
+    let offset = statement.start + offset;
+
+    let line_start = script[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = script[offset..].find('\n').map_or(script.len(), |i| offset + i);
+    Some(ErrorPosition {
+        line: script[..offset].matches('\n').count(),
+        column: script[line_start..offset].chars().count(),
+        line_text: script[line_start..line_end].trim_end_matches('\r').to_string(),
+    })
+}
+
+/// Broad kinds of failure, each with its own exit code for `sqli query` and `sqli import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Couldn't connect or authenticate, or the connection was lost
+    Connection,
+    /// Invalid SQL, or a table, column or function that doesn't exist
+    Syntax,
+    /// A value that is out of range or can't be converted
+    Data,
+    /// A unique, foreign key, not-null or check constraint was violated
+    Constraint,
+    /// Missing privileges, or a write in a read-only transaction
+    Permission,
+    /// The statement was stopped by a timeout or by the user
+    Cancelled,
+    /// A serialization failure, deadlock or aborted transaction
+    Transaction,
+    Other,
+}
+
+impl ErrorClass {
+    /// Classifies a SQLSTATE by its class (first two characters), and a few codes on their own
+    pub fn from_sqlstate(code: &str) -> Self {
+        match code {
+            "42501" | "25006" => ErrorClass::Permission,
+            "57014" | "55P03" => ErrorClass::Cancelled,
+            "57P01" | "57P02" | "57P03" => ErrorClass::Connection,
+            _ => match code.get(..2) {
+                Some("08" | "28" | "3D") => ErrorClass::Connection,
+                Some("42") => ErrorClass::Syntax,
+                Some("22") => ErrorClass::Data,
+                Some("23") => ErrorClass::Constraint,
+                Some("25" | "40") => ErrorClass::Transaction,
+                _ => ErrorClass::Other,
+            },
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorClass::Other => 1,
+            ErrorClass::Connection => 3,
+            ErrorClass::Syntax => 4,
+            ErrorClass::Data => 5,
+            ErrorClass::Constraint => 6,
+            ErrorClass::Permission => 7,
+            ErrorClass::Cancelled => 8,
+            ErrorClass::Transaction => 9,
+        }
+    }
+
+    /// The class of an error that ended an execution; errors that didn't come from the database are `Other`
+    pub fn of(error: &anyhow::Error) -> Self {
+        if let Some(timed_out) = error.downcast_ref::<QueryTimedOut>() {
+            return match timed_out.kind {
+                TimeoutKind::Connect => ErrorClass::Connection,
+                TimeoutKind::Statement | TimeoutKind::Lock => ErrorClass::Cancelled,
+            };
+        }
+        if error.is::<QueryCancelled>() {
+            return ErrorClass::Cancelled;
+        }
+        if let Some(query_error) = error.downcast_ref::<QueryError>() {
+            return query_error.class();
+        }
+        match error.downcast_ref::<sqlx::Error>() {
+            // E.g. a failed login, or a database that doesn't exist
+            Some(sqlx::Error::Database(e)) => e.code().map_or(ErrorClass::Other, |code| Self::from_sqlstate(&code)),
+            Some(sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed) => ErrorClass::Connection,
+            _ => ErrorClass::Other,
+        }
+    }
+}
+
+impl fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorClass::Connection => write!(f, "Connection error"),
+            ErrorClass::Syntax => write!(f, "Syntax error"),
+            ErrorClass::Data => write!(f, "Data error"),
+            ErrorClass::Constraint => write!(f, "Constraint violation"),
+            ErrorClass::Permission => write!(f, "Permission denied"),
+            ErrorClass::Cancelled => write!(f, "Cancelled"),
+            ErrorClass::Transaction => write!(f, "Transaction error"),
+            ErrorClass::Other => write!(f, "Error"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error_position, ErrorClass, ErrorPosition, QueryError};
+    use crate::sql::statement::split_statements;
+
+    #[test]
+    fn test_error_position_in_script() {
+        let script = "SELECT 1;\n\nSELECT id,\n       nme FROM users";
+        let statements = split_statements(script);
+        // The server counts characters from 1 within the statement it was sent
+        let position = error_position(script, &statements[1], 19);
+        assert_eq!(position, Some(ErrorPosition { line: 3, column: 7, line_text: "       nme FROM users".to_string() }));
+
+        let script = "SELECT 'é', nme";
+        let position = error_position(script, &split_statements(script)[0], 13);
+        assert_eq!(position.map(|p| p.column), Some(12));
+        // Just past the end, as for "syntax error at end of input"
+        assert_eq!(error_position(script, &split_statements(script)[0], 16).map(|p| p.column), Some(15));
+        assert_eq!(error_position(script, &split_statements(script)[0], 17), None);
+    }
+
+    #[test]
+    fn test_query_error_report() {
+        let error = QueryError {
+            severity: "ERROR".to_string(),
+            code: "42703".to_string(),
+            message: "column \"nme\" does not exist".to_string(),
+            detail: None,
+            hint: Some("Perhaps you meant to reference the column \"users.name\".".to_string()),
+            context: None,
+            constraint: None,
+            position: Some(ErrorPosition { line: 0, column: 7, line_text: "SELECT nme FROM users".to_string() }),
+        };
+        assert_eq!(error.report(), "\
+ERROR:  42703: column \"nme\" does not exist
+LINE 1: SELECT nme FROM users
+               ^
+HINT:  Perhaps you meant to reference the column \"users.name\".");
+        assert_eq!(error.class(), ErrorClass::Syntax);
+        assert_eq!(error.class().exit_code(), 4);
+    }
+
+    #[test]
+    fn test_error_class_from_sqlstate() {
+        assert_eq!(ErrorClass::from_sqlstate("23505"), ErrorClass::Constraint);
+        assert_eq!(ErrorClass::from_sqlstate("42501"), ErrorClass::Permission);
+        assert_eq!(ErrorClass::from_sqlstate("42P01"), ErrorClass::Syntax);
+        assert_eq!(ErrorClass::from_sqlstate("28P01"), ErrorClass::Connection);
+        assert_eq!(ErrorClass::from_sqlstate("40P01"), ErrorClass::Transaction);
+        assert_eq!(ErrorClass::from_sqlstate("57014"), ErrorClass::Cancelled);
+        assert_eq!(ErrorClass::from_sqlstate("XX000"), ErrorClass::Other);
+    }
+}
@@ -0,0 +1,354 @@
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value as Json;
+use std::fmt;
+
+use super::{result::QueryResult, statement::split_statements, value::Value};
+
+/// Nodes taking at least this share of the plan's time (or cost) are highlighted
+const HOT_SHARE: f64 = 0.3;
+/// Row estimates off by at least this factor are highlighted
+const MISESTIMATE_FACTOR: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainMode {
+    /// Only plan the statement
+    Plan,
+    /// Run the statement and report actual times and row counts
+    Analyze,
+}
+
+impl ExplainMode {
+    pub fn new(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "plan" => Ok(ExplainMode::Plan),
+            "analyze" => Ok(ExplainMode::Analyze),
+            _ => Err(anyhow!("Invalid explain mode: '{}'. Supported modes: plan, analyze", s)),
+        }
+    }
+}
+
+/// Wraps a single statement in `EXPLAIN (FORMAT JSON)`
+pub fn explain_sql(sql: &str, mode: ExplainMode) -> Result<String> {
+    let statements = split_statements(sql);
+    let [statement] = statements.as_slice() else {
+        bail!("EXPLAIN needs exactly one statement, found {}", statements.len());
+    };
+    let options = match mode {
+        ExplainMode::Plan => "FORMAT JSON",
+        ExplainMode::Analyze => "ANALYZE, BUFFERS, FORMAT JSON",
+    };
+    Ok(format!("EXPLAIN ({}) {}", options, statement.text))
+}
+
+/// What a node actually did, from `EXPLAIN ANALYZE`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActualStats {
+    /// Milliseconds per loop
+    pub startup_time: f64,
+    pub total_time: f64,
+    /// Rows per loop
+    pub rows: f64,
+    pub loops: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    pub node_type: String,
+    /// What the node works on, e.g. `on users u` or `using users_pkey on users`
+    pub detail: String,
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    pub plan_rows: f64,
+    pub plan_width: u64,
+    pub actual: Option<ActualStats>,
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn from_json(node: &Json) -> Result<Self> {
+        let text = |key: &str| node.get(key).and_then(Json::as_str);
+        let number = |key: &str| node.get(key).and_then(Json::as_f64);
+
+        let node_type = text("Node Type").ok_or_else(|| anyhow!("Plan node has no \"Node Type\""))?;
+        let node_type = match (node_type, text("Join Type"), text("Strategy")) {
+            (_, Some("Inner") | None, None) => node_type.to_string(),
+            ("Nested Loop", Some(join), _) => format!("Nested Loop {} Join", join),
+            (_, Some(join), _) if node_type.ends_with(" Join") => {
+                format!("{} {} Join", node_type.trim_end_matches(" Join"), join)
+            }
+            ("Aggregate", _, Some("Hashed")) => "HashAggregate".to_string(),
+            ("Aggregate", _, Some("Sorted")) => "GroupAggregate".to_string(),
+            ("Aggregate", _, Some("Mixed")) => "MixedAggregate".to_string(),
+            _ => node_type.to_string(),
+        };
+
+        let mut detail = Vec::new();
+        if let Some(index) = text("Index Name") {
+            detail.push(format!("using {}", index));
+        }
+        let target = text("Relation Name").or(text("CTE Name")).or(text("Function Name"));
+        if let Some(target) = target {
+            match text("Alias") {
+                Some(alias) if alias != target => detail.push(format!("on {} {}", target, alias)),
+                _ => detail.push(format!("on {}", target)),
+            }
+        }
+        if let Some(subplan) = text("Subplan Name") {
+            detail.insert(0, format!("({})", subplan));
+        }
+
+        let actual = number("Actual Total Time").map(|total_time| ActualStats {
+            startup_time: number("Actual Startup Time").unwrap_or_default(),
+            total_time,
+            rows: number("Actual Rows").unwrap_or_default(),
+            loops: number("Actual Loops").unwrap_or(1.0),
+        });
+
+        let children = node
+            .get("Plans")
+            .and_then(Json::as_array)
+            .map(|plans| plans.iter().map(PlanNode::from_json).collect::<Result<Vec<_>>>())
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            node_type,
+            detail: detail.join(" "),
+            startup_cost: number("Startup Cost").unwrap_or_default(),
+            total_cost: number("Total Cost").unwrap_or_default(),
+            plan_rows: number("Plan Rows").unwrap_or_default(),
+            plan_width: node.get("Plan Width").and_then(Json::as_u64).unwrap_or_default(),
+            actual,
+            children,
+        })
+    }
+
+    /// The node type with what it works on, e.g. `Index Scan using users_pkey on users`
+    pub fn summary(&self) -> String {
+        if self.detail.is_empty() {
+            self.node_type.clone()
+        } else {
+            format!("{} {}", self.node_type, self.detail)
+        }
+    }
+
+    /// Estimates and, when analyzed, actuals, in the same form as text EXPLAIN output
+    pub fn stats(&self) -> String {
+        let mut stats = format!(
+            "(cost={:.2}..{:.2} rows={} width={})",
+            self.startup_cost, self.total_cost, format_rows(self.plan_rows), self.plan_width
+        );
+        if let Some(actual) = &self.actual {
+            stats.push_str(&format!(
+                " (actual time={:.3}..{:.3} rows={} loops={})",
+                actual.startup_time, actual.total_time, format_rows(actual.rows), actual.loops
+            ));
+        }
+        stats
+    }
+
+    /// Milliseconds spent in this node itself, excluding its children, over all loops
+    pub fn self_time(&self) -> Option<f64> {
+        let own = self.actual.map(|a| a.total_time * a.loops)?;
+        let children: f64 = self.children
+            .iter()
+            .filter_map(|child| child.actual.map(|a| a.total_time * a.loops))
+            .sum();
+        Some((own - children).max(0.0))
+    }
+
+    /// Planner cost of this node itself, excluding its children
+    pub fn self_cost(&self) -> f64 {
+        let children: f64 = self.children.iter().map(|child| child.total_cost).sum();
+        (self.total_cost - children).max(0.0)
+    }
+
+    /// How far the row estimate was off (always at least 1), when it was off
+    /// by enough to matter; negative when the planner over-estimated.
+    pub fn row_misestimate(&self) -> Option<f64> {
+        let actual = self.actual?;
+        let estimated = self.plan_rows.max(1.0);
+        let rows = actual.rows.max(1.0);
+        if rows / estimated >= MISESTIMATE_FACTOR {
+            Some(rows / estimated)
+        } else if estimated / rows >= MISESTIMATE_FACTOR {
+            Some(-(estimated / rows))
+        } else {
+            None
+        }
+    }
+}
+
+/// A parsed `EXPLAIN (FORMAT JSON)` plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainPlan {
+    pub root: PlanNode,
+    /// Milliseconds
+    pub planning_time: Option<f64>,
+    pub execution_time: Option<f64>,
+}
+
+impl ExplainPlan {
+    pub fn from_json(json: &Json) -> Result<Self> {
+        let explained = json
+            .as_array()
+            .and_then(|plans| plans.first())
+            .unwrap_or(json);
+        let root = explained
+            .get("Plan")
+            .ok_or_else(|| anyhow!("EXPLAIN output has no \"Plan\""))?;
+
+        Ok(Self {
+            root: PlanNode::from_json(root)?,
+            planning_time: explained.get("Planning Time").and_then(Json::as_f64),
+            execution_time: explained.get("Execution Time").and_then(Json::as_f64),
+        })
+    }
+
+    /// Finds the plan in the results of running `explain_sql`
+    pub fn from_results(results: &[QueryResult]) -> Result<Self> {
+        let cell = results
+            .iter()
+            .rev()
+            .find_map(|result| result.rows.first().and_then(|row| row.first()))
+            .ok_or_else(|| anyhow!("EXPLAIN returned no plan"))?;
+        match cell {
+            Value::Json(json) => Self::from_json(json),
+            other => Self::from_json(&serde_json::from_str(&other.to_string())?),
+        }
+    }
+
+    pub fn is_analyzed(&self) -> bool {
+        self.root.actual.is_some()
+    }
+
+    /// The share of the plan's time (or cost, when not analyzed) spent in the node itself
+    pub fn share(&self, node: &PlanNode) -> f64 {
+        let (own, total) = match (node.self_time(), self.root.actual) {
+            (Some(own), Some(root)) => (own, root.total_time * root.loops),
+            _ => (node.self_cost(), self.root.total_cost),
+        };
+        if total > 0.0 { own / total } else { 0.0 }
+    }
+
+    /// Whether the node is one of the most expensive in the plan
+    pub fn is_hot(&self, node: &PlanNode) -> bool {
+        self.share(node) >= HOT_SHARE
+    }
+
+    /// Short notes about a node worth looking at: its share of the plan and bad row estimates
+    pub fn annotations(&self, node: &PlanNode) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.is_hot(node) {
+            let measure = if self.is_analyzed() { "time" } else { "cost" };
+            notes.push(format!("{:.0}% of {}", self.share(node) * 100.0, measure));
+        }
+        match node.row_misestimate() {
+            Some(factor) if factor > 0.0 => notes.push(format!("rows under-estimated {:.0}x", factor)),
+            Some(factor) => notes.push(format!("rows over-estimated {:.0}x", -factor)),
+            None => {}
+        }
+        notes
+    }
+
+    fn write_node(&self, f: &mut fmt::Formatter<'_>, node: &PlanNode, prefix: &str, branch: &str) -> fmt::Result {
+        write!(f, "{}{}  {}", branch, node.summary(), node.stats())?;
+        for note in self.annotations(node) {
+            write!(f, "  [{}]", note)?;
+        }
+        writeln!(f)?;
+
+        for (i, child) in node.children.iter().enumerate() {
+            let last = i + 1 == node.children.len();
+            let (child_branch, child_prefix) = if last { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+            self.write_node(f, child, &format!("{}{}", prefix, child_prefix), &format!("{}{}", prefix, child_branch))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the plan as an indented tree, like text EXPLAIN output
+impl fmt::Display for ExplainPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_node(f, &self.root, "", "")?;
+        if let Some(planning_time) = self.planning_time {
+            writeln!(f, "Planning Time: {:.3} ms", planning_time)?;
+        }
+        if let Some(execution_time) = self.execution_time {
+            writeln!(f, "Execution Time: {:.3} ms", execution_time)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_rows(rows: f64) -> String {
+    if rows.fract() == 0.0 {
+        format!("{:.0}", rows)
+    } else {
+        format!("{:.2}", rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain_sql, ExplainMode, ExplainPlan};
+
+    const ANALYZED: &str = r#"[{
+        "Plan": {
+            "Node Type": "Hash Join", "Join Type": "Left",
+            "Startup Cost": 1.07, "Total Cost": 2.2, "Plan Rows": 3, "Plan Width": 36,
+            "Actual Startup Time": 0.03, "Actual Total Time": 10.0, "Actual Rows": 3, "Actual Loops": 1,
+            "Plans": [
+                {
+                    "Node Type": "Seq Scan", "Relation Name": "orders", "Alias": "o",
+                    "Startup Cost": 0.0, "Total Cost": 1.03, "Plan Rows": 3, "Plan Width": 20,
+                    "Actual Startup Time": 0.01, "Actual Total Time": 8.0, "Actual Rows": 3000, "Actual Loops": 1
+                },
+                {
+                    "Node Type": "Hash",
+                    "Startup Cost": 1.03, "Total Cost": 1.03, "Plan Rows": 3, "Plan Width": 20,
+                    "Actual Startup Time": 0.5, "Actual Total Time": 0.5, "Actual Rows": 3, "Actual Loops": 1,
+                    "Plans": [{
+                        "Node Type": "Index Scan", "Index Name": "users_pkey", "Relation Name": "users", "Alias": "users",
+                        "Startup Cost": 0.0, "Total Cost": 1.03, "Plan Rows": 3, "Plan Width": 20,
+                        "Actual Startup Time": 0.1, "Actual Total Time": 0.4, "Actual Rows": 3, "Actual Loops": 1
+                    }]
+                }
+            ]
+        },
+        "Planning Time": 0.125,
+        "Execution Time": 10.5
+    }]"#;
+
+    #[test]
+    fn test_explain_sql() {
+        assert_eq!(explain_sql("SELECT 1;", ExplainMode::Plan).unwrap(), "EXPLAIN (FORMAT JSON) SELECT 1");
+        assert_eq!(explain_sql("-- q\nSELECT 1", ExplainMode::Analyze).unwrap(), "EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) SELECT 1");
+        assert_eq!(
+            explain_sql("SELECT 1; SELECT 2", ExplainMode::Plan).unwrap_err().to_string(),
+            "EXPLAIN needs exactly one statement, found 2"
+        );
+    }
+
+    #[test]
+    fn test_parse_and_render_plan() {
+        let plan = ExplainPlan::from_json(&serde_json::from_str(ANALYZED).unwrap()).unwrap();
+        assert!(plan.is_analyzed());
+        assert_eq!(plan.root.node_type, "Hash Left Join");
+        assert_eq!(plan.root.children[1].children[0].summary(), "Index Scan using users_pkey on users");
+
+        let scan = &plan.root.children[0];
+        assert!(plan.is_hot(scan));
+        assert_eq!(scan.row_misestimate(), Some(1000.0));
+        assert!(!plan.is_hot(&plan.root.children[1]));
+
+        assert_eq!(plan.to_string(), "\
+Hash Left Join  (cost=1.07..2.20 rows=3 width=36) (actual time=0.030..10.000 rows=3 loops=1)
+├─ Seq Scan on orders o  (cost=0.00..1.03 rows=3 width=20) (actual time=0.010..8.000 rows=3000 loops=1)  [80% of time]  [rows under-estimated 1000x]
+└─ Hash  (cost=1.03..1.03 rows=3 width=20) (actual time=0.500..0.500 rows=3 loops=1)
+   └─ Index Scan using users_pkey on users  (cost=0.00..1.03 rows=3 width=20) (actual time=0.100..0.400 rows=3 loops=1)
+Planning Time: 0.125 ms
+Execution Time: 10.500 ms
+");
+    }
+}
@@ -0,0 +1,418 @@
+use super::lexer::{tokenize, Token, TokenKind};
+
+const INDENT: &str = "    ";
+
+/// Keywords that start a clause, which goes on a line of its own
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "EXCEPT", "FETCH", "FROM", "HAVING", "INTERSECT", "LIMIT", "OFFSET", "RETURNING", "SELECT", "UNION",
+    "VALUES", "WHERE", "WINDOW",
+];
+
+/// Statements laid out clause by clause; others, like GRANT or ALTER, are kept to one line
+const QUERY_KEYWORDS: &[&str] = &["CREATE", "DELETE", "EXPLAIN", "INSERT", "SELECT", "TABLE", "UPDATE", "VALUES", "WITH"];
+
+/// Keywords that can make up a join, like `LEFT OUTER JOIN`
+const JOIN_KEYWORDS: &[&str] = &["CROSS", "FULL", "INNER", "JOIN", "LEFT", "NATURAL", "OUTER", "RIGHT"];
+
+/// Keywords after which `SELECT` carries on the line rather than starting a clause
+const SELECT_CONTINUES: &[&str] = &["ALL", "AS", "DISTINCT", "EXCEPT", "INTERSECT", "UNION"];
+
+/// Reformats SQL: clauses start on new lines, subqueries and the columns of `CREATE TABLE`
+/// are indented, `AND`/`OR` conditions in `WHERE` and `HAVING` go one per line, and keywords
+/// are uppercased. Comments and literals are kept as they are, as are blank lines between
+/// statements. Formatting already formatted SQL leaves it unchanged.
+pub fn format_sql(sql: &str) -> String {
+    let mut items = Vec::new();
+    let mut newlines = 0;
+    let mut spaced = false;
+    for token in tokenize(sql) {
+        if token.kind == TokenKind::Whitespace {
+            newlines += token.text.matches('\n').count();
+            spaced = true;
+            continue;
+        }
+        items.push(Item { token, newlines, spaced });
+        newlines = 0;
+        spaced = false;
+    }
+
+    let mut formatter = Formatter::default();
+    for i in 0..items.len() {
+        formatter.format(&items, i);
+    }
+    formatter.out.trim_end().to_string()
+}
+
+/// A token, with what separated it from the one before
+#[derive(Clone, Copy)]
+struct Item<'a> {
+    token: Token<'a>,
+    newlines: usize,
+    spaced: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+    /// A statement or subquery, laid out clause by clause
+    Query,
+    /// The columns of `CREATE TABLE`, one per line
+    List,
+    /// Anything else in parentheses, kept on the line
+    Inline,
+}
+
+struct Scope {
+    group: Group,
+    /// Indentation of the scope's lines
+    indent: usize,
+    /// Indentation of the line the scope was opened on, where its `)` goes
+    outer_indent: usize,
+    /// The clause being written, if laid out by clause
+    clause: Option<&'static str>,
+    saw_update: bool,
+    /// Set after `BETWEEN`, until the `AND` that goes with it
+    in_between: bool,
+}
+
+impl Scope {
+    fn new(group: Group, indent: usize, outer_indent: usize) -> Self {
+        Self { group, indent, outer_indent, clause: None, saw_update: false, in_between: false }
+    }
+}
+
+struct Formatter<'a> {
+    out: String,
+    /// The statement, then each open parenthesis
+    scopes: Vec<Scope>,
+    at_line_start: bool,
+    line_indent: usize,
+    /// Set when the next token has to go on a new line, with that line's indentation
+    pending_break: Option<usize>,
+    /// The last two tokens written, other than comments
+    prev: [Option<Token<'a>>; 2],
+    at_statement_start: bool,
+    /// Whether the statement is laid out by clause
+    is_query: bool,
+    /// Set in `CREATE TABLE`, until its column list or `AS`
+    expects_columns: bool,
+}
+
+impl Default for Formatter<'_> {
+    fn default() -> Self {
+        Self {
+            out: String::new(),
+            scopes: vec![Scope::new(Group::Query, 0, 0)],
+            at_line_start: true,
+            line_indent: 0,
+            pending_break: None,
+            prev: [None, None],
+            at_statement_start: true,
+            is_query: false,
+            expects_columns: false,
+        }
+    }
+}
+
+impl<'a> Formatter<'a> {
+    fn scope(&self) -> &Scope {
+        self.scopes.last().expect("the statement scope is never closed")
+    }
+
+    fn scope_mut(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("the statement scope is never closed")
+    }
+
+    fn format(&mut self, items: &[Item<'a>], i: usize) {
+        let Item { token, newlines, spaced } = items[i];
+        if token.is_trivia() {
+            self.comment(items, i);
+            return;
+        }
+
+        let line_break = self.line_break(items, i).or(self.pending_break.take());
+        if self.at_statement_start {
+            self.at_statement_start = false;
+            self.is_query = QUERY_KEYWORDS.iter().any(|k| token.is_word(k));
+            self.expects_columns = false;
+            if !self.out.is_empty() {
+                self.newline(0);
+                if newlines > 1 {
+                    self.out.push('\n');
+                }
+            }
+        } else if let Some(indent) = line_break {
+            self.newline(indent);
+        } else if newlines > 0 && token.kind == TokenKind::String && self.prev[0].is_some_and(|p| p.kind == TokenKind::String) {
+            // Adjacent string literals are only joined into one if a newline separates them
+            self.newline(self.continuation_indent());
+        }
+
+        let [prev, _] = self.prev;
+        match token.text {
+            "(" => {
+                let next = next_code(items, i);
+                let group = if next.is_some_and(|t| t.is_word("SELECT") || t.is_word("WITH")) {
+                    Group::Query
+                } else if self.expects_columns && self.scopes.len() == 1 && prev.is_some_and(|p| is_name(&p)) {
+                    self.expects_columns = false;
+                    Group::List
+                } else {
+                    Group::Inline
+                };
+                self.write("(", self.space_before(&token, spaced));
+                let indent = match group {
+                    Group::Inline => self.scope().indent,
+                    _ => self.line_indent + 1,
+                };
+                if group != Group::Inline {
+                    self.pending_break = Some(indent);
+                }
+                self.scopes.push(Scope::new(group, indent, self.line_indent));
+            }
+            ")" => {
+                if self.scopes.len() > 1 {
+                    self.scopes.pop();
+                }
+                self.write(")", false);
+            }
+            "," => {
+                self.write(",", false);
+                if self.scope().group == Group::List {
+                    self.pending_break = Some(self.scope().indent);
+                }
+            }
+            ";" => {
+                self.write(";", false);
+                self.scopes.truncate(1);
+                *self.scope_mut() = Scope::new(Group::Query, 0, 0);
+                self.at_statement_start = true;
+            }
+            _ => {
+                let is_keyword = token.is_keyword() && prev.is_none_or(|p| p.text != ".");
+                let text = if is_keyword { token.text.to_ascii_uppercase() } else { token.text.to_string() };
+                self.write(&text, self.space_before(&token, spaced));
+                if let Some(clause) = self.clause(items, i) {
+                    self.scope_mut().clause = Some(clause);
+                }
+                if token.is_word("UPDATE") {
+                    self.scope_mut().saw_update = true;
+                } else if token.is_word("BETWEEN") {
+                    self.scope_mut().in_between = true;
+                } else if token.is_word("AND") {
+                    self.scope_mut().in_between = false;
+                } else if token.is_word("TABLE") && self.is_query {
+                    self.expects_columns = true;
+                } else if token.is_word("AS") {
+                    self.expects_columns = false;
+                }
+            }
+        }
+        self.prev = [Some(token), prev];
+    }
+
+    /// Writes a comment where it was: after the code on its line, or on a line of its own
+    fn comment(&mut self, items: &[Item<'a>], i: usize) {
+        let Item { token, newlines, .. } = items[i];
+        let own_line = newlines > 0 || self.out.is_empty();
+        if own_line {
+            if self.at_statement_start {
+                self.newline(0);
+                if newlines > 1 && !self.out.is_empty() {
+                    self.out.push('\n');
+                }
+            } else {
+                // Lined up with the code that follows
+                let next = (i + 1..items.len()).find(|&j| !items[j].token.is_trivia());
+                let indent = next
+                    .and_then(|j| self.line_break(items, j))
+                    .or(self.pending_break)
+                    .unwrap_or_else(|| self.continuation_indent());
+                self.newline(indent);
+                self.pending_break = Some(indent);
+            }
+        }
+        self.write(token.text, true);
+        if token.kind == TokenKind::LineComment && self.pending_break.is_none() && !self.at_statement_start {
+            self.pending_break = Some(self.continuation_indent());
+        }
+    }
+
+    /// Where a line broken in the middle of a clause carries on
+    fn continuation_indent(&self) -> usize {
+        self.scope().indent + 1
+    }
+
+    /// The indentation of the new line the token at `i` has to start, if it does:
+    /// it begins a clause, an `AND`/`OR` condition, or closes a subquery or list
+    fn line_break(&self, items: &[Item<'a>], i: usize) -> Option<usize> {
+        let token = &items[i].token;
+        let scope = self.scope();
+        if self.at_statement_start {
+            return Some(0);
+        }
+        if token.text == ")" {
+            return match scope.group {
+                Group::Inline => None,
+                _ if self.scopes.len() == 1 => None,
+                _ => Some(scope.outer_indent),
+            };
+        }
+        if scope.group != Group::Query || (self.scopes.len() == 1 && !self.is_query) {
+            return None;
+        }
+        if self.clause(items, i).is_some() && self.prev[0].is_some_and(|p| p.text != "(") {
+            return Some(scope.indent);
+        }
+        let is_condition = (token.is_word("AND") && !scope.in_between) || token.is_word("OR");
+        if is_condition && matches!(scope.clause, Some("WHERE" | "HAVING")) {
+            return Some(scope.indent + 1);
+        }
+        None
+    }
+
+    /// The clause the token at `i` starts, if it starts one
+    fn clause(&self, items: &[Item<'a>], i: usize) -> Option<&'static str> {
+        let token = &items[i].token;
+        if token.kind != TokenKind::Word {
+            return None;
+        }
+        let prev = self.prev[0].as_ref();
+        let prev_is = |words: &[&str]| prev.is_some_and(|p| words.iter().any(|w| p.is_word(w)));
+        let next = next_code(items, i);
+        let next_is = |word: &str| next.is_some_and(|n| n.is_word(word));
+
+        if JOIN_KEYWORDS.iter().any(|k| token.is_word(k)) && !prev_is(JOIN_KEYWORDS) {
+            let mut rest = items[i..].iter().map(|item| item.token).filter(|t| !t.is_trivia());
+            let is_join = rest.find(|t| !JOIN_KEYWORDS.iter().any(|k| t.is_word(k)) || t.is_word("JOIN"));
+            return is_join.is_some_and(|t| t.is_word("JOIN")).then_some("JOIN");
+        }
+        match token.text.to_ascii_uppercase().as_str() {
+            "SELECT" if prev.is_some_and(|p| p.is_keyword()) && !prev_is(SELECT_CONTINUES) => None,
+            "FROM" if prev_is(&["DELETE", "DISTINCT"]) => None,
+            "VALUES" if prev_is(&["DEFAULT"]) => None,
+            "GROUP" if next_is("BY") => Some("GROUP"),
+            "ORDER" if next_is("BY") => Some("ORDER"),
+            "SET" if self.scope().saw_update => Some("SET"),
+            "ON" if next_is("CONFLICT") => Some("ON"),
+            _ => CLAUSE_KEYWORDS.iter().find(|k| token.is_word(k)).copied(),
+        }
+    }
+
+    /// Whether a space goes between the last token written and `token`
+    fn space_before(&self, token: &Token, spaced: bool) -> bool {
+        let [Some(prev), before] = self.prev else {
+            return false;
+        };
+        let is_unary = |t: &Token| {
+            matches!(t.text, "-" | "+")
+                && before.is_none_or(|b| b.kind == TokenKind::Operator || b.is_keyword() || matches!(b.text, "(" | "," | "["))
+        };
+        match (prev.text, token.text) {
+            (_, "," | ";" | ")" | "]" | "." | "::") | ("(" | "[" | "." | "::", _) => false,
+            (_, "(" | "[") if is_name(&prev) || prev.is_keyword() || matches!(prev.text, ")" | "]") => {
+                spaced && token.text == "("
+            }
+            _ => !is_unary(&prev),
+        }
+    }
+
+    fn newline(&mut self, indent: usize) {
+        if !self.at_line_start {
+            self.out.truncate(self.out.trim_end().len());
+            self.out.push('\n');
+            self.at_line_start = true;
+        }
+        self.line_indent = indent;
+    }
+
+    fn write(&mut self, text: &str, space: bool) {
+        if self.at_line_start {
+            if !text.is_empty() {
+                self.out.push_str(&INDENT.repeat(self.line_indent));
+            }
+            self.at_line_start = false;
+        } else if space {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+    }
+}
+
+/// Whether the token names something, rather than being a keyword or symbol
+fn is_name(token: &Token) -> bool {
+    match token.kind {
+        TokenKind::Word => !token.is_keyword(),
+        TokenKind::QuotedIdentifier => true,
+        _ => false,
+    }
+}
+
+/// The next token after `i` that isn't a comment
+fn next_code<'a>(items: &[Item<'a>], i: usize) -> Option<Token<'a>> {
+    items[i + 1..].iter().map(|item| item.token).find(|t| !t.is_trivia())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_sql;
+
+    fn assert_formats(sql: &str, expected: &str) {
+        let formatted = format_sql(sql);
+        assert_eq!(formatted, expected);
+        assert_eq!(format_sql(&formatted), formatted, "formatting again changed it");
+    }
+
+    #[test]
+    fn test_format_query() {
+        assert_formats(
+            "select u.id, count(o.id) as orders from users u left join orders o on o.user_id = u.id \
+             where u.active and u.created_at between $1 and :until or u.id in (select user_id from admins where level > -1) \
+             group by u.id order by orders desc limit 10",
+            "SELECT u.id, count(o.id) AS orders\n\
+             FROM users u\n\
+             LEFT JOIN orders o ON o.user_id = u.id\n\
+             WHERE u.active\n    AND u.created_at BETWEEN $1 AND :until\n    OR u.id IN (\n        SELECT user_id\n        FROM admins\n        WHERE level > -1\n    )\n\
+             GROUP BY u.id\n\
+             ORDER BY orders DESC\n\
+             LIMIT 10",
+        );
+        assert_formats(
+            "with recent as (select * from events where at > now() - interval '1 day') select count(*)::int from recent union all select 0",
+            "WITH recent AS (\n    SELECT *\n    FROM events\n    WHERE at > now() - INTERVAL '1 day'\n)\n\
+             SELECT count(*)::INT\nFROM recent\nUNION ALL\nSELECT 0",
+        );
+    }
+
+    #[test]
+    fn test_format_statements_and_comments() {
+        assert_formats(
+            "-- users\ninsert into users (id, name) values (1, 'a'), (2, 'b')\non conflict (id) do update set name = excluded.name returning id;\n\n\n\
+             update users set name='x' -- rename\nwhere id=1;delete from users where id = 1;",
+            "-- users\n\
+             INSERT INTO users (id, name)\nVALUES (1, 'a'), (2, 'b')\nON CONFLICT (id) DO UPDATE\nSET name = excluded.name\nRETURNING id;\n\n\
+             UPDATE users\nSET name = 'x' -- rename\nWHERE id = 1;\n\
+             DELETE FROM users\nWHERE id = 1;",
+        );
+        // Statements other than queries stay on one line; dollar-quoted bodies are kept as written
+        assert_formats(
+            "grant select on users to reader;\ncreate function f() returns int as $$ select  1 $$ language sql;",
+            "GRANT SELECT ON users TO reader;\nCREATE FUNCTION f() RETURNS INT AS $$ select  1 $$ LANGUAGE sql;",
+        );
+    }
+
+    #[test]
+    fn test_format_create_table() {
+        assert_formats(
+            "create table if not exists users (id serial primary key, -- the key\n\"Name\" varchar(100) not null, balance numeric(10, 2) check (balance >= 0));",
+            "CREATE TABLE IF NOT EXISTS users (\n    id SERIAL PRIMARY KEY, -- the key\n    \"Name\" VARCHAR(100) NOT NULL,\n    balance NUMERIC(10, 2) CHECK (balance >= 0)\n);",
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_string_continuations() {
+        assert_formats(
+            "select 'a'\n'b' as x, 'c' from t",
+            "SELECT 'a'\n    'b' AS x, 'c'\nFROM t",
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use super::lexer::{tokenize, Token, TokenKind};
+
+/// What a stretch of SQL is, for choosing its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Keyword,
+    Identifier,
+    String,
+    /// The body of a dollar-quoted string, usually a function definition
+    DollarString,
+    Number,
+    Comment,
+    Parameter,
+    Operator,
+}
+
+impl Highlight {
+    /// How a token is highlighted; whitespace and punctuation are left plain
+    pub fn of(token: &Token) -> Option<Self> {
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Punctuation => None,
+            TokenKind::LineComment | TokenKind::BlockComment => Some(Highlight::Comment),
+            TokenKind::Word if token.is_keyword() => Some(Highlight::Keyword),
+            TokenKind::Word | TokenKind::QuotedIdentifier => Some(Highlight::Identifier),
+            TokenKind::String => Some(Highlight::String),
+            TokenKind::DollarString => Some(Highlight::DollarString),
+            TokenKind::Number => Some(Highlight::Number),
+            TokenKind::Parameter => Some(Highlight::Parameter),
+            TokenKind::Operator => Some(Highlight::Operator),
+        }
+    }
+}
+
+/// A highlighted part of a line, as byte offsets into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
+    pub highlight: Highlight,
+}
+
+/// Highlights text line by line, caching the result so that only lines from the first
+/// edit onwards are tokenized again, and only as far down as is being shown.
+#[derive(Debug, Default)]
+pub struct Highlighter {
+    /// The lines the spans were worked out for
+    lines: Vec<String>,
+    spans: Vec<Vec<HighlightSpan>>,
+    /// Whether each line starts inside a token (a string or comment) begun on an earlier line
+    continued: Vec<bool>,
+}
+
+impl Highlighter {
+    /// Brings the highlights of the first `end` of `lines` up to date
+    pub fn update(&mut self, lines: &[String], end: usize) {
+        let end = end.min(lines.len());
+        let unchanged = self.lines.iter().zip(&lines[..end]).take_while(|(cached, line)| cached == line).count();
+        if unchanged == end {
+            return;
+        }
+
+        // Tokenizing has to start outside any multi-line token, so back up to a line that is
+        let restart = match self.lines.len() {
+            0 => 0,
+            len => (0..=unchanged.min(len - 1)).rev().find(|&row| !self.continued[row]).unwrap_or(0),
+        };
+        self.lines.truncate(restart);
+        self.spans.truncate(restart);
+        self.continued.truncate(restart);
+
+        let lines = &lines[restart..end];
+        let mut spans = vec![Vec::new(); lines.len()];
+        let mut continued = vec![false; lines.len()];
+        let mut row = 0;
+        let mut line_start = 0;
+        let mut line_end = lines[0].len();
+        for token in tokenize(&lines.join("\n")) {
+            let highlight = Highlight::of(&token);
+            let mut start = token.start;
+            loop {
+                let span_end = token.end().min(line_end);
+                if let Some(highlight) = highlight.filter(|_| span_end > start) {
+                    spans[row].push(HighlightSpan { start: start - line_start, end: span_end - line_start, highlight });
+                }
+                if token.end() <= line_end {
+                    break;
+                }
+                row += 1;
+                line_start = line_end + 1;
+                line_end = line_start + lines[row].len();
+                continued[row] = token.kind != TokenKind::Whitespace;
+                start = line_start;
+            }
+        }
+
+        self.lines.extend(lines.iter().cloned());
+        self.spans.extend(spans);
+        self.continued.extend(continued);
+    }
+
+    /// The highlighted parts of the line at `row`, in order; empty if it hasn't been updated yet
+    pub fn line(&self, row: usize) -> &[HighlightSpan] {
+        self.spans.get(row).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Highlight, Highlighter};
+
+    fn lines(sql: &str) -> Vec<String> {
+        sql.lines().map(String::from).collect()
+    }
+
+    fn highlighted(highlighter: &Highlighter, lines: &[String], row: usize) -> Vec<(Highlight, String)> {
+        highlighter
+            .line(row)
+            .iter()
+            .map(|span| (span.highlight, lines[row][span.start..span.end].to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_lines() {
+        let text = lines("SELECT id, 'a' -- note\nFROM users WHERE n > $1 AND x = :name;");
+        let mut highlighter = Highlighter::default();
+        highlighter.update(&text, text.len());
+        assert_eq!(highlighted(&highlighter, &text, 0), vec![
+            (Highlight::Keyword, "SELECT".into()),
+            (Highlight::Identifier, "id".into()),
+            (Highlight::String, "'a'".into()),
+            (Highlight::Comment, "-- note".into()),
+        ]);
+        assert_eq!(highlighted(&highlighter, &text, 1), vec![
+            (Highlight::Keyword, "FROM".into()),
+            (Highlight::Identifier, "users".into()),
+            (Highlight::Keyword, "WHERE".into()),
+            (Highlight::Identifier, "n".into()),
+            (Highlight::Operator, ">".into()),
+            (Highlight::Parameter, "$1".into()),
+            (Highlight::Keyword, "AND".into()),
+            (Highlight::Identifier, "x".into()),
+            (Highlight::Operator, "=".into()),
+            (Highlight::Parameter, ":name".into()),
+        ]);
+    }
+
+    #[test]
+    fn test_highlight_multiline_tokens_and_edits() {
+        let mut text = lines("SELECT 1;\n/* a\nb */ SELECT $$\nbody\n$$;\nSELECT 2;");
+        let mut highlighter = Highlighter::default();
+        highlighter.update(&text, 3);
+        assert_eq!(highlighted(&highlighter, &text, 2), vec![
+            (Highlight::Comment, "b */".into()),
+            (Highlight::Keyword, "SELECT".into()),
+            (Highlight::DollarString, "$$".into()),
+        ]);
+        assert!(highlighter.line(3).is_empty());
+
+        // Scrolling down highlights the rest, carrying on the dollar-quoted string
+        highlighter.update(&text, text.len());
+        assert_eq!(highlighted(&highlighter, &text, 3), vec![(Highlight::DollarString, "body".into())]);
+        assert_eq!(highlighted(&highlighter, &text, 5)[0], (Highlight::Keyword, "SELECT".into()));
+
+        // Closing the comment early changes how the following lines are highlighted
+        text[1] = "/* a */".to_string();
+        highlighter.update(&text, text.len());
+        assert_eq!(highlighted(&highlighter, &text, 2), vec![
+            (Highlight::Identifier, "b".into()),
+            (Highlight::Operator, "*/".into()),
+            (Highlight::Keyword, "SELECT".into()),
+            (Highlight::DollarString, "$$".into()),
+        ]);
+        assert_eq!(highlighted(&highlighter, &text, 3), vec![(Highlight::DollarString, "body".into())]);
+
+        // Editing inside the string re-tokenizes from where the string starts
+        text[3] = "select 'x'".to_string();
+        highlighter.update(&text, text.len());
+        assert_eq!(highlighted(&highlighter, &text, 3), vec![(Highlight::DollarString, "select 'x'".into())]);
+        assert_eq!(highlighted(&highlighter, &text, 4), vec![(Highlight::DollarString, "$$".into())]);
+    }
+}
@@ -0,0 +1,571 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use sqlx::{postgres::PgPool, Executor as _};
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, Lines},
+    path::Path,
+    time::Duration,
+};
+
+use super::{
+    error::QueryError,
+    lexer::{tokenize, TokenKind},
+    pool::pool_options,
+    statement::quote_identifier,
+};
+
+/// Rows are sent to the server in batches of about this many bytes
+const BATCH_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl ImportFormat {
+    /// Plain JSON isn't accepted, since only JSON Lines can be loaded
+    pub fn new(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" | "tsv" => Ok(ImportFormat::Csv),
+            "jsonl" | "ndjson" => Ok(ImportFormat::JsonLines),
+            "json" => Err(anyhow!("Only JSON Lines (one object per line) can be imported; pass --format jsonl if the file is")),
+            _ => Err(anyhow!("Unsupported import format: {}. Supported formats: csv, tsv, jsonl", s)),
+        }
+    }
+
+    /// Picks the format by the file's extension, e.g. `.csv`, `.tsv` or `.jsonl`
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        match extension.to_lowercase().as_str() {
+            "csv" | "tsv" | "jsonl" | "ndjson" | "json" => Self::new(extension),
+            _ => Err(anyhow!("Can't tell the format of {} from its extension; pass --format csv, tsv or jsonl", path.display())),
+        }
+    }
+}
+
+/// How to read a file and which table to load it into
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// As written in SQL, e.g. `public.users`
+    pub table: String,
+    pub format: ImportFormat,
+    /// Whether the first row of a CSV file names its columns
+    pub header: bool,
+    pub delimiter: u8,
+    /// The file's columns in order, instead of the CSV header; for JSON Lines, the keys to load
+    pub columns: Option<Vec<String>>,
+    /// File columns loaded into table columns of another name; an empty name skips the column
+    pub mappings: Vec<(String, String)>,
+    /// Create the table, with column types inferred from the data
+    pub create: bool,
+}
+
+impl ImportOptions {
+    pub fn new(table: impl Into<String>, format: ImportFormat) -> Self {
+        Self {
+            table: table.into(),
+            format,
+            header: true,
+            delimiter: b',',
+            columns: None,
+            mappings: Vec::new(),
+            create: false,
+        }
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub fn with_mapping(mut self, source: impl Into<String>, column: impl Into<String>) -> Self {
+        self.mappings.push((source.into(), column.into()));
+        self
+    }
+
+    pub fn with_create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub rows: u64,
+    /// The `CREATE TABLE` statement run before loading, with `--create`
+    pub created: Option<String>,
+}
+
+/// Loads `path` into a table with `COPY ... FROM STDIN`, in a transaction with the
+/// `CREATE TABLE` if `options.create` is set, so a failed import leaves nothing behind.
+/// `progress` is called with the number of rows sent so far, after each batch.
+pub async fn import_file(url: &str, path: &Path, options: &ImportOptions, connect_timeout: Duration, mut progress: impl FnMut(u64)) -> Result<ImportSummary> {
+    if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) {
+        bail!("Importing is only supported for PostgreSQL connections");
+    }
+    check_table_name(&options.table)?;
+
+    let records = RecordReader::open(path, options)?;
+    let targets = match &records.columns {
+        Some(columns) => target_columns(columns, &options.mappings)?,
+        None if options.mappings.is_empty() => Vec::new(),
+        None => bail!("Column mappings need column names, from a header row or --columns"),
+    };
+    let loaded: Vec<&str> = targets.iter().flatten().map(String::as_str).collect();
+
+    let create_sql = match options.create {
+        true if loaded.is_empty() => bail!("Creating the table needs column names, from a header row or --columns"),
+        true => {
+            let types = infer_column_types(RecordReader::open(path, options)?, &targets)?;
+            Some(create_table_sql(&options.table, &loaded, &types))
+        }
+        false => None,
+    };
+    let copy_sql = copy_sql(&options.table, &loaded);
+
+    let pool = pool_options(connect_timeout).max_connections(1).connect_lazy(url)?;
+    let rows = copy_records(&pool, create_sql.as_deref(), &copy_sql, records, &targets, &mut progress).await;
+    pool.close().await;
+
+    let rows = rows.map_err(|e| match QueryError::from_server(&e) {
+        Some(query_error) => anyhow::Error::new(query_error),
+        None => e,
+    })?;
+    Ok(ImportSummary { rows, created: create_sql })
+}
+
+async fn copy_records(
+    pool: &PgPool,
+    create_sql: Option<&str>,
+    copy_sql: &str,
+    records: RecordReader,
+    targets: &[Option<String>],
+    progress: &mut impl FnMut(u64),
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    if let Some(sql) = create_sql {
+        tx.execute(sqlx::raw_sql(sql)).await?;
+    }
+
+    let mut copy = tx.copy_in_raw(copy_sql).await?;
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut rows = 0;
+    for record in records {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let _ = copy.abort(e.to_string()).await;
+                return Err(e);
+            }
+        };
+        encode_record(&mut batch, record, targets);
+        rows += 1;
+        if batch.len() >= BATCH_SIZE {
+            copy.send(std::mem::replace(&mut batch, Vec::with_capacity(BATCH_SIZE))).await?;
+            progress(rows);
+        }
+    }
+    if !batch.is_empty() {
+        copy.send(batch).await?;
+    }
+    let rows = copy.finish().await?;
+    tx.commit().await?;
+    progress(rows);
+    Ok(rows)
+}
+
+/// Only plain or quoted identifiers, optionally schema-qualified, are accepted as the table
+fn check_table_name(table: &str) -> Result<()> {
+    let tokens: Vec<_> = tokenize(table).into_iter().filter(|t| !t.is_trivia()).collect();
+    let valid = tokens.len() % 2 == 1 && tokens.iter().enumerate().all(|(i, token)| match i % 2 {
+        0 => matches!(token.kind, TokenKind::Word | TokenKind::QuotedIdentifier),
+        _ => token.kind == TokenKind::Punctuation && token.text == ".",
+    });
+    if !valid {
+        bail!("Invalid table name '{}'; expected e.g. users or public.users", table);
+    }
+    Ok(())
+}
+
+/// The table column each file column is loaded into, or `None` if it is skipped
+fn target_columns(columns: &[String], mappings: &[(String, String)]) -> Result<Vec<Option<String>>> {
+    if let Some((source, _)) = mappings.iter().find(|(source, _)| !columns.contains(source)) {
+        bail!("No column '{}' in the file to map (columns: {})", source, columns.join(", "));
+    }
+    Ok(columns
+        .iter()
+        .map(|column| match mappings.iter().find(|(source, _)| source == column) {
+            Some((_, target)) if target.is_empty() => None,
+            Some((_, target)) => Some(target.clone()),
+            None => Some(column.clone()),
+        })
+        .collect())
+}
+
+fn copy_sql(table: &str, columns: &[&str]) -> String {
+    if columns.is_empty() {
+        return format!("COPY {} FROM STDIN WITH (FORMAT csv)", table);
+    }
+    let columns: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+    format!("COPY {} ({}) FROM STDIN WITH (FORMAT csv)", table, columns.join(", "))
+}
+
+fn create_table_sql(table: &str, columns: &[&str], types: &[ColumnType]) -> String {
+    let definitions: Vec<String> = columns
+        .iter()
+        .zip(types)
+        .map(|(column, column_type)| format!("{} {}", quote_identifier(column), column_type))
+        .collect();
+    format!("CREATE TABLE {} ({})", table, definitions.join(", "))
+}
+
+/// Appends a record in `COPY`'s CSV format: NULL is an empty unquoted field, and
+/// every other value is quoted, so empty strings and delimiters come through as-is.
+fn encode_record(out: &mut Vec<u8>, record: Vec<Field>, targets: &[Option<String>]) {
+    let loaded = record
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| targets.is_empty() || targets.get(*i).is_some_and(Option::is_some));
+    for (n, (_, field)) in loaded.enumerate() {
+        if n > 0 {
+            out.push(b',');
+        }
+        if let Field::Text(value) | Field::Json(value) = field {
+            out.push(b'"');
+            out.extend_from_slice(value.replace('"', "\"\"").as_bytes());
+            out.push(b'"');
+        }
+    }
+    out.push(b'\n');
+}
+
+/// The narrowest type that holds every value of each loaded column; columns without
+/// any values are `text`
+fn infer_column_types(records: RecordReader, targets: &[Option<String>]) -> Result<Vec<ColumnType>> {
+    let mut types: Vec<Option<ColumnType>> = vec![None; targets.len()];
+    for record in records {
+        for (inferred, field) in types.iter_mut().zip(record?) {
+            if let Some(column_type) = ColumnType::of(&field) {
+                *inferred = Some(inferred.map_or(column_type, |t| t.widen(column_type)));
+            }
+        }
+    }
+    Ok(types
+        .into_iter()
+        .zip(targets)
+        .filter(|(_, target)| target.is_some())
+        .map(|(column_type, _)| column_type.unwrap_or(ColumnType::Text))
+        .collect())
+}
+
+/// A column type `--create` can infer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Boolean,
+    Bigint,
+    Numeric,
+    Date,
+    Timestamp,
+    Timestamptz,
+    Jsonb,
+    Text,
+}
+
+impl ColumnType {
+    /// The type of a single value; `None` for NULL, which fits any type
+    fn of(field: &Field) -> Option<Self> {
+        let value = match field {
+            Field::Null => return None,
+            Field::Json(_) => return Some(ColumnType::Jsonb),
+            Field::Text(value) => value.as_str(),
+        };
+        let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+        // Leading zeros are kept, as in zip codes and phone numbers
+        let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+
+        let column_type = if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            ColumnType::Boolean
+        } else if leading_zero {
+            ColumnType::Text
+        } else if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            if value.parse::<i64>().is_ok() { ColumnType::Bigint } else { ColumnType::Numeric }
+        } else if digits.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'-' | b'+')) && value.parse::<f64>().is_ok() {
+            ColumnType::Numeric
+        } else if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() {
+            ColumnType::Date
+        } else if ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"].iter().any(|f| NaiveDateTime::parse_from_str(value, f).is_ok()) {
+            ColumnType::Timestamp
+        } else if DateTime::parse_from_rfc3339(value).is_ok() || DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z").is_ok() {
+            ColumnType::Timestamptz
+        } else {
+            ColumnType::Text
+        };
+        Some(column_type)
+    }
+
+    /// A type that holds values of both types
+    fn widen(self, other: Self) -> Self {
+        use ColumnType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Bigint, Numeric) | (Numeric, Bigint) => Numeric,
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            (Date | Timestamp, Timestamptz) | (Timestamptz, Date | Timestamp) => Timestamptz,
+            _ => Text,
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::Boolean => write!(f, "boolean"),
+            ColumnType::Bigint => write!(f, "bigint"),
+            ColumnType::Numeric => write!(f, "numeric"),
+            ColumnType::Date => write!(f, "date"),
+            ColumnType::Timestamp => write!(f, "timestamp"),
+            ColumnType::Timestamptz => write!(f, "timestamptz"),
+            ColumnType::Jsonb => write!(f, "jsonb"),
+            ColumnType::Text => write!(f, "text"),
+        }
+    }
+}
+
+/// A value read from the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    /// An empty CSV field, or a missing or null JSON value
+    Null,
+    Text(String),
+    /// A JSON object or array, as JSON text
+    Json(String),
+}
+
+impl From<serde_json::Value> for Field {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Field::Null,
+            serde_json::Value::String(s) => Field::Text(s),
+            value @ (serde_json::Value::Array(_) | serde_json::Value::Object(_)) => Field::Json(value.to_string()),
+            value => Field::Text(value.to_string()),
+        }
+    }
+}
+
+/// Reads the records of a file, with a field for each of its columns
+struct RecordReader {
+    source: Source,
+    /// The file's columns, in order; `None` for a CSV file without header or `--columns`
+    columns: Option<Vec<String>>,
+}
+
+enum Source {
+    Csv(csv::StringRecordsIntoIter<File>),
+    JsonLines { lines: Lines<BufReader<File>>, line: usize },
+}
+
+impl RecordReader {
+    fn open(path: &Path, options: &ImportOptions) -> Result<Self> {
+        let open = || File::open(path).with_context(|| format!("Failed to open {}", path.display()));
+        match options.format {
+            ImportFormat::Csv => {
+                let mut records = csv::ReaderBuilder::new()
+                    .delimiter(options.delimiter)
+                    .has_headers(false)
+                    .from_reader(open()?)
+                    .into_records();
+                let header = match options.header {
+                    true => records.next().transpose()?.map(|header| header.iter().map(str::to_string).collect()),
+                    false => None,
+                };
+                Ok(Self { source: Source::Csv(records), columns: options.columns.clone().or(header) })
+            }
+            ImportFormat::JsonLines => {
+                let columns = match &options.columns {
+                    Some(columns) => columns.clone(),
+                    None => json_keys(BufReader::new(open()?).lines())?,
+                };
+                Ok(Self { source: Source::JsonLines { lines: BufReader::new(open()?).lines(), line: 0 }, columns: Some(columns) })
+            }
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Vec<Field>>> {
+        match &mut self.source {
+            Source::Csv(records) => {
+                let Some(record) = records.next().transpose()? else {
+                    return Ok(None);
+                };
+                if let Some(columns) = &self.columns {
+                    if record.len() != columns.len() {
+                        let line = record.position().map_or(0, |p| p.line());
+                        bail!("Line {} has {} fields, expected {} ({})", line, record.len(), columns.len(), columns.join(", "));
+                    }
+                }
+                Ok(Some(record.iter().map(|v| if v.is_empty() { Field::Null } else { Field::Text(v.to_string()) }).collect()))
+            }
+            Source::JsonLines { lines, line } => {
+                let columns = self.columns.as_deref().unwrap_or_default();
+                for text in lines.by_ref() {
+                    *line += 1;
+                    let text = text?;
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    let JsonObject(mut fields) = serde_json::from_str(&text).with_context(|| format!("Line {} is not a JSON object", line))?;
+                    let record = columns
+                        .iter()
+                        .map(|column| match fields.iter().position(|(key, _)| key == column) {
+                            Some(i) => fields.swap_remove(i).1.into(),
+                            None => Field::Null,
+                        })
+                        .collect();
+                    return Ok(Some(record));
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Iterator for RecordReader {
+    type Item = Result<Vec<Field>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Every key of the objects in a JSON Lines file, in the order they first appear
+fn json_keys(lines: impl Iterator<Item = std::io::Result<String>>) -> Result<Vec<String>> {
+    let mut keys: Vec<String> = Vec::new();
+    for (i, text) in lines.enumerate() {
+        let text = text?;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let JsonObject(fields) = serde_json::from_str(&text).with_context(|| format!("Line {} is not a JSON object", i + 1))?;
+        for (key, _) in fields {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// A JSON object with its fields in the order they were written, which
+/// `serde_json::Map` doesn't keep
+struct JsonObject(Vec<(String, serde_json::Value)>);
+
+impl<'de> Deserialize<'de> for JsonObject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ObjectVisitor;
+
+        impl<'de> Visitor<'de> for ObjectVisitor {
+            type Value = JsonObject;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonObject, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(JsonObject(fields))
+            }
+        }
+
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use std::{io::Write, path::Path};
+    use tempfile::NamedTempFile;
+
+    use super::{copy_sql, create_table_sql, encode_record, infer_column_types, target_columns, ColumnType, Field, ImportFormat, ImportOptions, RecordReader};
+
+    fn file_with(contents: &str) -> Result<NamedTempFile> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(contents.as_bytes())?;
+        Ok(file)
+    }
+
+    fn text(value: &str) -> Field {
+        Field::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_infer_column_types() -> Result<()> {
+        let file = file_with("\
+id,name,zip,score,active,joined,seen_at,notes
+1,Ada,02134,9.5,true,2024-01-31,2024-01-31 10:00:00,
+2,Grace,94105,7,FALSE,2024-02-01,2024-02-01T11:30:00+02:00,
+")?;
+        let options = ImportOptions::new("users", ImportFormat::Csv);
+        let records = RecordReader::open(file.path(), &options)?;
+        let targets = target_columns(records.columns.as_deref().unwrap_or_default(), &[])?;
+        let types = infer_column_types(records, &targets)?;
+        assert_eq!(types, [
+            ColumnType::Bigint, ColumnType::Text, ColumnType::Text, ColumnType::Numeric,
+            ColumnType::Boolean, ColumnType::Date, ColumnType::Timestamptz, ColumnType::Text,
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert!(matches!(ImportFormat::from_path(Path::new("data.TSV")), Ok(ImportFormat::Csv)));
+        assert!(matches!(ImportFormat::from_path(Path::new("data.ndjson")), Ok(ImportFormat::JsonLines)));
+        assert!(ImportFormat::from_path(Path::new("data.json")).unwrap_err().to_string().contains("--format jsonl"));
+        assert!(ImportFormat::from_path(Path::new("data.txt")).is_err());
+    }
+
+    #[test]
+    fn test_json_lines_records_and_mappings() -> Result<()> {
+        let file = file_with("{\"name\": \"Ada\", \"id\": 1, \"tags\": [\"admin\"]}\n\n{\"id\": 2, \"name\": null, \"email\": \"grace@example.com\"}\n")?;
+        let options = ImportOptions::new("users", ImportFormat::JsonLines);
+        let mut records = RecordReader::open(file.path(), &options)?;
+        // Keys are taken from every line, in the order they first appear
+        assert_eq!(records.columns.as_deref(), Some(&["name".to_string(), "id".to_string(), "tags".to_string(), "email".to_string()][..]));
+        assert_eq!(records.next().transpose()?, Some(vec![text("Ada"), text("1"), Field::Json("[\"admin\"]".to_string()), Field::Null]));
+        assert_eq!(records.next().transpose()?, Some(vec![Field::Null, text("2"), Field::Null, text("grace@example.com")]));
+        assert_eq!(records.next().transpose()?, None);
+
+        let columns = records.columns.unwrap_or_default();
+        let mappings = [("name".to_string(), "full_name".to_string()), ("tags".to_string(), String::new())];
+        let targets = target_columns(&columns, &mappings)?;
+        let loaded: Vec<&str> = targets.iter().flatten().map(String::as_str).collect();
+        assert_eq!(copy_sql("users", &loaded), "COPY users (\"full_name\", \"id\", \"email\") FROM STDIN WITH (FORMAT csv)");
+        assert_eq!(
+            create_table_sql("users", &loaded, &[ColumnType::Text, ColumnType::Bigint, ColumnType::Text]),
+            "CREATE TABLE users (\"full_name\" text, \"id\" bigint, \"email\" text)"
+        );
+        assert!(target_columns(&columns, &[("nme".to_string(), "name".to_string())]).is_err());
+
+        let mut out = Vec::new();
+        encode_record(&mut out, vec![text("Ada \"the first\""), text("1"), Field::Json("[]".to_string()), Field::Null], &targets);
+        assert_eq!(String::from_utf8(out)?, "\"Ada \"\"the first\"\"\",\"1\",\n");
+        Ok(())
+    }
+}
@@ -2,18 +2,20 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use super::result::QueryResult;
+use super::{cancel::CancelHandle, params::QueryParams, result::{QueryResult, QueryResultStream}, session::Session, timeout::Timeouts};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum SQLType {
     Postgresql,
+    Sqlite,
 }
 
 impl fmt::Display for SQLType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SQLType::Postgresql => write!(f, "postgresql"),
+            SQLType::Sqlite => write!(f, "sqlite"),
         }
     }
 }
@@ -24,12 +26,65 @@ pub fn get_sql_type(conn_type: &str) -> Option<SQLType> {
         "postgresql" => {
             Some(SQLType::Postgresql)
         }
+        "sqlite" => {
+            Some(SQLType::Sqlite)
+        }
         _ => {
             None
         }
     }
 }
 
+/// How a single execution should run, beyond the SQL and the connection
+#[derive(Clone, Default)]
+pub struct ExecutionOptions {
+    /// Values for the `$1`/`:name` placeholders in the SQL
+    pub params: QueryParams,
+    /// Lets another task cancel the execution while it is running
+    pub cancel: Option<CancelHandle>,
+    /// Runs on the session's dedicated connection, without an implicit transaction
+    pub session: Option<Session>,
+    /// Limits on the execution; unset ones fall back to the connection's
+    pub timeouts: Timeouts,
+    /// Rows past this many in a result set are skipped instead of fetched
+    pub max_rows: Option<usize>,
+}
+
+impl ExecutionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_params(mut self, params: QueryParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: CancelHandle) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    pub fn with_session(mut self, session: Option<Session>) -> Self {
+        self.session = session;
+        self
+    }
+
+    pub fn with_timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn with_max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+}
+
 pub trait Executor {
-    fn execute(&self) -> impl std::future::Future<Output = Result<QueryResult>> + Send;
+    /// Runs every statement in the executor's SQL, returning one result per statement
+    fn execute(&self) -> impl std::future::Future<Output = Result<Vec<QueryResult>>> + Send;
+
+    /// Runs every statement in the executor's SQL, yielding rows as they arrive
+    fn execute_stream(&self) -> QueryResultStream;
 }
\ No newline at end of file
@@ -0,0 +1,372 @@
+/// Keywords that start or join the clauses of common statements, sorted. Highlighted in
+/// the editor and offered as completions.
+pub(crate) const CLAUSE_KEYWORDS: &[&str] = &[
+    "ALTER", "AND", "AS", "ASC", "BEGIN", "BETWEEN", "BY", "CASE", "COMMIT", "CREATE", "CROSS", "DELETE",
+    "DESC", "DISTINCT", "DROP", "ELSE", "END", "EXCEPT", "EXISTS", "EXPLAIN", "FALSE", "FROM", "FULL",
+    "GROUP", "HAVING", "ILIKE", "IN", "INNER", "INSERT", "INTERSECT", "INTO", "IS", "JOIN", "LATERAL", "LEFT",
+    "LIKE", "LIMIT", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER", "RETURNING", "RIGHT", "ROLLBACK",
+    "SELECT", "SET", "TABLE", "THEN", "TRUE", "TRUNCATE", "UNION", "UPDATE", "USING", "VALUES", "WHEN",
+    "WHERE", "WITH",
+];
+
+/// The rest of the Postgres keywords, sorted. Words that are keywords to the server but
+/// commonly used as column names (`name`, `type`, `value`, ...) are left out. Highlighted,
+/// but not offered as completions, where they would crowd out the names being typed.
+const KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ANALYZE", "ANY", "ARRAY", "BOTH", "CASCADE", "CAST", "CHECK", "COALESCE", "COLLATE",
+    "COLUMN", "CONCURRENTLY", "CONFLICT", "CONSTRAINT", "COPY", "CURRENT_DATE", "CURRENT_TIMESTAMP",
+    "CURRENT_USER", "DEFAULT", "DEFERRABLE", "DO", "ENUM", "EXCLUDE", "EXECUTE", "EXTENSION", "FETCH",
+    "FILTER", "FIRST", "FOR", "FOREIGN", "FUNCTION", "GRANT", "IF", "INDEX", "KEY", "LANGUAGE", "LAST",
+    "LEADING", "LOCAL", "MATERIALIZED", "NATURAL", "NOTHING", "NULLS", "OF", "ONLY", "OVER", "PARTITION",
+    "PRIMARY", "PROCEDURE", "RECURSIVE", "REFERENCES", "REPLACE", "RESTRICT", "RETURNS", "REVOKE", "ROW",
+    "ROWS", "SAVEPOINT", "SCHEMA", "SEQUENCE", "SOME", "TEMPORARY", "TO", "TRAILING", "TRANSACTION",
+    "TRIGGER", "UNIQUE", "VACUUM", "VIEW", "WINDOW", "WITHOUT",
+];
+
+/// Built-in type names, sorted; highlighted like keywords but not offered as completions
+const TYPE_NAMES: &[&str] = &[
+    "BIGINT", "BIGSERIAL", "BOOLEAN", "CHAR", "DOUBLE", "INT", "INTEGER", "INTERVAL", "JSON", "JSONB",
+    "NUMERIC", "PRECISION", "REAL", "SERIAL", "SMALLINT", "TEXT", "TIMESTAMP", "TIMESTAMPTZ", "UUID",
+    "VARCHAR", "VARYING", "ZONE",
+];
+
+/// Whether `word` is a Postgres keyword or type name, in any case
+pub fn is_keyword(word: &str) -> bool {
+    [CLAUSE_KEYWORDS, KEYWORDS, TYPE_NAMES].iter().any(|words| {
+        words
+            .binary_search_by(|k| k.bytes().cmp(word.bytes().map(|b| b.to_ascii_uppercase())))
+            .is_ok()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    /// A keyword or unquoted identifier
+    Word,
+    QuotedIdentifier,
+    String,
+    DollarString,
+    Number,
+    /// A positional (`$1`) or named (`:name`) placeholder
+    Parameter,
+    Operator,
+    Punctuation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of the token in the source
+    pub start: usize,
+}
+
+impl Token<'_> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment)
+    }
+
+    pub fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    pub fn is_keyword(&self) -> bool {
+        self.kind == TokenKind::Word && is_keyword(self.text)
+    }
+}
+
+/// Splits Postgres SQL into tokens. Never fails: unterminated strings and
+/// comments simply run to the end of the input.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens: Vec<Token<'_>> = Vec::new();
+    let mut pos = 0;
+    // For each open `[`, whether it is a subscript, where `:` separates slice bounds
+    let mut brackets: Vec<bool> = Vec::new();
+
+    while pos < bytes.len() {
+        let start = pos;
+        let c = bytes[pos];
+        let next = bytes.get(pos + 1).copied();
+
+        let kind = match c {
+            b' ' | b'\t' | b'\n' | b'\r' | b'\x0c' => {
+                while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                TokenKind::Whitespace
+            }
+            b'-' if next == Some(b'-') => {
+                pos = sql[pos..].find('\n').map(|i| pos + i).unwrap_or(bytes.len());
+                TokenKind::LineComment
+            }
+            b'/' if next == Some(b'*') => {
+                pos = scan_block_comment(bytes, pos);
+                TokenKind::BlockComment
+            }
+            b'\'' => {
+                pos = scan_quoted(bytes, pos, b'\'', false);
+                TokenKind::String
+            }
+            b'"' => {
+                pos = scan_quoted(bytes, pos, b'"', false);
+                TokenKind::QuotedIdentifier
+            }
+            b'e' | b'E' if next == Some(b'\'') => {
+                pos = scan_quoted(bytes, pos + 1, b'\'', true);
+                TokenKind::String
+            }
+            b'b' | b'B' | b'x' | b'X' | b'n' | b'N' if next == Some(b'\'') => {
+                pos = scan_quoted(bytes, pos + 1, b'\'', false);
+                TokenKind::String
+            }
+            b'$' => match dollar_tag(sql, pos) {
+                Some(tag) => {
+                    let body_start = pos + tag.len();
+                    pos = sql[body_start..]
+                        .find(tag)
+                        .map(|i| body_start + i + tag.len())
+                        .unwrap_or(bytes.len());
+                    TokenKind::DollarString
+                }
+                None if next.is_some_and(|n| n.is_ascii_digit()) => {
+                    pos += 1;
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                    TokenKind::Parameter
+                }
+                None => {
+                    pos += 1;
+                    TokenKind::Operator
+                }
+            },
+            b':' if next == Some(b':') || next == Some(b'=') => {
+                pos += 2;
+                TokenKind::Operator
+            }
+            b':' if next.is_some_and(is_ident_start) && brackets.last() != Some(&true) => {
+                pos += 1;
+                while pos < bytes.len() && is_ident_char(bytes[pos]) {
+                    pos += 1;
+                }
+                TokenKind::Parameter
+            }
+            b'0'..=b'9' => {
+                pos = scan_number(bytes, pos);
+                TokenKind::Number
+            }
+            b'.' if next.is_some_and(|n| n.is_ascii_digit()) => {
+                pos = scan_number(bytes, pos);
+                TokenKind::Number
+            }
+            b'[' => {
+                pos += 1;
+                brackets.push(tokens.iter().rev().find(|t| !t.is_trivia()).is_some_and(is_subscripted));
+                TokenKind::Punctuation
+            }
+            b']' => {
+                pos += 1;
+                brackets.pop();
+                TokenKind::Punctuation
+            }
+            b'(' | b')' | b',' | b';' | b'.' => {
+                pos += 1;
+                TokenKind::Punctuation
+            }
+            c if is_ident_start(c) => {
+                while pos < bytes.len() && is_ident_char(bytes[pos]) {
+                    pos += 1;
+                }
+                TokenKind::Word
+            }
+            c if is_operator_char(c) => {
+                while pos < bytes.len() && is_operator_char(bytes[pos]) {
+                    if bytes[pos] == b'-' && bytes.get(pos + 1) == Some(&b'-') && pos > start {
+                        break;
+                    }
+                    if bytes[pos] == b'/' && bytes.get(pos + 1) == Some(&b'*') && pos > start {
+                        break;
+                    }
+                    if bytes[pos] == b':' && pos > start {
+                        break;
+                    }
+                    pos += 1;
+                }
+                TokenKind::Operator
+            }
+            _ => {
+                // Any other (possibly multi-byte) character stands on its own
+                pos += sql[pos..].chars().next().map(char::len_utf8).unwrap_or(1);
+                TokenKind::Operator
+            }
+        };
+
+        tokens.push(Token { kind, text: &sql[start..pos], start });
+    }
+
+    tokens
+}
+
+/// Whether a `[` after `token` starts a subscript rather than an `ARRAY[...]` constructor
+fn is_subscripted(token: &Token) -> bool {
+    match token.kind {
+        TokenKind::Word => !token.text.eq_ignore_ascii_case("ARRAY"),
+        TokenKind::QuotedIdentifier | TokenKind::Parameter => true,
+        TokenKind::Punctuation => token.text == ")" || token.text == "]",
+        _ => false,
+    }
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c >= 0x80
+}
+
+fn is_ident_char(c: u8) -> bool {
+    is_ident_start(c) || c.is_ascii_digit() || c == b'$'
+}
+
+fn is_operator_char(c: u8) -> bool {
+    matches!(c, b'+' | b'-' | b'*' | b'/' | b'<' | b'>' | b'=' | b'~' | b'!' | b'@' | b'#' | b'%' | b'^' | b'&' | b'|' | b'`' | b'?' | b':')
+}
+
+/// Scans a quoted literal starting at `pos` (the opening quote); doubled quotes are escapes.
+fn scan_quoted(bytes: &[u8], pos: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = pos + 1;
+    while i < bytes.len() {
+        if backslash_escapes && bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn scan_block_comment(bytes: &[u8], pos: usize) -> usize {
+    let mut depth = 0;
+    let mut i = pos;
+    while i < bytes.len() {
+        if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn scan_number(bytes: &[u8], pos: usize) -> usize {
+    let mut i = pos;
+    while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'_') {
+        i += 1;
+    }
+    if i < bytes.len() && bytes[i] == b'.' && bytes.get(i + 1) != Some(&b'.') {
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        let mut j = i + 1;
+        if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+            j += 1;
+        }
+        if j < bytes.len() && bytes[j].is_ascii_digit() {
+            i = j;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+/// Returns the opening `$tag$` of a dollar-quoted string at `pos`, if there is one.
+fn dollar_tag(sql: &str, pos: usize) -> Option<&str> {
+    let bytes = sql.as_bytes();
+    let mut i = pos + 1;
+    if i < bytes.len() && bytes[i].is_ascii_digit() {
+        return None;
+    }
+    while i < bytes.len() && is_ident_char(bytes[i]) && bytes[i] != b'$' {
+        i += 1;
+    }
+    if i < bytes.len() && bytes[i] == b'$' {
+        Some(&sql[pos..=i])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_keyword, tokenize, TokenKind, CLAUSE_KEYWORDS, KEYWORDS, TYPE_NAMES};
+
+    fn kinds(sql: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(sql)
+            .into_iter()
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_literals_and_comments() {
+        assert_eq!(kinds("SELECT 'it''s', E'a\\'b', \"Col\" -- done"), vec![
+            (TokenKind::Word, "SELECT"),
+            (TokenKind::String, "'it''s'"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::String, "E'a\\'b'"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::QuotedIdentifier, "\"Col\""),
+            (TokenKind::LineComment, "-- done"),
+        ]);
+        assert_eq!(kinds("/* a /* nested */ ; */ 1.5e3"), vec![
+            (TokenKind::BlockComment, "/* a /* nested */ ; */"),
+            (TokenKind::Number, "1.5e3"),
+        ]);
+    }
+
+    #[test]
+    fn test_tokenize_dollar_quotes_and_parameters() {
+        assert_eq!(kinds("$body$ select ';' $body$ $$x$$ $1 :name ::int"), vec![
+            (TokenKind::DollarString, "$body$ select ';' $body$"),
+            (TokenKind::DollarString, "$$x$$"),
+            (TokenKind::Parameter, "$1"),
+            (TokenKind::Parameter, ":name"),
+            (TokenKind::Operator, "::"),
+            (TokenKind::Word, "int"),
+        ]);
+    }
+
+    #[test]
+    fn test_is_keyword() {
+        for words in [CLAUSE_KEYWORDS, KEYWORDS, TYPE_NAMES] {
+            assert!(words.windows(2).all(|w| w[0] < w[1]), "keyword lists must stay sorted");
+        }
+        assert!(is_keyword("select") && is_keyword("Timestamptz") && is_keyword("ADD"));
+        assert!(!is_keyword("users") && !is_keyword("name") && !is_keyword(""));
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use sqlx::postgres::{PgListener, PgNotification};
+use std::{io::Write, time::Duration};
+
+use super::{pool::pool_options, result::{write_csv_record, Format}};
+
+/// How long to wait before the first attempt to reconnect; it doubles after each failed attempt
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// A message sent with `NOTIFY` (or `pg_notify()`) on a channel being listened to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notification {
+    #[serde(rename = "timestamp")]
+    pub received_at: DateTime<Local>,
+    pub channel: String,
+    pub payload: String,
+    /// The server process of the session that sent the notification
+    pub process_id: u32,
+}
+
+impl Notification {
+    /// When the notification was received, e.g. `2024-05-01 12:30:05.123`
+    pub fn timestamp(&self) -> String {
+        self.received_at.format(TIMESTAMP_FORMAT).to_string()
+    }
+}
+
+impl From<PgNotification> for Notification {
+    fn from(notification: PgNotification) -> Self {
+        Self {
+            received_at: Local::now(),
+            channel: notification.channel().to_string(),
+            payload: notification.payload().to_string(),
+            process_id: notification.process_id(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenEvent {
+    Notification(Notification),
+    /// The connection was lost; notifications sent until `Reconnected` are missed
+    Disconnected(String),
+    /// Listening again on every channel after the connection was lost
+    Reconnected,
+}
+
+/// Subscribes to channels with `LISTEN` on a dedicated connection, and re-subscribes
+/// when that connection is lost, retrying with a growing delay until the server is back.
+pub struct Listener {
+    url: String,
+    channels: Vec<String>,
+    connect_timeout: Duration,
+    listener: Option<PgListener>,
+    retry_delay: Duration,
+}
+
+impl Listener {
+    /// Starts listening on `channels`. Unlike later reconnects, failing to connect here is an error.
+    pub async fn connect(url: &str, channels: Vec<String>, connect_timeout: Duration) -> Result<Self> {
+        if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) {
+            bail!("LISTEN/NOTIFY is only supported for PostgreSQL connections");
+        }
+        if channels.is_empty() {
+            bail!("No channels to listen on");
+        }
+
+        let mut this = Self {
+            url: url.to_string(),
+            channels,
+            connect_timeout,
+            listener: None,
+            retry_delay: INITIAL_RETRY_DELAY,
+        };
+        this.listener = Some(this.subscribe().await.with_context(|| format!("Failed to listen on {}", this.channels.join(", ")))?);
+        Ok(this)
+    }
+
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    async fn subscribe(&self) -> Result<PgListener> {
+        // The listener only uses its pool to get a connection, and we do the reconnecting
+        let pool = pool_options(self.connect_timeout)
+            .max_connections(1)
+            .max_lifetime(None)
+            .idle_timeout(None)
+            .connect_lazy(&self.url)?;
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.ignore_pool_close_event(true);
+        listener.eager_reconnect(false);
+        listener.listen_all(self.channels.iter().map(String::as_str)).await?;
+        Ok(listener)
+    }
+
+    /// Waits for the next notification, or for the connection to be lost or regained.
+    /// Safe to cancel, e.g. in a `select!`.
+    pub async fn next(&mut self) -> ListenEvent {
+        loop {
+            let Some(listener) = self.listener.as_mut() else {
+                tokio::time::sleep(self.retry_delay).await;
+                match self.subscribe().await {
+                    Ok(listener) => {
+                        self.listener = Some(listener);
+                        self.retry_delay = INITIAL_RETRY_DELAY;
+                        return ListenEvent::Reconnected;
+                    }
+                    Err(_) => {
+                        self.retry_delay = (self.retry_delay * 2).min(MAX_RETRY_DELAY);
+                        continue;
+                    }
+                }
+            };
+
+            let reason = match listener.try_recv().await {
+                Ok(Some(notification)) => return ListenEvent::Notification(notification.into()),
+                Ok(None) => "Connection closed".to_string(),
+                Err(e) => e.to_string(),
+            };
+            self.listener = None;
+            return ListenEvent::Disconnected(reason);
+        }
+    }
+}
+
+/// Prints notifications to `out` as they arrive, one line (or record) each.
+/// `Table` and `Wide` pad the channel column to the longest channel listened on.
+pub struct NotificationPrinter<W: Write> {
+    out: W,
+    format: Format,
+    channel_width: usize,
+    printed_header: bool,
+}
+
+impl<W: Write> NotificationPrinter<W> {
+    pub fn new(out: W, format: Format, channels: &[String]) -> Self {
+        let channel_width = channels.iter().map(|c| c.len()).chain(["channel".len()]).max().unwrap_or_default();
+        Self { out, format, channel_width, printed_header: false }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    pub fn print(&mut self, notification: &Notification) -> Result<()> {
+        let timestamp = notification.timestamp();
+        match self.format {
+            Format::Table | Format::Wide => {
+                if !self.printed_header {
+                    writeln!(self.out, "│ {:<ts$} │ {:<ch$} │ payload", "timestamp", "channel", ts = timestamp.len(), ch = self.channel_width)?;
+                    writeln!(self.out, "├─{:─<ts$}─┼─{:─<ch$}─┼─────────", "", "", ts = timestamp.len(), ch = self.channel_width)?;
+                }
+                writeln!(self.out, "│ {} │ {:<ch$} │ {}", timestamp, notification.channel, notification.payload, ch = self.channel_width)?;
+            }
+            Format::Json => writeln!(self.out, "{}", serde_json::to_string(notification)?)?,
+            Format::Csv => {
+                if !self.printed_header {
+                    write_csv_record(&mut self.out, ["timestamp", "channel", "payload", "process_id"])?;
+                }
+                write_csv_record(&mut self.out, [timestamp, notification.channel.clone(), notification.payload.clone(), notification.process_id.to_string()])?;
+            }
+            Format::Raw => writeln!(self.out, "{}\t{}\t{}", timestamp, notification.channel, notification.payload)?,
+        }
+        self.printed_header = true;
+        // Notifications trickle in, so each is shown as soon as it arrives
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use chrono::{Local, TimeZone};
+
+    use super::{Notification, NotificationPrinter};
+    use crate::sql::result::Format;
+
+    fn notification(channel: &str, payload: &str) -> Notification {
+        Notification {
+            received_at: Local.with_ymd_and_hms(2024, 5, 1, 12, 30, 5).unwrap(),
+            channel: channel.to_string(),
+            payload: payload.to_string(),
+            process_id: 4242,
+        }
+    }
+
+    fn print_all(format: Format, notifications: &[Notification]) -> Result<String> {
+        let channels = vec!["jobs".to_string(), "job_events".to_string()];
+        let mut printer = NotificationPrinter::new(Vec::new(), format, &channels);
+        for n in notifications {
+            printer.print(n)?;
+        }
+        Ok(String::from_utf8(printer.into_inner())?)
+    }
+
+    #[test]
+    fn test_notification_printer_formats() -> Result<()> {
+        let notifications = [notification("jobs", "42"), notification("job_events", "done, ok")];
+
+        assert_eq!(print_all(Format::Table, &notifications)?, "\
+│ timestamp               │ channel    │ payload
+├─────────────────────────┼────────────┼─────────
+│ 2024-05-01 12:30:05.000 │ jobs       │ 42
+│ 2024-05-01 12:30:05.000 │ job_events │ done, ok
+");
+        assert_eq!(print_all(Format::Raw, &notifications)?, "2024-05-01 12:30:05.000\tjobs\t42\n2024-05-01 12:30:05.000\tjob_events\tdone, ok\n");
+        assert_eq!(print_all(Format::Csv, &notifications)?, "\
+timestamp,channel,payload,process_id
+2024-05-01 12:30:05.000,jobs,42,4242
+2024-05-01 12:30:05.000,job_events,\"done, ok\",4242
+");
+
+        let json = print_all(Format::Json, &notifications[..1])?;
+        let value: serde_json::Value = serde_json::from_str(json.trim_end())?;
+        assert_eq!(value["channel"], "jobs");
+        assert_eq!(value["payload"], "42");
+        assert_eq!(value["process_id"], 4242);
+        assert!(value["timestamp"].as_str().is_some_and(|t| t.starts_with("2024-05-01T12:30:05")));
+        Ok(())
+    }
+}
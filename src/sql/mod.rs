@@ -1,4 +1,23 @@
-pub mod factory;
+pub mod backend;
+pub mod cancel;
+pub mod complete;
+pub mod error;
+pub mod explain;
+pub mod format;
+pub mod highlight;
+pub mod import;
 pub mod interface;
+pub mod lexer;
+pub mod listen;
+pub mod notice;
+pub mod params;
+mod pg_decode;
+pub mod pool;
 mod postgresql;
-pub mod result;
\ No newline at end of file
+pub mod result;
+pub mod schema;
+pub mod session;
+mod sqlite;
+pub mod statement;
+pub mod timeout;
+pub mod value;
@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::{
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    field::{Field, Visit},
+    instrument::WithSubscriber,
+    span, Event, Level, Metadata, Subscriber,
+};
+
+/// sqlx reports each notice from the server as a tracing event with this target
+const NOTICE_TARGET: &str = "sqlx::postgres::notice";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum NoticeSeverity {
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl fmt::Display for NoticeSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoticeSeverity::Warning => write!(f, "WARNING"),
+            NoticeSeverity::Notice => write!(f, "NOTICE"),
+            NoticeSeverity::Info => write!(f, "INFO"),
+            NoticeSeverity::Debug => write!(f, "DEBUG"),
+        }
+    }
+}
+
+/// A message the server sent while running a statement, e.g. from `RAISE NOTICE`
+/// or `CREATE TABLE IF NOT EXISTS` on an existing table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notice {
+    pub severity: NoticeSeverity,
+    pub message: String,
+}
+
+impl Notice {
+    pub fn new(severity: NoticeSeverity, message: impl Into<String>) -> Self {
+        Self { severity, message: message.into() }
+    }
+}
+
+/// Formatted like psql, e.g. `NOTICE:  relation "users" already exists, skipping`
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:  {}", self.severity, self.message)
+    }
+}
+
+/// Gathers the notices received while a future it is `capture`-ing runs.
+/// Cheap to clone; clones share notices.
+#[derive(Clone, Default)]
+pub(crate) struct NoticeCollector {
+    notices: Arc<Mutex<Vec<Notice>>>,
+}
+
+impl NoticeCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the notices received while `future` runs; the connection has to be
+    /// polled from within it, as it is by the Postgres executor. The collector is
+    /// only the tracing subscriber while `future` is polled, so logging set up
+    /// elsewhere in the process is left alone.
+    pub async fn capture<F: Future>(&self, future: F) -> F::Output {
+        future.with_subscriber(self.clone()).await
+    }
+
+    /// Hands back the notices collected so far, e.g. those of the statement that just finished
+    pub fn take(&self) -> Vec<Notice> {
+        std::mem::take(&mut *self.notices.lock().unwrap())
+    }
+}
+
+/// sqlx reports each notice as a tracing event; everything else is ignored
+impl Subscriber for NoticeCollector {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == NOTICE_TARGET
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        // Spans are never enabled, so this is never called
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        // sqlx maps the server's severities onto tracing levels
+        let severity = match *event.metadata().level() {
+            Level::ERROR | Level::WARN => NoticeSeverity::Warning,
+            Level::INFO => NoticeSeverity::Notice,
+            Level::DEBUG => NoticeSeverity::Debug,
+            Level::TRACE => NoticeSeverity::Info,
+        };
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        self.notices.lock().unwrap().push(Notice::new(severity, message.0));
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+/// Picks the `message` field out of an event
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{anyhow, Result};
+use std::{collections::BTreeMap, fmt};
+
+use super::lexer::{tokenize, TokenKind};
+
+/// A positional (`$1`) or named (`:name`) placeholder in a query
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Placeholder {
+    Positional(usize),
+    Named(String),
+}
+
+impl Placeholder {
+    fn parse(token: &str) -> Option<Self> {
+        if let Some(index) = token.strip_prefix('$') {
+            index.parse().ok().filter(|&i| i > 0).map(Placeholder::Positional)
+        } else {
+            token.strip_prefix(':').map(|name| Placeholder::Named(name.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placeholder::Positional(index) => write!(f, "${}", index),
+            Placeholder::Named(name) => write!(f, ":{}", name),
+        }
+    }
+}
+
+/// The distinct placeholders used in a script: positional ones in numeric
+/// order, then named ones in order of first appearance.
+pub fn placeholders(sql: &str) -> Vec<Placeholder> {
+    let mut positional = Vec::new();
+    let mut named = Vec::new();
+    for token in tokenize(sql) {
+        if token.kind != TokenKind::Parameter {
+            continue;
+        }
+        match Placeholder::parse(token.text) {
+            Some(p @ Placeholder::Positional(_)) if !positional.contains(&p) => positional.push(p),
+            Some(p @ Placeholder::Named(_)) if !named.contains(&p) => named.push(p),
+            _ => {}
+        }
+    }
+    positional.sort();
+    positional.extend(named);
+    positional
+}
+
+/// The value that binds a placeholder to NULL, as in psql's `COPY` text format
+pub const NULL_VALUE: &str = "\\N";
+
+/// A statement whose placeholders have been renumbered `$1..$n`, with the values to bind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundStatement {
+    pub sql: String,
+    /// None for NULL
+    pub values: Vec<Option<String>>,
+}
+
+/// Values for a query's placeholders. Values are always sent as text and
+/// converted by the server to whatever type the statement expects, except
+/// `NULL_VALUE`, which is sent as NULL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    values: BTreeMap<Placeholder, String>,
+}
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `--param` arguments: `name=value` binds `:name`, anything else
+    /// binds the next positional placeholder.
+    pub fn from_args(args: &[String]) -> Self {
+        let mut params = Self::new();
+        let mut next_position = 1;
+        for arg in args {
+            match arg.split_once('=') {
+                Some((name, value)) if is_identifier(name) => {
+                    params.set(Placeholder::Named(name.to_string()), value.to_string());
+                }
+                _ => {
+                    params.set(Placeholder::Positional(next_position), arg.clone());
+                    next_position += 1;
+                }
+            }
+        }
+        params
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get(&self, placeholder: &Placeholder) -> Option<&str> {
+        self.values.get(placeholder).map(String::as_str)
+    }
+
+    pub fn set(&mut self, placeholder: Placeholder, value: String) {
+        self.values.insert(placeholder, value);
+    }
+
+    /// Placeholders in `sql` that have no value yet
+    pub fn missing(&self, sql: &str) -> Vec<Placeholder> {
+        placeholders(sql)
+            .into_iter()
+            .filter(|p| !self.values.contains_key(p))
+            .collect()
+    }
+
+    /// Rewrites a single statement's placeholders to `$1..$n` in order of first
+    /// use, so positional and named placeholders can be mixed and scripts can
+    /// use different subsets of the parameters in each statement.
+    pub fn bind(&self, sql: &str) -> Result<BoundStatement> {
+        let mut bound = BoundStatement { sql: String::with_capacity(sql.len()), values: Vec::new() };
+        let mut order: Vec<Placeholder> = Vec::new();
+        let mut last = 0;
+
+        for token in tokenize(sql) {
+            let Some(placeholder) = (token.kind == TokenKind::Parameter).then(|| Placeholder::parse(token.text)).flatten() else {
+                continue;
+            };
+            let index = match order.iter().position(|p| *p == placeholder) {
+                Some(i) => i + 1,
+                None => {
+                    let value = self.get(&placeholder).ok_or_else(|| anyhow!("No value given for parameter {}", placeholder))?;
+                    bound.values.push((value != NULL_VALUE).then(|| value.to_string()));
+                    order.push(placeholder);
+                    order.len()
+                }
+            };
+            bound.sql.push_str(&sql[last..token.start]);
+            bound.sql.push_str(&format!("${}", index));
+            last = token.end();
+        }
+
+        bound.sql.push_str(&sql[last..]);
+        Ok(bound)
+    }
+}
+
+/// Casts each `$n` in `sql` from text to the type the server inferred for it
+/// (`types[n - 1]`), leaving text parameters alone.
+pub fn cast_placeholders(sql: &str, types: &[String]) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    for token in tokenize(sql) {
+        let Some(Placeholder::Positional(index)) = (token.kind == TokenKind::Parameter).then(|| Placeholder::parse(token.text)).flatten() else {
+            continue;
+        };
+        let Some(ty) = types.get(index - 1).filter(|ty| *ty != "text") else {
+            continue;
+        };
+        out.push_str(&sql[last..token.end()]);
+        out.push_str(&format!("::text::{}", ty));
+        last = token.end();
+    }
+    out.push_str(&sql[last..]);
+    out
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cast_placeholders, placeholders, Placeholder, QueryParams};
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            placeholders("SELECT $2, :name, $1, '$3', :name, a::int -- :comment"),
+            vec![Placeholder::Positional(1), Placeholder::Positional(2), Placeholder::Named("name".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_from_args() {
+        let params = QueryParams::from_args(&args(&["42", "name=alice", "a=b c", "x = y"]));
+        assert_eq!(params.get(&Placeholder::Positional(1)), Some("42"));
+        assert_eq!(params.get(&Placeholder::Named("name".to_string())), Some("alice"));
+        assert_eq!(params.get(&Placeholder::Named("a".to_string())), Some("b c"));
+        assert_eq!(params.get(&Placeholder::Positional(2)), Some("x = y"));
+    }
+
+    #[test]
+    fn test_bind_renumbers_placeholders() {
+        let params = QueryParams::from_args(&args(&["1", "2", "name=alice"]));
+        let bound = params.bind("SELECT $2, :name, $2, ':name'").unwrap();
+        assert_eq!(bound.sql, "SELECT $1, $2, $1, ':name'");
+        assert_eq!(bound.values, vec![Some("2".to_string()), Some("alice".to_string())]);
+
+        let err = params.bind("SELECT :missing").unwrap_err();
+        assert_eq!(err.to_string(), "No value given for parameter :missing");
+    }
+
+    #[test]
+    fn test_null_value_binds_null() {
+        let params = QueryParams::from_args(&args(&["\\N", "note=\\N", "name=N"]));
+        let bound = params.bind("SELECT $1, :note, :name").unwrap();
+        assert_eq!(bound.values, vec![None, None, Some("N".to_string())]);
+    }
+
+    #[test]
+    fn test_placeholders_skip_array_slices() {
+        assert_eq!(
+            placeholders("SELECT arr[1:n], arr[:n], arr[lo:hi][:m], (arr)[2:k], $1[1:n], ARRAY[:a, :b] FROM t WHERE id = :id"),
+            vec![Placeholder::Positional(1), Placeholder::Named("a".to_string()), Placeholder::Named("b".to_string()), Placeholder::Named("id".to_string())]
+        );
+        let params = QueryParams::from_args(&args(&["id=1"]));
+        assert_eq!(params.bind("SELECT arr[1:n] FROM t WHERE id = :id").unwrap().sql, "SELECT arr[1:n] FROM t WHERE id = $1");
+    }
+
+    #[test]
+    fn test_cast_placeholders() {
+        let types = vec!["integer".to_string(), "text".to_string()];
+        assert_eq!(cast_placeholders("SELECT $1, $2, $1", &types), "SELECT $1::text::integer, $2, $1::text::integer");
+    }
+}
@@ -0,0 +1,678 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use sqlx::{
+    postgres::{PgTypeInfo, PgTypeKind, PgValueFormat, PgValueRef},
+    TypeInfo, ValueRef,
+};
+use std::{
+    fmt::Write,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use super::value::{format_float, Value};
+
+// Built-in type OIDs, from `pg_type.dat`
+const BOOL: u32 = 16;
+const BYTEA: u32 = 17;
+const CHAR: u32 = 18;
+const NAME: u32 = 19;
+const INT8: u32 = 20;
+const INT2: u32 = 21;
+const INT2VECTOR: u32 = 22;
+const INT4: u32 = 23;
+const REGPROC: u32 = 24;
+const TEXT: u32 = 25;
+const OID: u32 = 26;
+const TID: u32 = 27;
+const XID: u32 = 28;
+const CID: u32 = 29;
+const OIDVECTOR: u32 = 30;
+const JSON: u32 = 114;
+const XML: u32 = 142;
+const PG_NODE_TREE: u32 = 194;
+const POINT: u32 = 600;
+const LSEG: u32 = 601;
+const PATH: u32 = 602;
+const BOX: u32 = 603;
+const POLYGON: u32 = 604;
+const LINE: u32 = 628;
+const CIDR: u32 = 650;
+const FLOAT4: u32 = 700;
+const FLOAT8: u32 = 701;
+const UNKNOWN: u32 = 705;
+const CIRCLE: u32 = 718;
+const MACADDR8: u32 = 774;
+const MONEY: u32 = 790;
+const MACADDR: u32 = 829;
+const INET: u32 = 869;
+const BPCHAR: u32 = 1042;
+const VARCHAR: u32 = 1043;
+const DATE: u32 = 1082;
+const TIME: u32 = 1083;
+const TIMESTAMP: u32 = 1114;
+const TIMESTAMPTZ: u32 = 1184;
+const INTERVAL: u32 = 1186;
+const TIMETZ: u32 = 1266;
+const BIT: u32 = 1560;
+const VARBIT: u32 = 1562;
+const NUMERIC: u32 = 1700;
+const REFCURSOR: u32 = 1790;
+const REGPROCEDURE: u32 = 2202;
+const REGOPER: u32 = 2203;
+const REGOPERATOR: u32 = 2204;
+const REGCLASS: u32 = 2205;
+const REGTYPE: u32 = 2206;
+const RECORD: u32 = 2249;
+const CSTRING: u32 = 2275;
+const VOID: u32 = 2278;
+const UUID: u32 = 2950;
+const TXID_SNAPSHOT: u32 = 2970;
+const PG_LSN: u32 = 3220;
+const TSVECTOR: u32 = 3614;
+const REGCONFIG: u32 = 3734;
+const REGDICTIONARY: u32 = 3769;
+const JSONB: u32 = 3802;
+const JSONPATH: u32 = 4072;
+const REGNAMESPACE: u32 = 4089;
+const REGROLE: u32 = 4096;
+const REGCOLLATION: u32 = 4191;
+const PG_SNAPSHOT: u32 = 5038;
+const XID8: u32 = 5069;
+
+/// Range type OIDs and the OIDs of their element types
+const RANGES: &[(u32, u32)] = &[
+    (3904, INT4),
+    (3906, NUMERIC),
+    (3908, TIMESTAMP),
+    (3910, TIMESTAMPTZ),
+    (3912, DATE),
+    (3926, INT8),
+];
+
+/// Multirange type OIDs and the OIDs of their range types
+const MULTIRANGES: &[(u32, u32)] = &[
+    (4451, 3904),
+    (4532, 3906),
+    (4533, 3908),
+    (4534, 3910),
+    (4535, 3912),
+    (4536, 3926),
+];
+
+/// Array types that can appear without type metadata (e.g. as fields of an anonymous record).
+/// Arrays carry their element OID, so only the array OIDs themselves need to be known.
+const ARRAYS: &[u32] = &[
+    143, 199, 629, 651, 719, 775, 791, 1000, 1001, 1002, 1003, 1005, 1006, 1007, 1008, 1009, 1010,
+    1011, 1012, 1013, 1014, 1015, 1016, 1017, 1018, 1019, 1020, 1021, 1022, 1027, 1028, 1040, 1041,
+    1115, 1182, 1183, 1185, 1187, 1231, 1270, 1561, 1563, 2201, 2951, 3221, 3643, 3807, 3905, 3907,
+    3909, 3911, 3913, 3927, 4073,
+];
+
+const MAX_UNKNOWN_LEN: usize = 50;
+
+/// Decodes a column value into a `Value`, rendering types without a dedicated
+/// variant the way Postgres prints them.
+pub fn decode(value: PgValueRef<'_>) -> Value {
+    if value.is_null() {
+        return Value::Null;
+    }
+
+    let type_info = value.type_info().into_owned();
+    match value.format() {
+        PgValueFormat::Text => match value.as_str() {
+            Ok(text) => Value::Text(text.to_string()),
+            Err(_) => unknown(type_info.name()),
+        },
+        PgValueFormat::Binary => match value.as_bytes() {
+            Ok(bytes) => decode_typed(bytes, &type_info),
+            Err(_) => unknown(type_info.name()),
+        },
+    }
+}
+
+/// The type of a nested value: known from the column's metadata, or only by the
+/// OID embedded in the binary value (anonymous records, built-in arrays)
+#[derive(Clone, Copy)]
+enum ElementType<'a> {
+    Info(&'a PgTypeInfo),
+    Oid(u32),
+}
+
+fn decode_element(bytes: &[u8], element_type: ElementType) -> Value {
+    match element_type {
+        ElementType::Info(type_info) => decode_typed(bytes, type_info),
+        ElementType::Oid(oid) => decode_oid(bytes, oid, ""),
+    }
+}
+
+fn decode_typed(bytes: &[u8], type_info: &PgTypeInfo) -> Value {
+    let decoded = match type_info.kind() {
+        PgTypeKind::Enum(_) => text(bytes),
+        PgTypeKind::Domain(base) => return decode_typed(bytes, base),
+        PgTypeKind::Composite(fields) => {
+            let field_types: Vec<ElementType> = fields.iter().map(|(_, ty)| ElementType::Info(ty)).collect();
+            record(bytes, Some(&field_types))
+        }
+        PgTypeKind::Array(element) => array(bytes, Some(ElementType::Info(element))),
+        PgTypeKind::Range(element) => range(bytes, ElementType::Info(element)).map(Value::Text),
+        PgTypeKind::Simple | PgTypeKind::Pseudo => match type_info.oid() {
+            Some(oid) => return decode_oid(bytes, oid.0, type_info.name()),
+            None => None,
+        },
+    };
+    decoded.unwrap_or_else(|| unknown(type_info.name()))
+}
+
+fn decode_oid(bytes: &[u8], oid: u32, type_name: &str) -> Value {
+    let mut r = Reader::new(bytes);
+    let decoded = match oid {
+        BOOL => r.u8().map(|v| Value::Bool(v != 0)),
+        INT2 => r.i16().map(|v| Value::Int(v.into())),
+        INT4 => r.i32().map(|v| Value::Int(v.into())),
+        INT8 => r.i64().map(Value::Int),
+        OID | XID | CID | REGPROC | REGPROCEDURE | REGOPER | REGOPERATOR | REGCLASS | REGTYPE
+        | REGCONFIG | REGDICTIONARY | REGNAMESPACE | REGROLE | REGCOLLATION => r.u32().map(|v| Value::Int(v.into())),
+        XID8 => r.u64().map(|v| i64::try_from(v).map(Value::Int).unwrap_or_else(|_| Value::Numeric(v.to_string()))),
+        // Going through the shortest f32 representation avoids showing widening noise (2.1 -> 2.0999999046325684)
+        FLOAT4 => r.f32().map(|v| Value::Float(v.to_string().parse().unwrap_or(v.into()))),
+        FLOAT8 => r.f64().map(Value::Float),
+        NUMERIC => numeric(&mut r).map(Value::Numeric),
+        MONEY => r.i64().map(|v| Value::Text(money(v))),
+        TEXT | VARCHAR | BPCHAR | NAME | CHAR | XML | UNKNOWN | PG_NODE_TREE | REFCURSOR | CSTRING => text(bytes),
+        VOID => Some(Value::Text(String::new())),
+        BYTEA => Some(Value::Bytes(bytes.to_vec())),
+        JSON => json(bytes),
+        JSONB => r.u8().and_then(|_| json(r.rest())),
+        JSONPATH => r.u8().and_then(|_| text(r.rest())),
+        UUID => (bytes.len() == 16).then(|| Value::Text(uuid(bytes))),
+        DATE => r.i32().map(date),
+        TIME => r.i64().map(time),
+        TIMETZ => r.i64().zip(r.i32()).map(|(micros, zone)| Value::Text(format!("{}{}", time(micros), utc_offset(zone)))),
+        TIMESTAMP => r.i64().map(|micros| timestamp(micros, false)),
+        TIMESTAMPTZ => r.i64().map(|micros| timestamp(micros, true)),
+        INTERVAL => interval(&mut r).map(Value::Text),
+        INET | CIDR => inet(&mut r, oid == CIDR).map(Value::Text),
+        MACADDR | MACADDR8 => Some(Value::Text(macaddr(bytes))),
+        BIT | VARBIT => bits(&mut r).map(Value::Text),
+        POINT | LSEG | PATH | BOX | POLYGON | LINE | CIRCLE => geometry(&mut r, oid).map(Value::Text),
+        TID => r.u32().zip(r.u16()).map(|(block, offset)| Value::Text(format!("({},{})", block, offset))),
+        PG_LSN => r.u64().map(|lsn| Value::Text(format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF))),
+        TXID_SNAPSHOT | PG_SNAPSHOT => snapshot(&mut r).map(Value::Text),
+        TSVECTOR => tsvector(&mut r).map(Value::Text),
+        RECORD => record(bytes, None),
+        INT2VECTOR | OIDVECTOR => array(bytes, None),
+        oid if ARRAYS.contains(&oid) => array(bytes, None),
+        oid => {
+            if let Some(&(_, element)) = RANGES.iter().find(|(range, _)| *range == oid) {
+                range(bytes, ElementType::Oid(element)).map(Value::Text)
+            } else if let Some(&(_, range_oid)) = MULTIRANGES.iter().find(|(multirange, _)| *multirange == oid) {
+                multirange(&mut r, range_oid).map(Value::Text)
+            } else {
+                extension_text(bytes)
+            }
+        }
+    };
+    decoded.unwrap_or_else(|| unknown(type_name))
+}
+
+fn unknown(type_name: &str) -> Value {
+    let label = format!("[{}]", type_name);
+    if label.len() <= MAX_UNKNOWN_LEN {
+        Value::Unknown(label)
+    } else {
+        Value::Unknown(format!("{}...", &label[..MAX_UNKNOWN_LEN]))
+    }
+}
+
+fn text(bytes: &[u8]) -> Option<Value> {
+    std::str::from_utf8(bytes).ok().map(|s| Value::Text(s.to_string()))
+}
+
+/// Many extension types (citext, ltree labels, ...) send plain text; anything that
+/// doesn't look like text is left undecoded.
+fn extension_text(bytes: &[u8]) -> Option<Value> {
+    let s = std::str::from_utf8(bytes).ok()?;
+    if s.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) {
+        return None;
+    }
+    Some(Value::Text(s.to_string()))
+}
+
+fn json(bytes: &[u8]) -> Option<Value> {
+    match serde_json::from_slice(bytes) {
+        Ok(json) => Some(Value::Json(json)),
+        Err(_) => text(bytes),
+    }
+}
+
+fn numeric(r: &mut Reader) -> Option<String> {
+    let ndigits = r.i16()?.max(0) as usize;
+    let weight = r.i16()? as i32;
+    let sign = r.u16()?;
+    let dscale = r.u16()? as usize;
+    match sign {
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+    let digits: Vec<i16> = (0..ndigits).map(|_| r.i16()).collect::<Option<_>>()?;
+    // Each digit is a base-10000 group; `weight` is the position of the first one
+    let digit = |i: i32| usize::try_from(i).ok().and_then(|i| digits.get(i)).copied().unwrap_or(0);
+
+    let mut out = String::new();
+    if sign == 0x4000 {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    } else {
+        write!(out, "{}", digit(0)).ok()?;
+        for i in 1..=weight {
+            write!(out, "{:04}", digit(i)).ok()?;
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            write!(fraction, "{:04}", digit(i)).ok()?;
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+    Some(out)
+}
+
+/// Formats cents the way Postgres does with the default `C` monetary locale
+fn money(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    let dollars = (cents / 100).to_string();
+    let mut grouped = String::new();
+    for (i, c) in dollars.chars().enumerate() {
+        if i > 0 && (dollars.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}${}.{:02}", sign, grouped, cents % 100)
+}
+
+fn uuid(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+fn epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()
+}
+
+fn date(days: i32) -> Value {
+    match days {
+        i32::MAX => Value::Text("infinity".to_string()),
+        i32::MIN => Value::Text("-infinity".to_string()),
+        days => epoch()
+            .date()
+            .checked_add_signed(Duration::days(days.into()))
+            .map(Value::Date)
+            .unwrap_or_else(|| unknown("DATE")),
+    }
+}
+
+fn time(micros: i64) -> Value {
+    let seconds = micros.div_euclid(1_000_000);
+    let nanos = micros.rem_euclid(1_000_000) * 1000;
+    match NaiveTime::from_num_seconds_from_midnight_opt(seconds as u32, nanos as u32) {
+        Some(time) if seconds < 86_400 => Value::Time(time),
+        // Postgres allows 24:00:00, which chrono can't represent
+        _ => Value::Text(format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)),
+    }
+}
+
+/// Postgres stores time zones as seconds west of UTC and prints them as `+HH[:MM[:SS]]`
+fn utc_offset(seconds_west: i32) -> String {
+    let offset = -seconds_west;
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    let mut out = format!("{}{:02}", sign, offset / 3600);
+    if offset % 3600 != 0 {
+        write!(out, ":{:02}", offset / 60 % 60).unwrap();
+        if offset % 60 != 0 {
+            write!(out, ":{:02}", offset % 60).unwrap();
+        }
+    }
+    out
+}
+
+fn timestamp(micros: i64, with_time_zone: bool) -> Value {
+    match micros {
+        i64::MAX => Value::Text("infinity".to_string()),
+        i64::MIN => Value::Text("-infinity".to_string()),
+        micros => match epoch().checked_add_signed(Duration::microseconds(micros)) {
+            Some(ts) if with_time_zone => Value::TimestampTz(ts.and_utc()),
+            Some(ts) => Value::Timestamp(ts),
+            None => unknown(if with_time_zone { "TIMESTAMPTZ" } else { "TIMESTAMP" }),
+        },
+    }
+}
+
+/// Formats an interval using Postgres' default `postgres` IntervalStyle,
+/// e.g. `1 year 2 mons 3 days 04:05:06.5` or `-1 days +02:00:00`
+fn interval(r: &mut Reader) -> Option<String> {
+    let micros = r.i64()?;
+    let days = r.i32()?;
+    let months = r.i32()?;
+
+    let mut out = String::new();
+    let mut is_zero = true;
+    let mut is_before = false;
+    for (value, unit) in [(months / 12, "year"), (months % 12, "mon"), (days, "day")] {
+        if value == 0 {
+            continue;
+        }
+        write!(
+            out,
+            "{}{}{} {}{}",
+            if is_zero { "" } else { " " },
+            if is_before && value > 0 { "+" } else { "" },
+            value,
+            unit,
+            if value != 1 { "s" } else { "" },
+        ).ok()?;
+        is_before = value < 0;
+        is_zero = false;
+    }
+
+    if is_zero || micros != 0 {
+        let sign = if micros < 0 { "-" } else if is_before { "+" } else { "" };
+        let micros = micros.unsigned_abs();
+        let seconds = micros / 1_000_000;
+        write!(
+            out,
+            "{}{}{:02}:{:02}:{:02}",
+            if is_zero { "" } else { " " },
+            sign,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+        ).ok()?;
+        let fraction = micros % 1_000_000;
+        if fraction != 0 {
+            write!(out, ".{}", format!("{:06}", fraction).trim_end_matches('0')).ok()?;
+        }
+    }
+    Some(out)
+}
+
+fn inet(r: &mut Reader, is_cidr_type: bool) -> Option<String> {
+    let family = r.u8()?;
+    let bits = r.u8()?;
+    let is_cidr = r.u8()? != 0 || is_cidr_type;
+    let len = r.u8()? as usize;
+    let addr = r.take(len)?;
+    let (addr, max_bits) = match family {
+        2 => (Ipv4Addr::from(<[u8; 4]>::try_from(addr).ok()?).to_string(), 32),
+        3 => (Ipv6Addr::from(<[u8; 16]>::try_from(addr).ok()?).to_string(), 128),
+        _ => return None,
+    };
+    if is_cidr || bits != max_bits {
+        Some(format!("{}/{}", addr, bits))
+    } else {
+        Some(addr)
+    }
+}
+
+fn macaddr(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
+}
+
+fn bits(r: &mut Reader) -> Option<String> {
+    let len = r.i32()?.max(0) as usize;
+    let bytes = r.rest();
+    (0..len)
+        .map(|i| bytes.get(i / 8).map(|byte| if byte >> (7 - i % 8) & 1 == 1 { '1' } else { '0' }))
+        .collect()
+}
+
+fn geometry(r: &mut Reader, oid: u32) -> Option<String> {
+    fn point(r: &mut Reader) -> Option<String> {
+        Some(format!("({},{})", format_float(r.f64()?), format_float(r.f64()?)))
+    }
+    fn points(r: &mut Reader) -> Option<String> {
+        let count = r.i32()?.max(0);
+        Some((0..count).map(|_| point(r)).collect::<Option<Vec<_>>>()?.join(","))
+    }
+
+    match oid {
+        POINT => point(r),
+        LSEG => Some(format!("[{},{}]", point(r)?, point(r)?)),
+        BOX => Some(format!("{},{}", point(r)?, point(r)?)),
+        LINE => Some(format!("{{{},{},{}}}", format_float(r.f64()?), format_float(r.f64()?), format_float(r.f64()?))),
+        PATH => {
+            let closed = r.u8()? != 0;
+            let points = points(r)?;
+            Some(if closed { format!("({})", points) } else { format!("[{}]", points) })
+        }
+        POLYGON => Some(format!("({})", points(r)?)),
+        CIRCLE => Some(format!("<{},{}>", point(r)?, format_float(r.f64()?))),
+        _ => None,
+    }
+}
+
+fn snapshot(r: &mut Reader) -> Option<String> {
+    let count = r.i32()?.max(0);
+    let xmin = r.u64()?;
+    let xmax = r.u64()?;
+    let in_progress: Vec<String> = (0..count).map(|_| r.u64().map(|xid| xid.to_string())).collect::<Option<_>>()?;
+    Some(format!("{}:{}:{}", xmin, xmax, in_progress.join(",")))
+}
+
+fn tsvector(r: &mut Reader) -> Option<String> {
+    let count = r.i32()?.max(0);
+    let mut lexemes = Vec::new();
+    for _ in 0..count {
+        let lexeme = std::str::from_utf8(r.cstring()?).ok()?;
+        let mut out = format!("'{}'", lexeme.replace('\\', "\\\\").replace('\'', "''"));
+        let positions: Vec<String> = (0..r.u16()?)
+            .map(|_| {
+                r.u16().map(|p| {
+                    let weight = match p >> 14 {
+                        3 => "A",
+                        2 => "B",
+                        1 => "C",
+                        _ => "",
+                    };
+                    format!("{}{}", p & 0x3FFF, weight)
+                })
+            })
+            .collect::<Option<_>>()?;
+        if !positions.is_empty() {
+            write!(out, ":{}", positions.join(",")).ok()?;
+        }
+        lexemes.push(out);
+    }
+    Some(lexemes.join(" "))
+}
+
+/// Quotes a composite field or range bound if Postgres would
+fn quote_if_needed(s: &str, special: &[char]) -> String {
+    if !s.is_empty() && !s.chars().any(|c| c.is_whitespace() || special.contains(&c)) {
+        return s.to_string();
+    }
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn record(bytes: &[u8], field_types: Option<&[ElementType]>) -> Option<Value> {
+    let mut r = Reader::new(bytes);
+    let count = r.i32()?.max(0) as usize;
+    let mut fields = Vec::with_capacity(count);
+    for i in 0..count {
+        let oid = r.u32()?;
+        let field = match r.value()? {
+            None => String::new(),
+            Some(bytes) => {
+                let field_type = field_types.and_then(|types| types.get(i).copied()).unwrap_or(ElementType::Oid(oid));
+                quote_if_needed(&decode_element(bytes, field_type).to_string(), &['"', '\\', '(', ')', ','])
+            }
+        };
+        fields.push(field);
+    }
+    Some(Value::Text(format!("({})", fields.join(","))))
+}
+
+fn array(bytes: &[u8], element_type: Option<ElementType>) -> Option<Value> {
+    let mut r = Reader::new(bytes);
+    let dimensions = r.i32()?.max(0) as usize;
+    let _has_nulls = r.i32()?;
+    let element_oid = r.u32()?;
+    let element_type = element_type.unwrap_or(ElementType::Oid(element_oid));
+    let lengths: Vec<usize> = (0..dimensions)
+        .map(|_| {
+            let len = r.i32()?.max(0) as usize;
+            r.i32()?; // lower bound
+            Some(len)
+        })
+        .collect::<Option<_>>()?;
+
+    if dimensions == 0 {
+        return Some(Value::Array(Vec::new()));
+    }
+    array_dimension(&mut r, &lengths, element_type)
+}
+
+/// Reads the elements of one dimension of a (possibly multidimensional) array
+fn array_dimension(r: &mut Reader, lengths: &[usize], element_type: ElementType) -> Option<Value> {
+    let (len, inner) = lengths.split_first()?;
+    let items = (0..*len)
+        .map(|_| {
+            if inner.is_empty() {
+                r.value().map(|v| v.map(|bytes| decode_element(bytes, element_type)).unwrap_or(Value::Null))
+            } else {
+                array_dimension(r, inner, element_type)
+            }
+        })
+        .collect::<Option<_>>()?;
+    Some(Value::Array(items))
+}
+
+fn range(bytes: &[u8], element_type: ElementType) -> Option<String> {
+    const EMPTY: u8 = 0x01;
+    const LOWER_INCLUSIVE: u8 = 0x02;
+    const UPPER_INCLUSIVE: u8 = 0x04;
+    const LOWER_INFINITE: u8 = 0x08;
+    const UPPER_INFINITE: u8 = 0x10;
+
+    let mut r = Reader::new(bytes);
+    let flags = r.u8()?;
+    if flags & EMPTY != 0 {
+        return Some("empty".to_string());
+    }
+
+    let mut bound = |infinite: u8| -> Option<String> {
+        if flags & infinite != 0 {
+            return Some(String::new());
+        }
+        let value = decode_element(r.value()??, element_type).to_string();
+        Some(quote_if_needed(&value, &['"', '\\', '(', ')', '[', ']', ',']))
+    };
+    let lower = bound(LOWER_INFINITE)?;
+    let upper = bound(UPPER_INFINITE)?;
+
+    Some(format!(
+        "{}{},{}{}",
+        if flags & LOWER_INCLUSIVE != 0 { '[' } else { '(' },
+        lower,
+        upper,
+        if flags & UPPER_INCLUSIVE != 0 { ']' } else { ')' },
+    ))
+}
+
+fn multirange(r: &mut Reader, range_oid: u32) -> Option<String> {
+    let element = RANGES.iter().find(|(range, _)| *range == range_oid).map(|(_, element)| *element)?;
+    let count = r.i32()?.max(0);
+    let ranges: Vec<String> = (0..count)
+        .map(|_| range(r.value()??, ElementType::Oid(element)))
+        .collect::<Option<_>>()?;
+    Some(format!("{{{}}}", ranges.join(",")))
+}
+
+/// Reads big-endian values from Postgres' binary wire format
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < n {
+            return None;
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Some(head)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.buf)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N).and_then(|bytes| bytes.try_into().ok())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.array::<1>().map(|b| b[0])
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.array().map(i16::from_be_bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.array().map(i32::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.array().map(i64::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.array().map(f32::from_be_bytes)
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.array().map(f64::from_be_bytes)
+    }
+
+    /// A length-prefixed value; `Some(None)` is SQL NULL
+    fn value(&mut self) -> Option<Option<&'a [u8]>> {
+        match self.i32()? {
+            len if len < 0 => Some(None),
+            len => self.take(len as usize).map(Some),
+        }
+    }
+
+    fn cstring(&mut self) -> Option<&'a [u8]> {
+        let end = self.buf.iter().position(|&b| b == 0)?;
+        let s = self.take(end)?;
+        self.take(1)?;
+        Some(s)
+    }
+}
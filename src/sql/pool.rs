@@ -0,0 +1,100 @@
+use anyhow::Result;
+use sqlx::{pool::PoolConnection, postgres::{PgPool, PgPoolOptions}, Postgres};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+tokio::task_local! {
+    /// Set by `after_connect` when a connection is opened while `acquire` waits
+    static OPENED: Cell<bool>;
+}
+
+struct PoolEntry {
+    url: String,
+    connect_timeout: Duration,
+    pool: PgPool,
+}
+
+/// Keeps one lazily-connected pool per connection so repeated executions
+/// skip the TCP/TLS/auth handshake. Cheap to clone; clones share the pools.
+#[derive(Clone, Default)]
+pub struct PoolManager {
+    pools: Arc<Mutex<HashMap<String, PoolEntry>>>,
+}
+
+/// `connect_timeout` bounds how long getting a connection from the pool may take
+pub fn pool_options(connect_timeout: Duration) -> PgPoolOptions {
+    PgPoolOptions::new()
+        .max_connections(10)
+        .acquire_timeout(connect_timeout)
+        .after_connect(|_, _| Box::pin(async {
+            // Connections opened outside `acquire` (e.g. by pool maintenance) have no flag to set
+            let _ = OPENED.try_with(|opened| opened.set(true));
+            Ok(())
+        }))
+}
+
+/// Takes a connection from `pool`, along with how long opening it took.
+/// The time is zero when an idle connection was reused.
+pub async fn acquire(pool: &PgPool) -> Result<(PoolConnection<Postgres>, Duration)> {
+    let start = Instant::now();
+    OPENED.scope(Cell::new(false), async {
+        let conn = pool.acquire().await?;
+        let connect_time = if OPENED.with(Cell::get) { start.elapsed() } else { Duration::ZERO };
+        Ok((conn, connect_time))
+    }).await
+}
+
+impl PoolManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the pool for `key`, creating it if needed. If the URL or connect timeout
+    /// for the key changed (e.g. a different password was entered), the old pool is replaced.
+    pub fn get_or_create(&self, key: &str, url: &str, connect_timeout: Duration) -> Result<PgPool> {
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(entry) = pools.get(key) {
+            if entry.url == url && entry.connect_timeout == connect_timeout {
+                return Ok(entry.pool.clone());
+            }
+        }
+
+        let pool = pool_options(connect_timeout).connect_lazy(url)?;
+        let entry = PoolEntry { url: url.to_string(), connect_timeout, pool: pool.clone() };
+        if let Some(old) = pools.insert(key.to_string(), entry) {
+            close_in_background(old.pool);
+        }
+        Ok(pool)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.pools.lock().unwrap().contains_key(key)
+    }
+
+    /// Drops the pool for `key`, closing its connections in the background.
+    pub fn close(&self, key: &str) {
+        let entry = self.pools.lock().unwrap().remove(key);
+        if let Some(entry) = entry {
+            close_in_background(entry.pool);
+        }
+    }
+
+    /// Closes every pool and waits for their connections to shut down.
+    pub async fn close_all(&self) {
+        let entries: Vec<PoolEntry> = self.pools.lock().unwrap().drain().map(|(_, entry)| entry).collect();
+        for entry in entries {
+            entry.pool.close().await;
+        }
+    }
+}
+
+fn close_in_background(pool: PgPool) {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn(async move { pool.close().await });
+    }
+}
+
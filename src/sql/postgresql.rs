@@ -1,117 +1,360 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc, NaiveDate};
-use sqlx::{postgres::{PgPoolOptions, PgRow}, Column, Row, ValueRef, TypeInfo};
+use futures::{future::BoxFuture, TryStreamExt};
+use sqlx::{postgres::{types::Oid, PgConnection, PgPool, PgRow}, Column, Connection, Either, Executor as _, Row, Statement, TypeInfo};
 use std::time::{Duration, Instant};
-use super::{interface::Executor, result::QueryResult};
+use tokio::sync::mpsc;
+use super::{backend::{Backend, Target}, cancel::{CancelHandle, Interrupt, QueryCancelled}, error::QueryError, interface::{ExecutionOptions, Executor}, notice::NoticeCollector, params::{cast_placeholders, placeholders, QueryParams}, pg_decode, pool::{acquire, pool_options, PoolManager}, result::{send, EventSender, QueryResult, QueryResultStream, StreamEvent, STREAM_BUFFER}, schema::{load_postgres, DatabaseSchema}, session::{Session, TransactionStatus}, statement::{changes_rows, command_tag, limit_rows, split_statements, LIMIT_PREFIX}, timeout::{QueryTimedOut, Timeouts}, value::Value};
 
+/// The OID of the `text` type
+const TEXT_OID: u32 = 25;
+
+#[derive(Clone)]
 pub struct PostgresExecutor {
     pub url: String,
     pub sql: String,
+    /// A long-lived pool to run on; when absent a one-off pool is created for this execution.
+    pub pool: Option<PgPool>,
+    /// Lets another task cancel this execution while it is running.
+    pub cancel: Option<CancelHandle>,
+    /// Values for the `$1`/`:name` placeholders in `sql`.
+    pub params: QueryParams,
+    /// Runs on the session's connection instead of in a transaction of its own.
+    pub session: Option<Session>,
+    /// Statement and lock timeouts are set for each execution; the connect
+    /// timeout applies to one-off pools and session connections.
+    pub timeouts: Timeouts,
+    /// Plain queries are limited to this many rows on the server; other statements' extra rows are skipped.
+    pub max_rows: Option<usize>,
+}
+
+/// Runs queries on Postgres, keeping a pool of connections for each target key
+#[derive(Clone, Default)]
+pub struct PostgresBackend {
+    pools: PoolManager,
+}
+
+impl Backend for PostgresBackend {
+    fn name(&self) -> &'static str {
+        "postgresql"
+    }
+
+    fn handles(&self, url: &str) -> bool {
+        url.starts_with("postgres://") || url.starts_with("postgresql://")
+    }
+
+    fn connect<'a>(&'a self, target: &'a Target, timeouts: Timeouts) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let pool = self.pools.get_or_create(&target.key, &target.url, timeouts.connect_or_default())?;
+            pool.acquire().await?;
+            Ok(())
+        })
+    }
+
+    fn execute_stream(&self, target: &Target, sql: String, options: ExecutionOptions) -> Result<QueryResultStream> {
+        let pool = self.pools.get_or_create(&target.key, &target.url, options.timeouts.connect_or_default())?;
+        Ok(PostgresExecutor::new(target.url.clone(), sql, Some(pool), options).execute_stream())
+    }
+
+    fn schema<'a>(&'a self, target: &'a Target, timeouts: Timeouts) -> BoxFuture<'a, Result<DatabaseSchema>> {
+        Box::pin(async move {
+            let pool = self.pools.get_or_create(&target.key, &target.url, timeouts.connect_or_default())?;
+            load_postgres(&pool).await
+        })
+    }
+
+    fn close(&self, key: &str) {
+        self.pools.close(key);
+    }
+
+    fn close_all(&self) -> BoxFuture<'_, ()> {
+        Box::pin(self.pools.close_all())
+    }
+}
+
+/// Asks the server to abort whatever the backend with `backend_pid` is running
+struct CancelBackend {
+    pool: PgPool,
+    backend_pid: i32,
+}
+
+impl Interrupt for CancelBackend {
+    fn interrupt(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            sqlx::query("SELECT pg_cancel_backend($1)")
+                .bind(self.backend_pid)
+                .execute(&self.pool)
+                .await?;
+            Ok(())
+        })
+    }
 }
 
 impl Executor for PostgresExecutor {
-    async fn execute(&self) -> Result<QueryResult> {
-        let start_time = Instant::now();
-        
-        let pool = PgPoolOptions::new()
-            .max_connections(10)
-            .acquire_timeout(Duration::from_secs(5))
-            .connect(&self.url).await?;
-
-        let mut tx = pool.begin().await?;
-            
-        let rows = sqlx::query(&self.sql)
-            .fetch_all(&mut *tx)
-            .await?;
-            
-        let mut columns = Vec::new();
-        let mut result_rows = Vec::new();
-        
-        if !rows.is_empty() {
-            columns = rows[0].columns()
-                .iter()
-                .map(|c| c.name().to_string())
-                .collect();
-                
-                for row in &rows {
-                    let values: Vec<String> = row.columns()
-                        .iter()
-                        .enumerate()
-                        .map(|(i, col)| {
-                            convert_pg_value_to_string(row, i, col)
-                        })
-                        .collect();
-                    result_rows.push(values);
+    async fn execute(&self) -> Result<Vec<QueryResult>> {
+        self.execute_stream().collect().await
+    }
+
+    fn execute_stream(&self) -> QueryResultStream {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let executor = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = executor.run(&sender).await {
+                let _ = sender.send(Err(e)).await;
+            }
+        });
+        QueryResultStream::new(receiver)
+    }
+}
+
+impl PostgresExecutor {
+    pub fn new(url: String, sql: String, pool: Option<PgPool>, options: ExecutionOptions) -> Self {
+        let ExecutionOptions { params, cancel, session, timeouts, max_rows } = options;
+        Self {
+            url,
+            sql,
+            pool,
+            cancel,
+            params,
+            session,
+            timeouts,
+            max_rows,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelHandle::is_cancelled)
+    }
+
+    async fn run(&self, sender: &EventSender) -> Result<()> {
+        let pool = match &self.pool {
+            Some(pool) => pool.clone(),
+            None => pool_options(self.timeouts.connect_or_default()).connect_lazy(&self.url)?,
+        };
+
+        let notices = NoticeCollector::new();
+        let outcome = notices.capture(async {
+            match &self.session {
+                Some(session) => self.run_in_session(session, &pool, &notices, sender).await,
+                None => self.run_in_transaction(&pool, &notices, sender).await,
+            }
+        }).await;
+
+        if self.pool.is_none() {
+            pool.close().await;
+        }
+        outcome.map_err(|e| match QueryTimedOut::from_error(&e, &self.timeouts) {
+            Some(timed_out) => e.context(timed_out),
+            None => e,
+        })
+    }
+
+    /// Runs the script on a pooled connection, inside a transaction that is
+    /// committed once every statement succeeds.
+    async fn run_in_transaction(&self, pool: &PgPool, notices: &NoticeCollector, sender: &EventSender) -> Result<()> {
+        let (mut conn, connect_time) = acquire(pool).await?;
+        self.register_cancel(pool, &mut conn).await?;
+
+        let outcome = async {
+            let mut tx = conn.begin().await?;
+            // Scoped to the transaction, so they don't linger on the pooled connection
+            if let Some(settings) = self.timeouts.settings_sql(true) {
+                tx.execute(sqlx::raw_sql(&settings)).await?;
+            }
+            self.execute_statements(&mut tx, connect_time, notices, sender).await?;
+            tx.commit().await?;
+            Ok(())
+        }.await;
+
+        self.finish_cancel();
+        // Hand the connection back before the stream ends, so the next execution can reuse it
+        conn.return_to_pool().await;
+        outcome
+    }
+
+    /// Runs the script as-is on the session's connection, so transaction
+    /// control statements carry over to later executions.
+    async fn run_in_session(&self, session: &Session, pool: &PgPool, notices: &NoticeCollector, sender: &EventSender) -> Result<()> {
+        let mut guard = session.acquire(&self.url, self.timeouts.connect_or_default()).await?;
+        let session_conn = guard.as_mut().expect("session is connected once acquired");
+        let connect_time = std::mem::take(&mut session_conn.connect_time);
+        let conn = &mut session_conn.conn;
+        // A failed transaction rejects everything but ROLLBACK, so the timeouts
+        // already set on the connection are left as they are
+        if session.status() != TransactionStatus::Failed {
+            if let Some(settings) = self.timeouts.settings_sql(false) {
+                conn.execute(sqlx::raw_sql(&settings)).await?;
+            }
+        }
+        if let Some(cancel) = &self.cancel {
+            cancel.register(CancelBackend { pool: pool.clone(), backend_pid: session_conn.backend_pid });
+        }
+
+        let outcome = self.execute_statements(conn, connect_time, notices, sender).await;
+        self.finish_cancel();
+
+        // The execution's own error matters more than a failure to read the status
+        let status = session.refresh_status(conn).await;
+        outcome.and(status.map(|_| ()))
+    }
+
+    async fn register_cancel(&self, pool: &PgPool, conn: &mut PgConnection) -> Result<()> {
+        if let Some(cancel) = &self.cancel {
+            let backend_pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+                .fetch_one(&mut *conn)
+                .await?;
+            cancel.register(CancelBackend { pool: pool.clone(), backend_pid });
+        }
+        Ok(())
+    }
+
+    fn finish_cancel(&self) {
+        if let Some(cancel) = &self.cancel {
+            cancel.finish();
+        }
+    }
+
+    async fn execute_statements(&self, conn: &mut PgConnection, mut connect_time: Duration, notices: &NoticeCollector, sender: &EventSender) -> Result<()> {
+        let statements = split_statements(&self.sql);
+
+        for (i, statement) in statements.iter().enumerate() {
+            if self.is_cancelled() {
+                return Err(QueryCancelled.into());
+            }
+            // Asking for one row past the limit tells whether the query had more
+            let limited = self.max_rows.and_then(|max| limit_rows(statement.text, max + 1));
+            let summary = match execute_statement(conn, statement.text, limited.as_deref(), &self.params, self.max_rows, sender).await {
+                Ok(summary) => summary,
+                // The server reports the cancel as an error on the running statement
+                Err(_) if self.is_cancelled() => return Err(QueryCancelled.into()),
+                Err(e) => {
+                    // Placeholders are rewritten before sending, which would throw off the error position
+                    let offset = match limited {
+                        Some(_) => LIMIT_PREFIX.chars().count(),
+                        None => 0,
+                    };
+                    let offset = placeholders(statement.text).is_empty().then_some(offset);
+                    let e = match QueryError::from_error(&e, &self.sql, statement, offset) {
+                        Some(query_error) => anyhow::Error::new(query_error),
+                        None => e,
+                    };
+                    if statements.len() > 1 {
+                        return Err(e.context(format!("Statement {} of {} failed", i + 1, statements.len())));
+                    }
+                    return Err(e);
                 }
+            };
+            let fetch_time = summary.fetch_time;
+            let summary = summary
+                .with_timings(std::mem::take(&mut connect_time), fetch_time)
+                .with_notices(notices.take());
+            send(sender, StreamEvent::Done(summary)).await?;
         }
-        
-        tx.commit().await?;
-        
-        let execution_time = start_time.elapsed();
-        Ok(QueryResult::new(columns, result_rows, execution_time))
+
+        Ok(())
     }
 }
 
-fn convert_pg_value_to_string(row: &PgRow, idx: usize, col: &sqlx::postgres::PgColumn) -> String {
-    let type_info = col.type_info();
-    let type_name = type_info.name();
-
-    match type_name {
-        _ if row.try_get_raw(idx).map(|v| v.is_null()).unwrap_or(true) => "NULL".to_string(),        
-        "INT2" | "SMALLINT" => row.try_get::<i16, _>(idx).map(|v: i16| v.to_string()).unwrap_or_else(|_| "NULL".to_string()),
-        "INT4" | "INT" => row.try_get::<i32, _>(idx).map(|v| v.to_string()).unwrap_or_else(|_| "NULL".to_string()),
-        "INT8" | "BIGINT" => row.try_get::<i64, _>(idx).map(|v| v.to_string()).unwrap_or_else(|_| "NULL".to_string()),
-        "FLOAT4" | "REAL" => row.try_get::<f32, _>(idx).map(|v| v.to_string()).unwrap_or_else(|_| "NULL".to_string()),
-        "FLOAT8" | "DOUBLE PRECISION" => row.try_get::<f64, _>(idx).map(|v| v.to_string()).unwrap_or_else(|_| "NULL".to_string()),
-        "NUMERIC" | "DECIMAL" => row.try_get::<String, _>(idx).unwrap_or_else(|_| "NULL".to_string()),
-        "VARCHAR" | "CHAR" | "TEXT" | "NAME" => row.try_get::<String, _>(idx).unwrap_or_else(|_| "NULL".to_string()),
-        "BOOL" | "BOOLEAN" => row.try_get::<bool, _>(idx).map(|v| v.to_string()).unwrap_or_else(|_| "NULL".to_string()),
-        "DATE" => row.try_get::<NaiveDate, _>(idx)
-            .map(|v| v.to_string())
-            .unwrap_or_else(|_| "NULL".to_string()),
-        "TIME" => row.try_get::<String, _>(idx).unwrap_or_else(|_| "NULL".to_string()),
-        "TIMESTAMP" => row.try_get::<String, _>(idx).unwrap_or_else(|_| "NULL".to_string()),
-        "TIMESTAMPTZ" | "TIMESTAMP WITH TIME ZONE" => row.try_get::<DateTime<Utc>, _>(idx)
-            .map(|v| v.to_rfc3339())
-            .unwrap_or_else(|_| "NULL".to_string()),
-        "JSON" | "JSONB" => row.try_get::<serde_json::Value, _>(idx)
-            .map(|v| serde_json::to_string(&v).unwrap_or_else(|_| "{}".to_string()))
-            .unwrap_or_else(|_| "NULL".to_string()),
-
-        type_name if type_name.contains("[]") => {
-            match type_name {
-                "TEXT[]" => format_array::<String>(row, idx),
-                "VARCHAR[]" => format_array::<String>(row, idx),
-                "INT[]" => format_array::<i32>(row, idx),
-                "BIGINT[]" => format_array::<i64>(row, idx),
-                "FLOAT8[]" => format_array::<f64>(row, idx),
-                "BOOL[]" => format_array::<bool>(row, idx),
-                _ => format!("[Unhandled array type: {}]", type_name)
+/// Streams a statement's rows to `sender`, returning its summary (without rows).
+/// `limited` is the statement wrapped in a `LIMIT`, sent in its place when given.
+async fn execute_statement(conn: &mut PgConnection, sql: &str, limited: Option<&str>, params: &QueryParams, max_rows: Option<usize>, sender: &EventSender) -> Result<QueryResult> {
+    let execute_start = Instant::now();
+    let bound = params.bind(limited.unwrap_or(sql))?;
+    let query_sql = if bound.values.is_empty() {
+        bound.sql
+    } else {
+        cast_parameters(conn, &bound.sql).await?
+    };
+    let mut query = sqlx::query(&query_sql);
+    for value in bound.values {
+        query = query.bind(value);
+    }
+    let mut execution_time = None;
+    let mut columns = Vec::new();
+    let mut column_types = Vec::new();
+    let mut row_count = 0;
+    let mut rows_affected = 0;
+    let mut truncated = false;
+
+    let mut stream = conn.fetch_many(query);
+    while let Some(step) = stream.try_next().await? {
+        if execution_time.is_none() {
+            execution_time = Some(execute_start.elapsed());
+        }
+        match step {
+            Either::Left(done) => {
+                rows_affected += done.rows_affected();
             }
-        },
+            Either::Right(row) => {
+                if columns.is_empty() {
+                    columns = row.columns()
+                        .iter()
+                        .map(|c| c.name().to_string())
+                        .collect();
+                    column_types = row.columns()
+                        .iter()
+                        .map(|c| c.type_info().name().to_string())
+                        .collect();
+                    send(sender, StreamEvent::Columns { names: columns.clone(), types: column_types.clone() }).await?;
+                }
+                if max_rows.is_some_and(|max| row_count >= max) {
+                    // Unless the statement was wrapped in a LIMIT, the server still sends the
+                    // remaining rows; they are read and thrown away without being decoded
+                    truncated = true;
+                    break;
+                }
 
-        _ => row.try_get::<String, _>(idx)
-            .unwrap_or_else(|_| truncate_string(&format!("[{}]", type_name), 50))
+                let values: Vec<Value> = row.columns()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, _)| convert_pg_value(&row, i))
+                    .collect();
+                row_count += 1;
+                send(sender, StreamEvent::Row(values)).await?;
+            }
+        }
     }
+
+    let total_time = execute_start.elapsed();
+    let execution_time = execution_time.unwrap_or(total_time);
+    let fetch_time = total_time.saturating_sub(execution_time);
+
+    let returned_rows = !columns.is_empty();
+    let count = if returned_rows { row_count as u64 } else { rows_affected };
+    let tag = command_tag(sql, count, returned_rows);
+
+    let mut summary = QueryResult::new(columns, Vec::new(), execution_time)
+        .with_timings(Duration::ZERO, fetch_time)
+        .with_command_tag(tag)
+        .with_rows_affected((changes_rows(sql) && !truncated).then_some(rows_affected))
+        .with_column_types(column_types);
+    summary.row_count = row_count;
+    summary.truncated = truncated;
+    Ok(summary)
 }
 
-fn format_array<T>(row: &PgRow, idx: usize) -> String 
-where 
-    T: ToString + for<'a> sqlx::decode::Decode<'a, sqlx::postgres::Postgres> + 
-       sqlx::types::Type<sqlx::postgres::Postgres> + sqlx::postgres::PgHasArrayType
-{
-    row.try_get::<Vec<T>, _>(idx)
-        .map(|arr| {
-            let items: Vec<String> = arr.iter().map(|item| item.to_string()).collect();
-            format!("[{}]", items.join(", "))
-        })
-        .unwrap_or_else(|_| "[]".to_string())
+/// Parameters are bound as text, so each placeholder is cast to the type the
+/// server infers for it when preparing the statement.
+async fn cast_parameters(conn: &mut PgConnection, sql: &str) -> Result<String> {
+    let statement = conn.prepare(sql).await?;
+    let Some(Either::Left(param_types)) = statement.parameters() else {
+        return Ok(sql.to_string());
+    };
+
+    // Types the server couldn't infer (no OID) stay text
+    let oids: Vec<Oid> = param_types.iter().map(|param_type| param_type.oid().unwrap_or(Oid(TEXT_OID))).collect();
+    let type_names: Vec<String> = sqlx::query_scalar(
+        "SELECT format_type(oid, NULL) FROM unnest($1::oid[]) WITH ORDINALITY AS t(oid, n) ORDER BY n",
+    )
+        .bind(oids)
+        .fetch_all(&mut *conn)
+        .await?;
+    Ok(cast_placeholders(sql, &type_names))
 }
-fn truncate_string(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
-    } else {
-        format!("{}...", &s[0..max_len])
+
+fn convert_pg_value(row: &PgRow, idx: usize) -> Value {
+    match row.try_get_raw(idx) {
+        Ok(value) => pg_decode::decode(value),
+        Err(_) => Value::Null,
     }
 }
 
@@ -120,9 +363,22 @@ mod tests {
     use anyhow::Result;
     use pgtemp::PgTempDB;
     use sqlx::Connection;
+    use std::time::Duration;
     
-    use crate::sql::interface::Executor;
-    use crate::sql::postgresql::PostgresExecutor;
+    use crate::sql::backend::{Backend, Target};
+    use crate::sql::cancel::{CancelHandle, QueryCancelled};
+    use crate::sql::error::{ErrorClass, ErrorPosition, QueryError};
+    use crate::sql::interface::{ExecutionOptions, Executor};
+    use crate::sql::listen::{ListenEvent, Listener};
+    use crate::sql::notice::{Notice, NoticeSeverity};
+    use crate::sql::params::QueryParams;
+    use crate::sql::pool::PoolManager;
+    use crate::sql::postgresql::{PostgresBackend, PostgresExecutor};
+    use crate::sql::result::StreamEvent;
+    use crate::sql::schema::{ConstraintKind, ForeignKeyTarget};
+    use crate::sql::session::{Session, TransactionStatus};
+    use crate::sql::timeout::{QueryTimedOut, TimeoutKind, Timeouts, DEFAULT_CONNECT_TIMEOUT};
+    use crate::sql::value::Value;
 
     async fn create_test_db() -> Result<(PgTempDB, String)> {
         let db = PgTempDB::async_new().await;
@@ -175,17 +431,15 @@ mod tests {
     async fn test_postgres_executor_simple_query() -> Result<()> {
         let (_db, conn_uri) = create_test_db().await?;
 
-        let executor = PostgresExecutor {
-            url: conn_uri,
-            sql: "SELECT 1 as test".to_string(),
-        };
+        let executor = PostgresExecutor::new(conn_uri, "SELECT 1 as test".to_string(), None, ExecutionOptions::new());
 
-        let result = executor.execute().await?;
+        let results = executor.execute().await?;
+        let result = &results[0];
         
         assert_eq!(result.columns.len(), 1);
         assert_eq!(result.columns[0], "test");
         assert_eq!(result.rows.len(), 1);
-        assert_eq!(result.rows[0][0], "1");
+        assert_eq!(result.rows[0][0], Value::Int(1));
 
         Ok(())
     }
@@ -194,18 +448,16 @@ mod tests {
     async fn test_postgres_executor_query_users() -> Result<()> {
         let (_db, conn_uri) = create_test_db().await?;
 
-        let executor = PostgresExecutor {
-            url: conn_uri,
-            sql: "SELECT id, name, email FROM users ORDER BY id".to_string(),
-        };
+        let executor = PostgresExecutor::new(conn_uri, "SELECT id, name, email FROM users ORDER BY id".to_string(), None, ExecutionOptions::new());
 
-        let result = executor.execute().await?;
+        let results = executor.execute().await?;
+        let result = &results[0];
         
         assert_eq!(result.columns.len(), 3);
         assert_eq!(result.columns, vec!["id", "name", "email"]);
         assert_eq!(result.rows.len(), 5);
-        assert_eq!(result.rows[0][1], "John Doe");
-        assert_eq!(result.rows[1][1], "Jane Smith");
+        assert_eq!(result.rows[0][1], "John Doe".into());
+        assert_eq!(result.rows[1][1], "Jane Smith".into());
 
         Ok(())
     }
@@ -222,20 +474,18 @@ mod tests {
             ORDER BY order_count DESC
         "#;
 
-        let executor = PostgresExecutor {
-            url: conn_uri,
-            sql: query.to_string(),
-        };
+        let executor = PostgresExecutor::new(conn_uri, query.to_string(), None, ExecutionOptions::new());
 
-        let result = executor.execute().await?;
+        let results = executor.execute().await?;
+        let result = &results[0];
         
         assert_eq!(result.columns.len(), 2);
         assert_eq!(result.columns, vec!["name", "order_count"]);
-        assert!(result.rows.len() > 0);
+        assert!(!result.rows.is_empty());
         
         // Charlie Wilson should have the most orders (3)
-        assert_eq!(result.rows[0][0], "Charlie Wilson");
-        assert_eq!(result.rows[0][1], "3");
+        assert_eq!(result.rows[0][0], "Charlie Wilson".into());
+        assert_eq!(result.rows[0][1], Value::Int(3));
 
         Ok(())
     }
@@ -244,10 +494,7 @@ mod tests {
     async fn test_postgres_executor_invalid_query() -> Result<()> {
         let (_db, conn_uri) = create_test_db().await?;
 
-        let executor = PostgresExecutor {
-            url: conn_uri,
-            sql: "SELECT * FROM nonexistent_table".to_string(),
-        };
+        let executor = PostgresExecutor::new(conn_uri, "SELECT * FROM nonexistent_table".to_string(), None, ExecutionOptions::new());
 
         let result = executor.execute().await;
         assert!(result.is_err());
@@ -255,21 +502,559 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_postgres_executor_structured_errors() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let sql = "SELECT 1;\nSELECT id,\n  nme FROM users";
+        let executor = PostgresExecutor::new(conn_uri.clone(), sql.to_string(), None, ExecutionOptions::new());
+        let error = executor.execute().await.unwrap_err();
+        let query_error = error.downcast_ref::<QueryError>().expect("a structured error");
+        assert_eq!(query_error.code, "42703");
+        assert_eq!(query_error.class(), ErrorClass::Syntax);
+        assert_eq!(query_error.position, Some(ErrorPosition { line: 2, column: 2, line_text: "  nme FROM users".to_string() }));
+        assert!(query_error.hint.as_deref().is_some_and(|hint| hint.contains("users.name")));
+
+        let sql = "INSERT INTO orders (user_id, item, amount) VALUES (99, 'Item Z', 1)";
+        let executor = PostgresExecutor::new(conn_uri, sql.to_string(), None, ExecutionOptions::new());
+        let error = executor.execute().await.unwrap_err();
+        let query_error = error.downcast_ref::<QueryError>().expect("a structured error");
+        assert_eq!(query_error.class(), ErrorClass::Constraint);
+        assert_eq!(query_error.constraint.as_deref(), Some("orders_user_id_fkey"));
+        assert_eq!(query_error.detail.as_deref(), Some("Key (user_id)=(99) is not present in table \"users\"."));
+        assert_eq!(query_error.position, None);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_postgres_executor_parameterized_query() -> Result<()> {
         let (_db, conn_uri) = create_test_db().await?;
 
-        let param_query = "SELECT * FROM users WHERE id = $1";
+        let param_query = "SELECT name FROM users WHERE id = $1";
 
-        let executor = PostgresExecutor {
-            url: conn_uri,
-            sql: param_query.to_string(),
-        };
+        let executor = PostgresExecutor::new(conn_uri.clone(), param_query.to_string(), None, ExecutionOptions::new());
 
         let result = executor.execute().await;
-        
-        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().to_string(), "No value given for parameter $1");
+
+        let sql = "SELECT name FROM users WHERE id = $1 AND email LIKE :domain; SELECT :domain || '!' AS d";
+        let options = ExecutionOptions::new().with_params(QueryParams::from_args(&["2".to_string(), "domain=%@example.com".to_string()]));
+        let executor = PostgresExecutor::new(conn_uri, sql.to_string(), None, options);
+
+        let results = executor.execute().await?;
+        assert_eq!(results[0].rows, vec![vec![Value::from("Jane Smith")]]);
+        assert_eq!(results[1].rows, vec![vec![Value::from("%@example.com!")]]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_reuses_pooled_connection() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let pools = PoolManager::new();
+        let pool = pools.get_or_create("test", &conn_uri, DEFAULT_CONNECT_TIMEOUT)?;
+
+        let mut connect_times = Vec::new();
+        for _ in 0..2 {
+            let shared = pools.get_or_create("test", &conn_uri, DEFAULT_CONNECT_TIMEOUT)?;
+            let executor = PostgresExecutor::new(conn_uri.clone(), "SELECT pg_backend_pid()".to_string(), Some(shared), ExecutionOptions::new());
+            let results = executor.execute().await?;
+            connect_times.push(results[0].connect_time);
+            assert_eq!(pool.size(), 1);
+            assert_eq!(pool.num_idle(), 1);
+        }
+
+        assert!(!connect_times[0].is_zero());
+        assert!(connect_times[1].is_zero());
+
+        // With the idle connection taken, the execution has to open its own
+        let held = pool.acquire().await?;
+        let executor = PostgresExecutor::new(conn_uri.clone(), "SELECT 1".to_string(), Some(pool.clone()), ExecutionOptions::new());
+        assert!(!executor.execute().await?[0].connect_time.is_zero());
+        assert_eq!(pool.size(), 2);
+        drop(held);
+
+        pools.close("test");
+        assert!(!pools.contains("test"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_multi_statement_script() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let script = r#"
+            -- add a few users; the ';' in this comment is ignored
+            INSERT INTO users (name, email) VALUES
+                ('Dan', 'dan;@example.com'),
+                ('Eve', 'eve@example.com'),
+                ('Fay', 'fay@example.com');
+            CREATE FUNCTION user_count() RETURNS bigint AS $$
+            BEGIN
+                RETURN (SELECT COUNT(*) FROM users);
+            END;
+            $$ LANGUAGE plpgsql;
+            UPDATE users SET name = upper(name) WHERE id <= 2;
+            SELECT user_count() AS total;
+        "#;
+
+        let executor = PostgresExecutor::new(conn_uri, script.to_string(), None, ExecutionOptions::new());
+
+        let results = executor.execute().await?;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].command_tag, "INSERT 0 3");
+        assert!(results[0].columns.is_empty());
+        assert_eq!(results[1].command_tag, "CREATE FUNCTION");
+        assert_eq!(results[1].rows_affected, None);
+        assert_eq!(results[2].command_tag, "UPDATE 2");
+        assert_eq!(results[2].rows_affected, Some(2));
+        assert_eq!(results[3].command_tag, "SELECT 1");
+        assert_eq!(results[3].columns, vec!["total"]);
+        assert_eq!(results[3].rows[0][0], Value::Int(8));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_multi_statement_error_rolls_back() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let executor = PostgresExecutor::new(conn_uri.clone(), "DELETE FROM orders; SELECT * FROM nonexistent_table;".to_string(), None, ExecutionOptions::new());
+
+        let err = executor.execute().await.unwrap_err();
+        assert!(err.to_string().contains("Statement 2 of 2"));
+
+        let executor = PostgresExecutor::new(conn_uri, "SELECT COUNT(*) FROM orders".to_string(), None, ExecutionOptions::new());
+        let results = executor.execute().await?;
+        assert_eq!(results[0].rows[0][0], Value::Int(7));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_cancel_running_query() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let pools = PoolManager::new();
+        let cancel = CancelHandle::new();
+
+        let pool = pools.get_or_create("test", &conn_uri, DEFAULT_CONNECT_TIMEOUT)?;
+        let options = ExecutionOptions::new().with_cancel(cancel.clone());
+        let executor = PostgresExecutor::new(conn_uri.clone(), "SELECT pg_sleep(30)".to_string(), Some(pool), options);
+        let running = tokio::spawn(async move { executor.execute().await });
+
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        assert!(PostgresBackend::default().cancel(&cancel).await?);
+
+        let err = tokio::time::timeout(std::time::Duration::from_secs(10), running).await??.unwrap_err();
+        assert!(err.is::<QueryCancelled>());
+
+        pools.close_all().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_streams_rows() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let executor = PostgresExecutor::new(conn_uri, "UPDATE users SET name = name; SELECT n FROM generate_series(1, 5000) AS n".to_string(), None, ExecutionOptions::new());
+
+        let mut stream = executor.execute_stream();
+        let mut events = Vec::new();
+        let mut rows = 0;
+        while let Some(event) = stream.next().await {
+            match event? {
+                StreamEvent::Columns { names, types } => events.push(format!("columns {} {}", names.join(","), types.join(","))),
+                StreamEvent::Row(row) => {
+                    rows += 1;
+                    assert_eq!(row[0], Value::Int(rows));
+                }
+                StreamEvent::Done(summary) => {
+                    assert!(summary.rows.is_empty());
+                    events.push(format!("done {}", summary.command_tag));
+                }
+            }
+        }
+
+        assert_eq!(rows, 5000);
+        assert_eq!(events, vec!["done UPDATE 5", "columns n INT4", "done SELECT 5000"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_max_rows_skips_the_rest() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let options = ExecutionOptions::new().with_max_rows(Some(3));
+        let sql = "SELECT n FROM generate_series(1, 5000) AS n; UPDATE users SET name = name RETURNING id; SELECT COUNT(*) FROM users";
+        let executor = PostgresExecutor::new(conn_uri.clone(), sql.to_string(), None, options);
+        let results = executor.execute().await?;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].rows, vec![vec![Value::Int(1)], vec![Value::Int(2)], vec![Value::Int(3)]]);
+        assert!(results[0].truncated);
+        assert_eq!(results[1].rows.len(), 3);
+        assert!(results[1].truncated);
+        assert_eq!(results[2].rows, vec![vec![Value::Int(5)]]);
+        assert!(!results[2].truncated);
+
+        // The server stops before the fifth row, which would divide by zero
+        let options = ExecutionOptions::new().with_max_rows(Some(3));
+        let sql = "SELECT 1 / (5 - n) AS a, n AS a FROM generate_series(1, 10) AS n -- no more";
+        let results = PostgresExecutor::new(conn_uri.clone(), sql.to_string(), None, options).execute().await?;
+        assert_eq!(results[0].columns, ["a", "a"]);
+        assert_eq!(results[0].rows.len(), 3);
+        assert!(results[0].truncated);
+
+        let options = ExecutionOptions::new().with_max_rows(Some(3));
+        let error = PostgresExecutor::new(conn_uri, "SELECT 1;\nSELECT nme FROM users".to_string(), None, options).execute().await.unwrap_err();
+        let query_error = error.downcast_ref::<QueryError>().expect("a structured error");
+        assert_eq!(query_error.position, Some(ErrorPosition { line: 1, column: 7, line_text: "SELECT nme FROM users".to_string() }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_typed_values() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let sql = "SELECT 'NULL'::text AS t, NULL::text AS n, true AS b, 2.5::float8 AS f, '{\"a\": 1}'::jsonb AS j, ARRAY[1, 2] AS a";
+        let executor = PostgresExecutor::new(conn_uri, sql.to_string(), None, ExecutionOptions::new());
+
+        let results = executor.execute().await?;
+        let result = &results[0];
+
+        assert_eq!(result.column_types, vec!["TEXT", "TEXT", "BOOL", "FLOAT8", "JSONB", "INT4[]"]);
+        assert_eq!(result.rows[0], vec![
+            Value::Text("NULL".to_string()),
+            Value::Null,
+            Value::Bool(true),
+            Value::Float(2.5),
+            Value::Json(serde_json::json!({"a": 1})),
+            Value::Array(vec![Value::Int(1), Value::Int(2)]),
+        ]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_session_spans_transactions() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let session = Session::new();
+
+        let run = |sql: &str, session: Option<Session>| {
+            PostgresExecutor::new(conn_uri.clone(), sql.to_string(), None, ExecutionOptions::new().with_session(session))
+        };
+        let name_of_first_user = || run("SELECT name FROM users WHERE id = 1", None);
+
+        run("BEGIN; UPDATE users SET name = 'Changed' WHERE id = 1", Some(session.clone())).execute().await?;
+        assert_eq!(session.status(), TransactionStatus::InTransaction);
+        let seen = run("SELECT name FROM users WHERE id = 1", Some(session.clone())).execute().await?;
+        assert_eq!(seen[0].rows[0][0], "Changed".into());
+        // Other connections don't see the uncommitted change
+        assert_eq!(name_of_first_user().execute().await?[0].rows[0][0], "John Doe".into());
+
+        run("SAVEPOINT before_error", Some(session.clone())).execute().await?;
+        assert!(run("SELECT 1 / 0", Some(session.clone())).execute().await.is_err());
+        assert_eq!(session.status(), TransactionStatus::Failed);
+        run("ROLLBACK TO SAVEPOINT before_error", Some(session.clone())).execute().await?;
+        assert_eq!(session.status(), TransactionStatus::InTransaction);
+
+        session.rollback().await?;
+        assert_eq!(session.status(), TransactionStatus::Idle);
+        assert_eq!(name_of_first_user().execute().await?[0].rows[0][0], "John Doe".into());
+
+        run("BEGIN; UPDATE users SET name = 'Committed' WHERE id = 1", Some(session.clone())).execute().await?;
+        session.commit().await?;
+        assert_eq!(session.status(), TransactionStatus::Idle);
+        assert_eq!(name_of_first_user().execute().await?[0].rows[0][0], "Committed".into());
+
+        session.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_session_rolls_back_failed_transaction() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let session = Session::new();
+        // Runs the way the TUI does, with timeouts to apply and a cancel handle
+        let run = |sql: &str| {
+            let options = ExecutionOptions::new()
+                .with_session(Some(session.clone()))
+                .with_cancel(CancelHandle::new())
+                .with_timeouts(Timeouts::new().with_statement(Some(Duration::from_secs(5))));
+            PostgresExecutor::new(conn_uri.clone(), sql.to_string(), None, options)
+        };
+
+        run("BEGIN").execute().await?;
+        assert!(run("SELECT 1 / 0").execute().await.is_err());
+        assert_eq!(session.status(), TransactionStatus::Failed);
+
+        run("ROLLBACK").execute().await?;
+        assert_eq!(session.status(), TransactionStatus::Idle);
+        assert_eq!(run("SELECT 1").execute().await?[0].rows[0][0], Value::Int(1));
+
+        session.close().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_timeouts() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let pools = PoolManager::new();
+        let pool = pools.get_or_create("test", &conn_uri, DEFAULT_CONNECT_TIMEOUT)?;
+        let run = |sql: &str, session: Option<Session>, timeouts: Timeouts| {
+            let options = ExecutionOptions::new().with_session(session).with_timeouts(timeouts);
+            PostgresExecutor::new(conn_uri.clone(), sql.to_string(), Some(pool.clone()), options)
+        };
+
+        let statement_timeout = Timeouts::new().with_statement(Some(Duration::from_millis(100)));
+        let err = run("SELECT pg_sleep(5)", None, statement_timeout).execute().await.unwrap_err();
+        let timed_out = err.downcast_ref::<QueryTimedOut>().expect("a statement timeout");
+        assert_eq!(timed_out.kind, TimeoutKind::Statement);
+        assert_eq!(err.to_string(), "Query timed out: it ran longer than the statement timeout of 100ms");
+
+        // The limit only applied to that execution's transaction
+        let results = run("SHOW statement_timeout", None, Timeouts::new()).execute().await?;
+        assert_eq!(results[0].rows[0][0], "0".into());
+
+        // Hold a lock from a session while another execution waits for it
+        let session = Session::new();
+        run("BEGIN; LOCK TABLE users IN ACCESS EXCLUSIVE MODE", Some(session.clone()), Timeouts::new()).execute().await?;
+        let lock_timeout = Timeouts { lock: Some(Duration::from_millis(100)), ..Timeouts::new() };
+        let err = run("SELECT * FROM users", None, lock_timeout).execute().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<QueryTimedOut>().map(|t| t.kind), Some(TimeoutKind::Lock));
+        session.rollback().await?;
+        session.close().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_notices() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let sql = "DO $$ BEGIN RAISE NOTICE 'checked % rows', 2; RAISE WARNING 'slow plan'; END $$;
+            SELECT 1;
+            CREATE TABLE IF NOT EXISTS users (id int)";
+        let executor = PostgresExecutor::new(conn_uri, sql.to_string(), None, ExecutionOptions::new());
+
+        let results = executor.execute().await?;
+        assert_eq!(results[0].notices, vec![
+            Notice::new(NoticeSeverity::Notice, "checked 2 rows"),
+            Notice::new(NoticeSeverity::Warning, "slow plan"),
+        ]);
+        assert!(results[1].notices.is_empty());
+        assert_eq!(results[2].notices, vec![Notice::new(NoticeSeverity::Notice, "relation \"users\" already exists, skipping")]);
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_postgres_backend() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let backend = PostgresBackend::default();
+        let target = Target::new("test", conn_uri);
+
+        backend.connect(&target, Timeouts::new()).await?;
+        let unreachable = Target::new("unreachable", "postgres://nobody@127.0.0.1:1/none");
+        assert!(backend.connect(&unreachable, Timeouts::new()).await.is_err());
+
+        let options = ExecutionOptions::new().with_params(QueryParams::from_args(&["1".to_string()]));
+        let results = backend.execute(&target, "SELECT name FROM users WHERE id = $1".to_string(), options).await?;
+        assert_eq!(results[0].rows.len(), 1);
+        backend.close_all().await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_backend_schema() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let backend = PostgresBackend::default();
+        let target = Target::new("test", conn_uri.clone());
+        let setup = "CREATE TYPE status AS ENUM ('pending', 'shipped');
+            CREATE INDEX orders_item_idx ON orders (lower(item));
+            CREATE VIEW big_orders AS SELECT * FROM orders WHERE amount > 100;
+            CREATE FUNCTION order_total(uid integer) RETURNS numeric LANGUAGE sql AS 'SELECT sum(amount) FROM orders WHERE user_id = uid';";
+        backend.execute(&target, setup.to_string(), ExecutionOptions::new()).await?;
+
+        let schema = backend.schema(&target, Timeouts::new()).await?;
+        assert_eq!(schema.schemas.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["public"]);
+        let names: Vec<&str> = schema.tables().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["big_orders", "orders", "users"]);
+
+        let orders = schema.find_table("public.orders").expect("orders is listed");
+        let columns: Vec<(&str, &str, bool)> = orders.columns.iter().map(|c| (c.name.as_str(), c.data_type.as_str(), c.nullable)).collect();
+        assert_eq!(columns, [("id", "integer", false), ("user_id", "integer", true), ("item", "text", false), ("amount", "numeric", false)]);
+        assert_eq!(orders.column("id").and_then(|c| c.default.as_deref()), Some("nextval('orders_id_seq'::regclass)"));
+        assert_eq!(orders.primary_key().map(|pk| pk.columns.clone()), Some(vec!["id".to_string()]));
+        let foreign_key = orders.foreign_keys().next().expect("orders references users");
+        assert_eq!(foreign_key.kind, ConstraintKind::ForeignKey);
+        assert_eq!(foreign_key.references, Some(ForeignKeyTarget { schema: "public".to_string(), table: "users".to_string(), columns: vec!["id".to_string()] }));
+        let indexes: Vec<(&str, Vec<String>, bool)> = orders.indexes.iter().map(|i| (i.name.as_str(), i.columns.clone(), i.primary)).collect();
+        assert_eq!(indexes, [("orders_pkey", vec!["id".to_string()], true), ("orders_item_idx", vec!["lower(item)".to_string()], false)]);
+
+        assert_eq!(orders.ddl(), "\
+CREATE TABLE public.orders (
+    id integer NOT NULL DEFAULT nextval('orders_id_seq'::regclass),
+    user_id integer,
+    item text NOT NULL,
+    amount numeric NOT NULL,
+    CONSTRAINT orders_pkey PRIMARY KEY (id),
+    CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(id)
+);
+CREATE INDEX orders_item_idx ON public.orders USING btree (lower(item));");
+        assert!(schema.find_table("big_orders").is_some_and(|view| view.ddl().starts_with("-- view public.big_orders\n--   id integer")));
+
+        let function = schema.functions().next().expect("order_total is listed");
+        assert_eq!((function.name.as_str(), function.arguments.as_str(), function.result.as_deref()), ("order_total", "uid integer", Some("numeric")));
+        let status = schema.enums().next().expect("status is listed");
+        assert_eq!(status.values, ["pending", "shipped"]);
+        backend.close_all().await;
+
+        Ok(())
+    }
+
+    async fn next(listener: &mut Listener) -> ListenEvent {
+        tokio::time::timeout(Duration::from_secs(10), listener.next()).await.expect("no listen event within 10s")
+    }
+
+    #[tokio::test]
+    async fn test_postgres_listener_reconnects() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+        let mut listener = Listener::connect(&conn_uri, vec!["jobs".to_string(), "Events".to_string()], DEFAULT_CONNECT_TIMEOUT).await?;
+        let mut conn = sqlx::postgres::PgConnection::connect(&conn_uri).await?;
+
+        sqlx::query("SELECT pg_notify('jobs', '42')").execute(&mut conn).await?;
+        // Channel names are case-sensitive, as when quoted in LISTEN
+        sqlx::query(r#"NOTIFY "Events""#).execute(&mut conn).await?;
+        let ListenEvent::Notification(first) = next(&mut listener).await else { panic!("expected a notification") };
+        assert_eq!((first.channel.as_str(), first.payload.as_str()), ("jobs", "42"));
+        let ListenEvent::Notification(second) = next(&mut listener).await else { panic!("expected a notification") };
+        assert_eq!((second.channel.as_str(), second.payload.as_str()), ("Events", ""));
+
+        sqlx::query("SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE query LIKE 'LISTEN%'").execute(&mut conn).await?;
+        assert!(matches!(next(&mut listener).await, ListenEvent::Disconnected(_)));
+        assert_eq!(next(&mut listener).await, ListenEvent::Reconnected);
+        sqlx::query("SELECT pg_notify('jobs', 'after')").execute(&mut conn).await?;
+        let ListenEvent::Notification(third) = next(&mut listener).await else { panic!("expected a notification") };
+        assert_eq!(third.payload, "after");
+
+        assert!(Listener::connect("sqlite://test.db", vec!["jobs".to_string()], DEFAULT_CONNECT_TIMEOUT).await.is_err());
+        Ok(())
+    }
+
+    async fn query_row(conn_uri: &str, sql: &str) -> Result<Vec<String>> {
+        let executor = PostgresExecutor::new(conn_uri.to_string(), sql.to_string(), None, ExecutionOptions::new());
+        let results = executor.execute().await?;
+        let result = results.last().expect("a result set");
+        Ok(result.rows[0].iter().map(|v| v.to_string()).collect())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_decodes_builtin_types() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let row = query_row(&conn_uri, "SELECT
+            'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::uuid,
+            interval '1 year 2 months 3 days 04:05:06.5',
+            interval '-1 day 2 hours',
+            '\\xdeadbeef'::bytea,
+            '192.168.0.1'::inet,
+            '10.0.0.0/8'::cidr,
+            '2001:db8::1/64'::inet,
+            '08:00:2b:01:02:03'::macaddr,
+            1234.5::money,
+            '12:34:56.789'::time,
+            '2024-02-29 13:14:15.5'::timestamp,
+            '2024-02-29 13:14:15+00'::timestamptz,
+            '0044-03-15 BC'::date,
+            'infinity'::timestamp,
+            '12:00:00+05:30'::timetz,
+            12345678901234567890.000123::numeric,
+            -0.0012::numeric(10, 5),
+            'NaN'::numeric,
+            2.1::float4,
+            1e20::float8,
+            B'10110'::bit(5),
+            point(1.5, -2),
+            '0/16B3748'::pg_lsn,
+            to_tsvector('simple', 'fat cats')
+        ").await?;
+
+        assert_eq!(row, vec![
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "1 year 2 mons 3 days 04:05:06.5",
+            "-1 days +02:00:00",
+            "\\xdeadbeef",
+            "192.168.0.1",
+            "10.0.0.0/8",
+            "2001:db8::1/64",
+            "08:00:2b:01:02:03",
+            "$1,234.50",
+            "12:34:56.789",
+            "2024-02-29 13:14:15.5",
+            "2024-02-29 13:14:15+00",
+            "0044-03-15 BC",
+            "infinity",
+            "12:00:00+05:30",
+            "12345678901234567890.000123",
+            "-0.00120",
+            "NaN",
+            "2.1",
+            "1e+20",
+            "10110",
+            "(1.5,-2)",
+            "0/16B3748",
+            "'cats':2 'fat':1",
+        ]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_executor_decodes_user_defined_types() -> Result<()> {
+        let (_db, conn_uri) = create_test_db().await?;
+
+        let row = query_row(&conn_uri, "
+            CREATE TYPE mood AS ENUM ('sad', 'happy');
+            CREATE TYPE pair AS (label text, n int4);
+            CREATE DOMAIN positive AS int4 CHECK (VALUE > 0);
+            SELECT
+                'happy'::mood,
+                ROW('a b', 1)::pair,
+                ROW(1, NULL, 'x'),
+                5::positive,
+                int4range(1, 5),
+                tsrange('2024-01-01', '2024-01-02', '[]'),
+                'empty'::numrange,
+                int4multirange(int4range(1, 3), int4range(5, 7)),
+                ARRAY[['a', NULL], ['b', 'c']],
+                ARRAY['sad', 'happy']::mood[],
+                ARRAY[int4range(1, 2)],
+                ARRAY['2024-01-01'::date],
+                ARRAY['', 'null', 'a b', 'x\"y', 'c,d']
+        ").await?;
+
+        assert_eq!(row, vec![
+            "happy",
+            "(\"a b\",1)",
+            "(1,,x)",
+            "5",
+            "[1,5)",
+            "[\"2024-01-01 00:00:00\",\"2024-01-02 00:00:00\"]",
+            "empty",
+            "{[1,3),[5,7)}",
+            "{{a,NULL},{b,c}}",
+            "{sad,happy}",
+            "{\"[1,2)\"}",
+            "{2024-01-01}",
+            "{\"\",\"null\",\"a b\",\"x\\\"y\",\"c,d\"}",
+        ]);
+
+        Ok(())
+    }
+}
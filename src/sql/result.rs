@@ -1,5 +1,9 @@
 use anyhow::{anyhow, Result};
-use serde::{Deserialize, Serialize};
+use serde::{ser::{Error as _, SerializeMap, SerializeSeq}, Deserialize, Serialize, Serializer};
+use std::{cell::RefCell, io::Write};
+use tokio::sync::mpsc;
+
+use super::{notice::Notice, value::Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -32,177 +36,578 @@ impl Format {
 #[derive(Debug, Clone)]
 pub struct QueryResult {
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    /// Database type name of each column (e.g. `INT4`, `TEXT`), parallel to `columns`
+    pub column_types: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// Time until the server started returning results
     pub execution_time: std::time::Duration,
+    /// Time spent acquiring a connection (zero when a pooled connection was reused)
+    pub connect_time: std::time::Duration,
+    /// Time spent receiving and decoding the remaining rows
+    pub fetch_time: std::time::Duration,
     pub row_count: usize,
+    /// psql-style status for the statement, e.g. `SELECT 5` or `INSERT 0 3`
+    pub command_tag: String,
+    /// Rows inserted, updated, deleted, merged or copied; `None` for other statements
+    pub rows_affected: Option<u64>,
+    /// Notices and warnings the server sent while running the statement
+    pub notices: Vec<Notice>,
+    /// The statement returned more rows than the execution's `max_rows`; the rest were skipped
+    pub truncated: bool,
 }
 
 impl Default for QueryResult {
     fn default() -> Self {
-        Self {
-            columns: Vec::new(),
-            rows: Vec::new(),
-            execution_time: std::time::Duration::from_secs(0),
-            row_count: 0,
-        }
+        Self::empty()
     }
 }
 
 impl QueryResult {
-    pub fn new(columns: Vec<String>, rows: Vec<Vec<String>>, execution_time: std::time::Duration) -> Self {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<Value>>, execution_time: std::time::Duration) -> Self {
         let row_count = rows.len();
         Self {
             columns,
+            column_types: Vec::new(),
             rows,
             execution_time,
+            connect_time: std::time::Duration::from_secs(0),
+            fetch_time: std::time::Duration::from_secs(0),
             row_count,
+            command_tag: String::new(),
+            rows_affected: None,
+            notices: Vec::new(),
+            truncated: false,
         }
     }
 
     pub fn empty() -> Self {
+        Self::new(Vec::new(), Vec::new(), std::time::Duration::from_secs(0))
+    }
+
+    pub fn with_timings(mut self, connect_time: std::time::Duration, fetch_time: std::time::Duration) -> Self {
+        self.connect_time = connect_time;
+        self.fetch_time = fetch_time;
+        self
+    }
+
+    pub fn with_command_tag(mut self, command_tag: String) -> Self {
+        self.command_tag = command_tag;
+        self
+    }
+
+    pub fn with_rows_affected(mut self, rows_affected: Option<u64>) -> Self {
+        self.rows_affected = rows_affected;
+        self
+    }
+
+    pub fn with_column_types(mut self, column_types: Vec<String>) -> Self {
+        self.column_types = column_types;
+        self
+    }
+
+    pub fn with_notices(mut self, notices: Vec<Notice>) -> Self {
+        self.notices = notices;
+        self
+    }
+
+    pub fn column_type(&self, idx: usize) -> Option<&str> {
+        self.column_types.get(idx).map(String::as_str)
+    }
+
+    /// Time spent on the query itself (execute + fetch), excluding connection setup
+    pub fn query_time(&self) -> std::time::Duration {
+        self.execution_time + self.fetch_time
+    }
+}
+
+/// One step of a streamed execution. Each statement produces `Columns` and
+/// its `Row`s (only if it returns rows), followed by `Done`.
+#[derive(Debug)]
+pub enum StreamEvent {
+    Columns { names: Vec<String>, types: Vec<String> },
+    Row(Vec<Value>),
+    /// The statement finished; the summary carries everything except the rows
+    Done(QueryResult),
+}
+
+/// How many rows may be decoded ahead of the consumer of a result stream
+pub(crate) const STREAM_BUFFER: usize = 1024;
+
+/// The executor's end of a `QueryResultStream`
+pub(crate) type EventSender = mpsc::Sender<Result<StreamEvent>>;
+
+pub(crate) async fn send(sender: &EventSender, event: StreamEvent) -> Result<()> {
+    sender.send(Ok(event)).await.map_err(|_| anyhow!("Result stream was closed"))
+}
+
+/// Results of an execution as they arrive from the server, statement by statement.
+/// The producer waits while the consumer falls behind, so memory use stays bounded.
+pub struct QueryResultStream {
+    receiver: mpsc::Receiver<Result<StreamEvent>>,
+}
+
+impl QueryResultStream {
+    pub fn new(receiver: mpsc::Receiver<Result<StreamEvent>>) -> Self {
+        Self { receiver }
+    }
+
+    pub async fn next(&mut self) -> Option<Result<StreamEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Like `next`, but blocks the thread; for use outside the async runtime, e.g. in `spawn_blocking`
+    pub fn blocking_next(&mut self) -> Option<Result<StreamEvent>> {
+        self.receiver.blocking_recv()
+    }
+
+    /// Buffers the remaining events into one `QueryResult` per statement
+    pub async fn collect(mut self) -> Result<Vec<QueryResult>> {
+        let mut results = Vec::new();
+        let mut rows = Vec::new();
+        while let Some(event) = self.next().await {
+            match event? {
+                StreamEvent::Columns { .. } => {}
+                StreamEvent::Row(row) => rows.push(row),
+                StreamEvent::Done(mut summary) => {
+                    summary.rows = std::mem::take(&mut rows);
+                    results.push(summary);
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Serializes a row as a JSON object, keeping the column order
+struct JsonRow<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (col, value) in self.columns.iter().zip(self.values) {
+            map.serialize_entry(col, value)?;
+        }
+        map.end()
+    }
+}
+
+/// What the JSON output shows of a statement besides its rows
+#[derive(Serialize)]
+struct JsonSummary<'a> {
+    command_tag: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rows_affected: Option<u64>,
+}
+
+/// Events pulled for the JSON output while it is being written. Serde only passes its own
+/// errors back, so the stream's first error is kept here, along with each statement's summary.
+struct JsonSource<'a> {
+    events: RefCell<Box<dyn Iterator<Item = Result<StreamEvent>> + 'a>>,
+    /// Rows beyond this many per result set are dropped
+    max_rows: Option<usize>,
+    error: RefCell<Option<anyhow::Error>>,
+    summaries: RefCell<Vec<QueryResult>>,
+}
+
+/// How the next statement in the JSON output starts
+enum JsonStatementStart {
+    /// With the columns of the rows that follow
+    Rows(Vec<String>),
+    /// With its summary; it returned no rows
+    Summary,
+}
+
+impl<'a> JsonSource<'a> {
+    fn new(events: impl Iterator<Item = Result<StreamEvent>> + 'a, max_rows: Option<usize>) -> Self {
         Self {
-            columns: Vec::new(),
-            rows: Vec::new(),
-            execution_time: std::time::Duration::from_secs(0),
-            row_count: 0,
+            events: RefCell::new(Box::new(events)),
+            max_rows,
+            error: RefCell::new(None),
+            summaries: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The next event; None at the end of the stream or at an error, which is kept
+    fn next(&self) -> Option<StreamEvent> {
+        match self.events.borrow_mut().next()? {
+            Ok(event) => Some(event),
+            Err(e) => {
+                self.error.borrow_mut().get_or_insert(e);
+                None
+            }
         }
     }
+
+    /// Skips to the start of the next statement that shows up in the output
+    fn next_statement(&self) -> Option<JsonStatementStart> {
+        loop {
+            match self.next()? {
+                StreamEvent::Columns { names, .. } => return Some(JsonStatementStart::Rows(names)),
+                StreamEvent::Done(summary) => {
+                    let shown = !summary.command_tag.is_empty();
+                    self.finish_statement(summary, 0, false);
+                    if shown {
+                        return Some(JsonStatementStart::Summary);
+                    }
+                }
+                StreamEvent::Row(_) => {}
+            }
+        }
+    }
+
+    fn finish_statement(&self, summary: QueryResult, rows_printed: usize, truncated: bool) {
+        // Like psql, notices go to stderr so they don't mix with the results
+        for notice in &summary.notices {
+            eprintln!("{}", notice);
+        }
+        if truncated || summary.truncated {
+            eprintln!("(first {} rows shown, the rest were skipped because of --max-rows)", rows_printed);
+        }
+        self.summaries.borrow_mut().push(summary);
+    }
 }
 
+/// The rows of the statement whose columns were just read, written as they are pulled
+struct JsonRows<'s, 'a> {
+    source: &'s JsonSource<'a>,
+    columns: Vec<String>,
+}
+
+impl Serialize for JsonRows<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        let mut rows_printed = 0;
+        let mut truncated = false;
+        loop {
+            match self.source.next() {
+                Some(StreamEvent::Row(_)) if self.source.max_rows.is_some_and(|max| rows_printed >= max) => truncated = true,
+                Some(StreamEvent::Row(values)) => {
+                    rows_printed += 1;
+                    seq.serialize_element(&JsonRow { columns: &self.columns, values: &values })?;
+                }
+                Some(StreamEvent::Done(summary)) => {
+                    self.source.finish_statement(summary, rows_printed, truncated);
+                    return seq.end();
+                }
+                Some(StreamEvent::Columns { .. }) | None => return Err(S::Error::custom("the results ended before the statement did")),
+            }
+        }
+    }
+}
+
+/// The summary of the statement just read, which is only known once its rows are written
+struct LastSummary<'s, 'a>(&'s JsonSource<'a>);
+
+impl Serialize for LastSummary<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let summaries = self.0.summaries.borrow();
+        let summary = summaries.last().ok_or_else(|| S::Error::custom("no statement has finished"))?;
+        JsonSummary { command_tag: &summary.command_tag, rows_affected: summary.rows_affected }.serialize(serializer)
+    }
+}
+
+/// A statement in a script's JSON output: its rows, if it returned any, then its summary
 #[derive(Serialize)]
-struct QueryResultRow<'a> {
+struct JsonStatement<'s, 'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rows: Option<JsonRows<'s, 'a>>,
     #[serde(flatten)]
-    data: std::collections::HashMap<&'a str, String>,
+    summary: LastSummary<'s, 'a>,
 }
 
-pub fn format_output(result: &QueryResult, format: Format) -> Result<()> {
-    match format {
-        Format::Table => format_table(result),
-        Format::Json => format_json(result),
-        Format::Csv => format_csv(result),
-        Format::Raw => format_raw(result),
-        Format::Wide => format_wide(result),
+/// A script's JSON output: an array with an object per statement
+struct JsonScript<'s, 'a>(&'s JsonSource<'a>);
+
+impl Serialize for JsonScript<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        while let Some(start) = self.0.next_statement() {
+            let rows = match start {
+                JsonStatementStart::Rows(columns) => Some(JsonRows { source: self.0, columns }),
+                JsonStatementStart::Summary => None,
+            };
+            seq.serialize_element(&JsonStatement { rows, summary: LastSummary(self.0) })?;
+        }
+        seq.end()
     }
 }
 
-fn format_table(result: &QueryResult) -> Result<()> {
-    if result.columns.is_empty() {
-        return Ok(());
+/// Writes `events` as JSON, pulling them as it goes so rows are never all held at once.
+/// A statement that returns rows is an array of row objects, and one that doesn't is an
+/// object with its command tag and rows affected. With `script`, the statements are
+/// wrapped in one array, with an object per statement holding its rows, tag and rows
+/// affected. Returns the summary of each statement, without its rows.
+fn write_json<'a>(out: &mut impl Write, events: impl Iterator<Item = Result<StreamEvent>> + 'a, max_rows: Option<usize>, script: bool) -> Result<Vec<QueryResult>> {
+    let source = JsonSource::new(events, max_rows);
+    let written = if script {
+        serde_json::to_writer_pretty(&mut *out, &JsonScript(&source)).map_err(anyhow::Error::from).and_then(|_| Ok(writeln!(out)?))
+    } else {
+        (|| {
+            while let Some(start) = source.next_statement() {
+                match start {
+                    JsonStatementStart::Rows(columns) => serde_json::to_writer_pretty(&mut *out, &JsonRows { source: &source, columns })?,
+                    JsonStatementStart::Summary => serde_json::to_writer_pretty(&mut *out, &LastSummary(&source))?,
+                }
+                writeln!(out)?;
+            }
+            Ok(())
+        })()
+    };
+    // The stream's own error says more than the one serde was stopped with
+    if let Some(e) = source.error.take() {
+        return Err(e);
     }
+    written?;
+    out.flush()?;
+    Ok(source.summaries.into_inner())
+}
 
-    let mut widths: Vec<usize> = result.columns.iter()
-        .map(|col| col.len())
-        .collect();
+/// Prints results to `out` as they stream in, in every format but JSON, which `write_json`
+/// writes. Table output needs every row to size its columns, so it is buffered per
+/// statement; the other formats are written row by row.
+struct ResultPrinter<W: Write> {
+    out: W,
+    format: Format,
+    /// Rows beyond this many per result set are dropped
+    max_rows: Option<usize>,
+    printed_any: bool,
+    columns: Vec<String>,
+    buffered: Vec<Vec<String>>,
+    rows_printed: usize,
+    truncated: bool,
+}
 
-    for row in &result.rows {
-        for (i, cell) in row.iter().enumerate() {
-            if i < widths.len() {
-                widths[i] = widths[i].max(cell.len());
-            }
+impl<W: Write> ResultPrinter<W> {
+    pub fn new(out: W, format: Format) -> Self {
+        Self {
+            out,
+            format,
+            max_rows: None,
+            printed_any: false,
+            columns: Vec::new(),
+            buffered: Vec::new(),
+            rows_printed: 0,
+            truncated: false,
         }
     }
 
-    print!("│ ");
-    for (i, col) in result.columns.iter().enumerate() {
-        print!("{:<width$} │ ", col, width=widths[i]);
+    pub fn max_rows(mut self, max_rows: Option<usize>) -> Self {
+        self.max_rows = max_rows;
+        self
     }
-    println!();
 
-    print!("├─");
-    for i in widths.iter() {
-        print!("{:─<item$}─┼─", "", item=i);
+    pub fn into_inner(self) -> W {
+        self.out
     }
-    println!();
 
-    for row in &result.rows {
-        print!("│ ");
-        for (i, cell) in row.iter().enumerate() {
-            if i < widths.len() {
-                print!("{:<width$} │ ", cell, width=widths[i]);
-            }
+    fn is_human_readable(&self) -> bool {
+        matches!(self.format, Format::Table | Format::Wide)
+    }
+
+    fn start_block(&mut self) -> Result<()> {
+        if self.printed_any && self.is_human_readable() {
+            writeln!(self.out)?;
         }
-        println!();
+        self.printed_any = true;
+        Ok(())
     }
 
-    Ok(())
-}
+    pub fn handle(&mut self, event: StreamEvent) -> Result<()> {
+        match event {
+            StreamEvent::Columns { names, .. } => self.columns(names),
+            StreamEvent::Row(row) => self.row(row),
+            StreamEvent::Done(summary) => self.done(&summary),
+        }
+    }
+
+    pub fn columns(&mut self, columns: Vec<String>) -> Result<()> {
+        self.start_block()?;
+        self.columns = columns;
+        self.buffered.clear();
+        self.rows_printed = 0;
+        self.truncated = false;
+
+        if self.format == Format::Csv {
+            write_csv_record(&mut self.out, &self.columns)?;
+        }
+        Ok(())
+    }
 
-fn format_json(result: &QueryResult) -> Result<()> {
-    let mut rows = Vec::new();
+    pub fn row(&mut self, row: Vec<Value>) -> Result<()> {
+        if self.max_rows.is_some_and(|max| self.rows_printed >= max) {
+            self.truncated = true;
+            return Ok(());
+        }
+        self.rows_printed += 1;
 
-    for row in &result.rows {
-        let mut map = std::collections::HashMap::new();
-        for (i, col) in result.columns.iter().enumerate() {
-            if i < row.len() {
-                map.insert(col.as_str(), row[i].clone());
+        match self.format {
+            Format::Table => self.buffered.push(row.iter().map(Value::to_string).collect()),
+            Format::Csv => write_csv_record(&mut self.out, row.iter().map(Value::to_string))?,
+            Format::Raw => {
+                let cells: Vec<String> = row.iter().map(Value::to_string).collect();
+                writeln!(self.out, "{}", cells.join("\t"))?
             }
+            Format::Wide => format_wide_row(&mut self.out, &self.columns, &row, self.rows_printed)?,
+            Format::Json => {}
         }
-        rows.push(QueryResultRow { data: map });
+        Ok(())
     }
 
-    let json = serde_json::to_string_pretty(&rows)?;
-    println!("{}", json);
-    Ok(())
+    pub fn done(&mut self, summary: &QueryResult) -> Result<()> {
+        // Like psql, notices go to stderr so they don't mix with the results
+        for notice in &summary.notices {
+            eprintln!("{}", notice);
+        }
+
+        if self.columns.is_empty() {
+            if summary.command_tag.is_empty() {
+                return Ok(());
+            }
+            if self.is_human_readable() {
+                self.start_block()?;
+                writeln!(self.out, "{}", summary.command_tag)?;
+            }
+            return Ok(());
+        }
+
+        if self.format == Format::Table {
+            format_table(&mut self.out, &self.columns, &self.buffered)?;
+        }
+        self.out.flush()?;
+
+        if self.truncated || summary.truncated {
+            eprintln!("(first {} rows shown, the rest were skipped because of --max-rows)", self.rows_printed);
+        }
+
+        self.columns.clear();
+        self.buffered.clear();
+        Ok(())
+    }
 }
 
-fn format_csv(result: &QueryResult) -> Result<()> {
-    if result.columns.is_empty() {
+/// Prints each statement's result in order. Statements that return no rows are
+/// shown by their command tag in the human-readable formats, and as an object
+/// with the tag and rows affected in JSON. The JSON for several statements is an
+/// array with an object per statement, holding its rows, tag and rows affected.
+pub fn format_output(results: &[QueryResult], format: Format) -> Result<()> {
+    let events = results.iter().flat_map(result_events);
+    if format == Format::Json {
+        write_json(&mut std::io::stdout().lock(), events.map(Ok), None, results.len() > 1)?;
         return Ok(());
     }
+    let mut printer = ResultPrinter::new(std::io::stdout().lock(), format);
+    for event in events {
+        printer.handle(event)?;
+    }
+    Ok(())
+}
 
-    let stdout = std::io::stdout();
-    let mut writer = csv::Writer::from_writer(stdout.lock());
+/// The events a statement's result would have streamed as
+fn result_events(result: &QueryResult) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    if !result.columns.is_empty() {
+        events.push(StreamEvent::Columns { names: result.columns.clone(), types: result.column_types.clone() });
+        events.extend(result.rows.iter().cloned().map(StreamEvent::Row));
+    }
+    events.push(StreamEvent::Done(QueryResult { rows: Vec::new(), ..result.clone() }));
+    events
+}
 
-    writer.write_record(&result.columns)?;
+/// Like `format_output`, but prints rows as they arrive instead of waiting for the whole result.
+/// `script` tells whether the stream is of several statements. Returns the summary of
+/// each statement, without its rows.
+pub async fn format_stream(mut stream: QueryResultStream, format: Format, max_rows: Option<usize>, script: bool) -> Result<Vec<QueryResult>> {
+    if format == Format::Json {
+        // Serde pulls the rows while it writes, so the stream is read from a blocking thread
+        return tokio::task::spawn_blocking(move || {
+            let events = std::iter::from_fn(|| stream.blocking_next());
+            write_json(&mut std::io::BufWriter::new(std::io::stdout()), events, max_rows, script)
+        }).await?;
+    }
+    let mut printer = ResultPrinter::new(std::io::BufWriter::new(std::io::stdout()), format).max_rows(max_rows);
+    let mut summaries = Vec::new();
+    while let Some(event) = stream.next().await {
+        let event = event?;
+        if let StreamEvent::Done(summary) = &event {
+            summaries.push(summary.clone());
+        }
+        printer.handle(event)?;
+    }
+    printer.into_inner().flush()?;
+    Ok(summaries)
+}
+
+fn format_table(out: &mut impl Write, columns: &[String], rows: &[Vec<String>]) -> Result<()> {
+    let mut widths: Vec<usize> = columns.iter()
+        .map(|col| col.len())
+        .collect();
 
-    for row in &result.rows {
-        writer.write_record(row)?;
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i < widths.len() {
+                widths[i] = widths[i].max(cell.len());
+            }
+        }
     }
 
-    writer.flush()?;
-    Ok(())
-}
+    write!(out, "│ ")?;
+    for (i, col) in columns.iter().enumerate() {
+        write!(out, "{:<width$} │ ", col, width=widths[i])?;
+    }
+    writeln!(out)?;
+
+    write!(out, "├─")?;
+    for i in widths.iter() {
+        write!(out, "{:─<item$}─┼─", "", item=i)?;
+    }
+    writeln!(out)?;
 
-fn format_raw(result: &QueryResult) -> Result<()> {
-    for row in &result.rows {
+    for row in rows {
+        write!(out, "│ ")?;
         for (i, cell) in row.iter().enumerate() {
-            if i > 0 {
-                print!("\t");
+            if i < widths.len() {
+                write!(out, "{:<width$} │ ", cell, width=widths[i])?;
             }
-            print!("{}", cell);
         }
-        println!();
+        writeln!(out)?;
     }
+
     Ok(())
 }
 
-fn format_wide(result: &QueryResult) -> Result<()> {
-    if result.columns.is_empty() {
-        return Ok(());
-    }
+pub(crate) fn write_csv_record<I, T>(out: &mut impl Write, record: I) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(record)?;
+    out.write_all(&writer.into_inner()?)?;
+    Ok(())
+}
 
-    for (row_idx, row) in result.rows.iter().enumerate() {
-        println!("Row {}:", row_idx + 1);
-        println!("├────────────────────────────────┬─────────────────────────────────────────────");
-        
-        for (i, col) in result.columns.iter().enumerate() {
-            if i < row.len() {
-                let value = row[i].clone();
-                let wrapped_value = textwrap::fill(&value, 50);
-                let lines: Vec<&str> = wrapped_value.lines().collect();
-                
-                println!("│ {:<30} │ {}", truncate(col, 30), lines.first().unwrap_or(&""));
-                
-                for line in lines.iter().skip(1) {
-                    println!("│ {:<30} │ {}", "", line);
-                }
+fn format_wide_row(out: &mut impl Write, columns: &[String], row: &[Value], row_number: usize) -> Result<()> {
+    writeln!(out, "Row {}:", row_number)?;
+    writeln!(out, "├────────────────────────────────┬─────────────────────────────────────────────")?;
+
+    for (i, col) in columns.iter().enumerate() {
+        if i < row.len() {
+            let wrapped_value = textwrap::fill(&row[i].to_string(), 50);
+            let lines: Vec<&str> = wrapped_value.lines().collect();
+
+            writeln!(out, "│ {:<30} │ {}", truncate(col, 30), lines.first().unwrap_or(&""))?;
+
+            for line in lines.iter().skip(1) {
+                writeln!(out, "│ {:<30} │ {}", "", line)?;
             }
         }
-        
-        println!("├────────────────────────────────┴─────────────────────────────────────────────");
-        println!();
     }
 
+    writeln!(out, "├────────────────────────────────┴─────────────────────────────────────────────")?;
+    writeln!(out)?;
     Ok(())
 }
 
@@ -212,4 +617,126 @@ fn truncate(s: &str, max_len: usize) -> String {
     } else {
         format!("{}...", &s[0..max_len-3])
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_json, Format, QueryResult, ResultPrinter, StreamEvent};
+    use crate::sql::value::Value;
+
+    fn print(format: Format, max_rows: Option<usize>, events: Vec<StreamEvent>) -> String {
+        if format == Format::Json {
+            let mut out = Vec::new();
+            write_json(&mut out, events.into_iter().map(Ok), max_rows, false).unwrap();
+            return String::from_utf8(out).unwrap();
+        }
+        let mut printer = ResultPrinter::new(Vec::new(), format).max_rows(max_rows);
+        for event in events {
+            printer.handle(event).unwrap();
+        }
+        String::from_utf8(printer.into_inner()).unwrap()
+    }
+
+    fn select(rows: &[&[&str]]) -> Vec<StreamEvent> {
+        let mut events = vec![StreamEvent::Columns {
+            names: vec!["id".to_string(), "name".to_string()],
+            types: vec!["TEXT".to_string(), "TEXT".to_string()],
+        }];
+        events.extend(rows.iter().map(|row| StreamEvent::Row(row.iter().map(|&c| c.into()).collect())));
+        events.push(StreamEvent::Done(QueryResult::empty().with_command_tag(format!("SELECT {}", rows.len()))));
+        events
+    }
+
+    #[test]
+    fn test_streamed_json_matches_buffered_layout() {
+        let output = print(Format::Json, None, select(&[&["1", "a"], &["2", "b"]]));
+        let expected = serde_json::to_string_pretty(&serde_json::json!([
+            {"id": "1", "name": "a"},
+            {"id": "2", "name": "b"},
+        ])).unwrap();
+        assert_eq!(output, format!("{}\n", expected));
+    }
+
+    #[test]
+    fn test_json_keeps_value_types() {
+        let events = vec![
+            StreamEvent::Columns {
+                names: vec!["id".to_string(), "note".to_string(), "active".to_string(), "price".to_string()],
+                types: vec!["INT4".to_string(), "TEXT".to_string(), "BOOL".to_string(), "NUMERIC".to_string()],
+            },
+            StreamEvent::Row(vec![Value::Int(1), Value::Null, Value::Bool(true), Value::Numeric("10.50".to_string())]),
+            StreamEvent::Row(vec![Value::Int(2), "NULL".into(), Value::Bool(false), Value::Numeric("3".to_string())]),
+            StreamEvent::Row(vec![Value::Int(3), Value::Float(2.5), Value::Float(f64::NAN), Value::Float(f64::NEG_INFINITY)]),
+            StreamEvent::Done(QueryResult::empty()),
+        ];
+
+        let output: serde_json::Value = serde_json::from_str(&print(Format::Json, None, events)).unwrap();
+        assert_eq!(output, serde_json::json!([
+            {"id": 1, "note": null, "active": true, "price": "10.50"},
+            {"id": 2, "note": "NULL", "active": false, "price": "3"},
+            {"id": 3, "note": 2.5, "active": "NaN", "price": "-Infinity"},
+        ]));
+    }
+
+    #[test]
+    fn test_max_rows_limits_each_result_set() {
+        let mut events = select(&[&["1", "a"], &["2", "b"], &["3", "c"]]);
+        events.push(StreamEvent::Done(QueryResult::empty().with_command_tag("UPDATE 3".to_string())));
+        events.extend(select(&[&["4", "d"], &["5", "e"]]));
+
+        assert_eq!(print(Format::Csv, Some(2), events), "id,name\n1,a\n2,b\nid,name\n4,d\n5,e\n");
+    }
+
+    #[test]
+    fn test_json_shows_command_tags_as_metadata() {
+        let events = vec![StreamEvent::Done(QueryResult::empty().with_command_tag("UPDATE 2".to_string()).with_rows_affected(Some(2)))];
+
+        assert_eq!(print(Format::Json, None, events), "{\n  \"command_tag\": \"UPDATE 2\",\n  \"rows_affected\": 2\n}\n");
+    }
+
+    #[test]
+    fn test_json_script_is_one_array_of_statements() {
+        let mut events = vec![
+            StreamEvent::Done(QueryResult::empty().with_command_tag("UPDATE 2".to_string()).with_rows_affected(Some(2))),
+            StreamEvent::Done(QueryResult::empty().with_command_tag("CREATE TABLE".to_string())),
+        ];
+        events.extend(select(&[&["1", "a"], &["2", "b"]]));
+        events.extend(select(&[]));
+
+        let mut output = Vec::new();
+        let summaries = write_json(&mut output, events.into_iter().map(Ok), None, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(summaries.len(), 4);
+
+        let expected = serde_json::to_string_pretty(&serde_json::json!([
+            {"command_tag": "UPDATE 2", "rows_affected": 2},
+            {"command_tag": "CREATE TABLE"},
+            {"rows": [{"id": "1", "name": "a"}, {"id": "2", "name": "b"}], "command_tag": "SELECT 2"},
+            {"rows": [], "command_tag": "SELECT 0"},
+        ])).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed, serde_json::from_str::<serde_json::Value>(&expected).unwrap());
+        assert!(output.starts_with("[\n  {\n    \"command_tag\": \"UPDATE 2\",\n"));
+        assert!(output.ends_with("\n    \"rows\": [],\n    \"command_tag\": \"SELECT 0\"\n  }\n]\n"));
+    }
+
+    #[test]
+    fn test_json_returns_the_stream_error() {
+        let mut events: Vec<anyhow::Result<StreamEvent>> = select(&[&["1", "a"]]).into_iter().map(Ok).collect();
+        events.insert(2, Err(anyhow::anyhow!("connection lost")));
+
+        let error = write_json(&mut Vec::new(), events.into_iter(), Some(1), false).unwrap_err();
+        assert_eq!(error.to_string(), "connection lost");
+    }
+
+    #[test]
+    fn test_table_shows_command_tags_between_results() {
+        let mut events = vec![StreamEvent::Done(QueryResult::empty().with_command_tag("INSERT 0 1".to_string()))];
+        events.extend(select(&[&["1", "alice"]]));
+
+        assert_eq!(
+            print(Format::Table, None, events),
+            "INSERT 0 1\n\n│ id │ name  │ \n├────┼───────┼─\n│ 1  │ alice │ \n"
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{postgres::PgPool, sqlite::SqliteConnection};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use super::statement::quote_identifier;
+
+/// What a database contains, as introspected by `Backend::schema`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DatabaseSchema {
+    /// Ordered by name; system schemas are left out
+    pub schemas: Vec<Schema>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Schema {
+    pub name: String,
+    /// Tables, views and the like, ordered by name
+    pub tables: Vec<Table>,
+    pub functions: Vec<Function>,
+    pub enums: Vec<EnumType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    Table,
+    View,
+    MaterializedView,
+    ForeignTable,
+}
+
+impl fmt::Display for TableKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableKind::Table => write!(f, "table"),
+            TableKind::View => write!(f, "view"),
+            TableKind::MaterializedView => write!(f, "materialized view"),
+            TableKind::ForeignTable => write!(f, "foreign table"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Table {
+    pub schema: String,
+    pub name: String,
+    pub kind: TableKind,
+    /// In the order they were defined
+    pub columns: Vec<Column>,
+    pub constraints: Vec<Constraint>,
+    pub indexes: Vec<Index>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Column {
+    pub name: String,
+    /// As the database spells it, e.g. `character varying(255)`
+    pub data_type: String,
+    pub nullable: bool,
+    /// The default expression, e.g. `nextval('users_id_seq'::regclass)`
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintKind {
+    PrimaryKey,
+    ForeignKey,
+    Unique,
+    Check,
+    Exclusion,
+}
+
+impl fmt::Display for ConstraintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintKind::PrimaryKey => write!(f, "PRIMARY KEY"),
+            ConstraintKind::ForeignKey => write!(f, "FOREIGN KEY"),
+            ConstraintKind::Unique => write!(f, "UNIQUE"),
+            ConstraintKind::Check => write!(f, "CHECK"),
+            ConstraintKind::Exclusion => write!(f, "EXCLUDE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Constraint {
+    pub name: String,
+    pub kind: ConstraintKind,
+    /// The constrained columns; empty for checks on expressions
+    pub columns: Vec<String>,
+    /// For foreign keys, the referenced table and its columns
+    pub references: Option<ForeignKeyTarget>,
+    /// As it would be written in `CREATE TABLE`, e.g. `PRIMARY KEY (id)`
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ForeignKeyTarget {
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Index {
+    pub name: String,
+    /// Key columns, or the expressions indexed
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub primary: bool,
+    /// The `CREATE INDEX` statement, where the database keeps one
+    pub definition: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionKind {
+    Function,
+    Procedure,
+    Aggregate,
+    Window,
+}
+
+impl fmt::Display for FunctionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FunctionKind::Function => write!(f, "function"),
+            FunctionKind::Procedure => write!(f, "procedure"),
+            FunctionKind::Aggregate => write!(f, "aggregate"),
+            FunctionKind::Window => write!(f, "window"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Function {
+    pub schema: String,
+    pub name: String,
+    pub kind: FunctionKind,
+    /// e.g. `user_id integer, since date`
+    pub arguments: String,
+    /// The return type; `None` for procedures
+    pub result: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnumType {
+    pub schema: String,
+    pub name: String,
+    /// In sort order
+    pub values: Vec<String>,
+}
+
+impl DatabaseSchema {
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.schemas.iter().flat_map(|schema| schema.tables.iter())
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.schemas.iter().flat_map(|schema| schema.functions.iter())
+    }
+
+    pub fn enums(&self) -> impl Iterator<Item = &EnumType> {
+        self.schemas.iter().flat_map(|schema| schema.enums.iter())
+    }
+
+    /// Finds a table by `name` or `schema.name`. An unqualified name is looked up in
+    /// `public` (or SQLite's `main`) first, then in the other schemas.
+    pub fn find_table(&self, name: &str) -> Option<&Table> {
+        if let Some((schema, table)) = name.split_once('.') {
+            return self.tables().find(|t| t.schema == schema && t.name == table);
+        }
+        self.tables()
+            .find(|t| t.name == name && matches!(t.schema.as_str(), "public" | "main"))
+            .or_else(|| self.tables().find(|t| t.name == name))
+    }
+}
+
+impl Table {
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    pub fn primary_key(&self) -> Option<&Constraint> {
+        self.constraints.iter().find(|c| c.kind == ConstraintKind::PrimaryKey)
+    }
+
+    pub fn foreign_keys(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints.iter().filter(|c| c.kind == ConstraintKind::ForeignKey)
+    }
+
+    /// `schema.name`, quoting either part where SQL needs it
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", sql_identifier(&self.schema), sql_identifier(&self.name))
+    }
+
+    /// A `CREATE TABLE` statement rebuilt from the columns and constraints, followed by
+    /// the indexes that don't back a constraint. Views only list their columns, since
+    /// their definitions aren't introspected.
+    pub fn ddl(&self) -> String {
+        if self.kind != TableKind::Table {
+            let mut lines = vec![format!("-- {} {}", self.kind, self.qualified_name())];
+            lines.extend(self.columns.iter().map(|c| format!("--   {} {}", sql_identifier(&c.name), c.data_type)));
+            return lines.join("\n");
+        }
+
+        let mut definitions: Vec<String> = self.columns
+            .iter()
+            .map(|column| {
+                let mut definition = format!("{} {}", sql_identifier(&column.name), column.data_type);
+                if !column.nullable {
+                    definition.push_str(" NOT NULL");
+                }
+                if let Some(default) = &column.default {
+                    definition.push_str(&format!(" DEFAULT {}", default));
+                }
+                definition
+            })
+            .collect();
+        definitions.extend(self.constraints
+            .iter()
+            .filter(|c| !c.definition.is_empty())
+            .map(|c| format!("CONSTRAINT {} {}", sql_identifier(&c.name), c.definition)));
+
+        let mut ddl = format!("CREATE TABLE {} (\n    {}\n);", self.qualified_name(), definitions.join(",\n    "));
+        for index in &self.indexes {
+            if self.constraints.iter().any(|c| c.name == index.name) {
+                continue;
+            }
+            if let Some(definition) = &index.definition {
+                ddl.push_str(&format!("\n{};", definition));
+            }
+        }
+        ddl
+    }
+}
+
+/// Quotes an identifier unless it is a plain lowercase name
+fn sql_identifier(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        quote_identifier(name)
+    }
+}
+
+/// Introspected schemas, kept per target key until refreshed. Cheap to clone; clones share the cache.
+#[derive(Clone, Default)]
+pub struct SchemaCache {
+    schemas: Arc<Mutex<HashMap<String, Arc<DatabaseSchema>>>>,
+}
+
+impl SchemaCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<DatabaseSchema>> {
+        self.schemas.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn insert(&self, key: &str, schema: DatabaseSchema) -> Arc<DatabaseSchema> {
+        let schema = Arc::new(schema);
+        self.schemas.lock().unwrap().insert(key.to_string(), schema.clone());
+        schema
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        self.schemas.lock().unwrap().remove(key);
+    }
+
+    pub fn clear(&self) {
+        self.schemas.lock().unwrap().clear();
+    }
+}
+
+/// Leaves out the catalogs, TOAST tables and other sessions' temporary schemas
+const USER_SCHEMAS: &str = "n.nspname NOT IN ('pg_catalog', 'information_schema') AND n.nspname NOT LIKE 'pg\\_toast%' AND n.nspname NOT LIKE 'pg\\_temp\\_%'";
+
+/// Reads the schema of a Postgres database from the system catalogs
+#[allow(clippy::type_complexity)]
+pub(crate) async fn load_postgres(pool: &PgPool) -> Result<DatabaseSchema> {
+    let mut conn = pool.acquire().await?;
+
+    let names: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT n.nspname::text FROM pg_namespace n WHERE {} ORDER BY 1",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+    let mut schemas: Vec<Schema> = names.into_iter().map(|name| Schema { name, ..Schema::default() }).collect();
+
+    let relations: Vec<(String, String, String)> = sqlx::query_as(&format!(
+        "SELECT n.nspname::text, c.relname::text, c.relkind::text
+         FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+         WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f') AND {}
+         ORDER BY 1, 2",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+    let mut tables: Vec<Table> = relations
+        .into_iter()
+        .map(|(schema, name, kind)| Table {
+            schema,
+            name,
+            kind: match kind.as_str() {
+                "v" => TableKind::View,
+                "m" => TableKind::MaterializedView,
+                "f" => TableKind::ForeignTable,
+                _ => TableKind::Table,
+            },
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            indexes: Vec::new(),
+        })
+        .collect();
+    let positions: HashMap<(String, String), usize> = tables
+        .iter()
+        .enumerate()
+        .map(|(i, t)| ((t.schema.clone(), t.name.clone()), i))
+        .collect();
+    let table_for = |schema: String, name: String| positions.get(&(schema, name)).copied();
+
+    let columns: Vec<(String, String, String, String, bool, Option<String>)> = sqlx::query_as(&format!(
+        "SELECT n.nspname::text, c.relname::text, a.attname::text, format_type(a.atttypid, a.atttypmod),
+                NOT a.attnotnull, pg_get_expr(d.adbin, d.adrelid)
+         FROM pg_attribute a
+         JOIN pg_class c ON c.oid = a.attrelid
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+         WHERE a.attnum > 0 AND NOT a.attisdropped AND c.relkind IN ('r', 'p', 'v', 'm', 'f') AND {}
+         ORDER BY 1, 2, a.attnum",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+    for (schema, table, name, data_type, nullable, default) in columns {
+        if let Some(i) = table_for(schema, table) {
+            tables[i].columns.push(Column { name, data_type, nullable, default });
+        }
+    }
+
+    let constraints: Vec<(String, String, String, String, Vec<String>, Option<String>, Option<String>, Vec<String>, String)> = sqlx::query_as(&format!(
+        "SELECT n.nspname::text, c.relname::text, con.conname::text, con.contype::text,
+                ARRAY(SELECT a.attname::text FROM unnest(con.conkey) WITH ORDINALITY k(attnum, i)
+                      JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum ORDER BY k.i),
+                fn.nspname::text, fc.relname::text,
+                ARRAY(SELECT a.attname::text FROM unnest(con.confkey) WITH ORDINALITY k(attnum, i)
+                      JOIN pg_attribute a ON a.attrelid = con.confrelid AND a.attnum = k.attnum ORDER BY k.i),
+                pg_get_constraintdef(con.oid, true)
+         FROM pg_constraint con
+         JOIN pg_class c ON c.oid = con.conrelid
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         LEFT JOIN pg_class fc ON fc.oid = con.confrelid
+         LEFT JOIN pg_namespace fn ON fn.oid = fc.relnamespace
+         WHERE con.contype IN ('p', 'f', 'u', 'c', 'x') AND {}
+         ORDER BY 1, 2, position(con.contype IN 'pufcx'), 3",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+    for (schema, table, name, kind, columns, ref_schema, ref_table, ref_columns, definition) in constraints {
+        let kind = match kind.as_str() {
+            "p" => ConstraintKind::PrimaryKey,
+            "f" => ConstraintKind::ForeignKey,
+            "u" => ConstraintKind::Unique,
+            "c" => ConstraintKind::Check,
+            _ => ConstraintKind::Exclusion,
+        };
+        let references = match (ref_schema, ref_table) {
+            (Some(schema), Some(table)) => Some(ForeignKeyTarget { schema, table, columns: ref_columns }),
+            _ => None,
+        };
+        if let Some(i) = table_for(schema, table) {
+            tables[i].constraints.push(Constraint { name, kind, columns, references, definition });
+        }
+    }
+
+    let indexes: Vec<(String, String, String, Vec<String>, bool, bool, String)> = sqlx::query_as(&format!(
+        "SELECT n.nspname::text, t.relname::text, i.relname::text,
+                ARRAY(SELECT pg_get_indexdef(ix.indexrelid, k, true) FROM generate_series(1, ix.indnkeyatts) k ORDER BY k),
+                ix.indisunique, ix.indisprimary, pg_get_indexdef(ix.indexrelid)
+         FROM pg_index ix
+         JOIN pg_class i ON i.oid = ix.indexrelid
+         JOIN pg_class t ON t.oid = ix.indrelid
+         JOIN pg_namespace n ON n.oid = t.relnamespace
+         WHERE {}
+         ORDER BY 1, 2, ix.indisprimary DESC, 3",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+    for (schema, table, name, columns, unique, primary, definition) in indexes {
+        if let Some(i) = table_for(schema, table) {
+            tables[i].indexes.push(Index { name, columns, unique, primary, definition: Some(definition) });
+        }
+    }
+
+    // Functions that belong to extensions are left out, as they would crowd out the user's own
+    let functions: Vec<(String, String, String, String, Option<String>)> = sqlx::query_as(&format!(
+        "SELECT n.nspname::text, p.proname::text, p.prokind::text, pg_get_function_identity_arguments(p.oid),
+                CASE WHEN p.prokind = 'p' THEN NULL ELSE pg_get_function_result(p.oid) END
+         FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace
+         WHERE {} AND NOT EXISTS (SELECT 1 FROM pg_depend d WHERE d.objid = p.oid AND d.deptype = 'e')
+         ORDER BY 1, 2, 4",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+    let enums: Vec<(String, String, Vec<String>)> = sqlx::query_as(&format!(
+        "SELECT n.nspname::text, t.typname::text, array_agg(e.enumlabel::text ORDER BY e.enumsortorder)
+         FROM pg_type t
+         JOIN pg_enum e ON e.enumtypid = t.oid
+         JOIN pg_namespace n ON n.oid = t.typnamespace
+         WHERE {}
+         GROUP BY 1, 2
+         ORDER BY 1, 2",
+        USER_SCHEMAS
+    ))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for schema in &mut schemas {
+        let name = schema.name.clone();
+        schema.tables = tables.iter().filter(|t| t.schema == name).cloned().collect();
+        schema.functions = functions
+            .iter()
+            .filter(|(s, ..)| *s == name)
+            .map(|(schema, name, kind, arguments, result)| Function {
+                schema: schema.clone(),
+                name: name.clone(),
+                kind: match kind.as_str() {
+                    "p" => FunctionKind::Procedure,
+                    "a" => FunctionKind::Aggregate,
+                    "w" => FunctionKind::Window,
+                    _ => FunctionKind::Function,
+                },
+                arguments: arguments.clone(),
+                result: result.clone(),
+            })
+            .collect();
+        schema.enums = enums
+            .iter()
+            .filter(|(s, ..)| *s == name)
+            .map(|(schema, name, values)| EnumType { schema: schema.clone(), name: name.clone(), values: values.clone() })
+            .collect();
+    }
+    Ok(DatabaseSchema { schemas })
+}
+
+/// Reads the schema of an SQLite database; everything is in the `main` schema
+pub(crate) async fn load_sqlite(conn: &mut SqliteConnection) -> Result<DatabaseSchema> {
+    let relations: Vec<(String, String)> = sqlx::query_as(
+        "SELECT name, type FROM sqlite_schema WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut tables = Vec::new();
+    for (name, kind) in relations {
+        let columns: Vec<(String, String, bool, Option<String>, i64)> = sqlx::query_as(
+            "SELECT name, type, NOT \"notnull\", dflt_value, pk FROM pragma_table_info(?) ORDER BY cid",
+        )
+            .bind(&name)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let mut key: Vec<(i64, String)> = columns.iter().filter(|c| c.4 > 0).map(|c| (c.4, c.0.clone())).collect();
+        key.sort();
+        let key: Vec<String> = key.into_iter().map(|(_, column)| column).collect();
+        let mut constraints = Vec::new();
+        if !key.is_empty() {
+            constraints.push(Constraint {
+                name: format!("{}_pkey", name),
+                kind: ConstraintKind::PrimaryKey,
+                definition: format!("PRIMARY KEY ({})", key.join(", ")),
+                columns: key,
+                references: None,
+            });
+        }
+
+        let foreign_keys: Vec<(i64, String, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, \"table\", \"from\", \"to\" FROM pragma_foreign_key_list(?) ORDER BY id, seq",
+        )
+            .bind(&name)
+            .fetch_all(&mut *conn)
+            .await?;
+        let mut previous_id = None;
+        for (id, table, from, to) in foreign_keys {
+            // A key over several columns has a row per column
+            if previous_id != Some(id) {
+                constraints.push(Constraint {
+                    name: format!("{}_fkey{}", name, id),
+                    kind: ConstraintKind::ForeignKey,
+                    columns: Vec::new(),
+                    references: Some(ForeignKeyTarget { schema: "main".to_string(), table, columns: Vec::new() }),
+                    definition: String::new(),
+                });
+                previous_id = Some(id);
+            }
+            if let Some(constraint) = constraints.last_mut() {
+                constraint.columns.push(from);
+                if let (Some(references), Some(to)) = (&mut constraint.references, to) {
+                    references.columns.push(to);
+                }
+            }
+        }
+        for constraint in constraints.iter_mut().filter(|c| c.kind == ConstraintKind::ForeignKey) {
+            if let Some(references) = &constraint.references {
+                constraint.definition = format!(
+                    "FOREIGN KEY ({}) REFERENCES {}({})",
+                    constraint.columns.join(", "),
+                    references.table,
+                    references.columns.join(", ")
+                );
+            }
+        }
+
+        let index_list: Vec<(String, bool, String)> = sqlx::query_as(
+            "SELECT name, \"unique\", origin FROM pragma_index_list(?) ORDER BY origin = 'pk' DESC, name",
+        )
+            .bind(&name)
+            .fetch_all(&mut *conn)
+            .await?;
+        let mut indexes = Vec::new();
+        for (index, unique, origin) in index_list {
+            let columns: Vec<Option<String>> = sqlx::query_scalar("SELECT name FROM pragma_index_info(?) ORDER BY seqno")
+                .bind(&index)
+                .fetch_all(&mut *conn)
+                .await?;
+            let definition: Option<String> = sqlx::query_scalar("SELECT sql FROM sqlite_schema WHERE type = 'index' AND name = ?")
+                .bind(&index)
+                .fetch_optional(&mut *conn)
+                .await?
+                .flatten();
+            indexes.push(Index {
+                name: index,
+                columns: columns.into_iter().map(|c| c.unwrap_or_else(|| "<expression>".to_string())).collect(),
+                unique,
+                primary: origin == "pk",
+                definition,
+            });
+        }
+
+        tables.push(Table {
+            schema: "main".to_string(),
+            name,
+            kind: if kind == "view" { TableKind::View } else { TableKind::Table },
+            columns: columns
+                .into_iter()
+                .map(|(name, data_type, nullable, default, _)| Column { name, data_type, nullable, default })
+                .collect(),
+            constraints,
+            indexes,
+        });
+    }
+
+    Ok(DatabaseSchema {
+        schemas: vec![Schema { name: "main".to_string(), tables, functions: Vec::new(), enums: Vec::new() }],
+    })
+}
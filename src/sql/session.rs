@@ -0,0 +1,137 @@
+use anyhow::Result;
+use sqlx::{postgres::PgConnection, Connection, Executor, Row};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use super::timeout::{QueryTimedOut, TimeoutKind};
+
+/// Where a session's connection is relative to a transaction block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionStatus {
+    #[default]
+    Idle,
+    InTransaction,
+    /// An error occurred inside the transaction; only ROLLBACK (to a savepoint) will work
+    Failed,
+}
+
+impl TransactionStatus {
+    pub fn is_open(&self) -> bool {
+        !matches!(self, TransactionStatus::Idle)
+    }
+}
+
+impl fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionStatus::Idle => write!(f, "idle"),
+            TransactionStatus::InTransaction => write!(f, "in transaction"),
+            TransactionStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+pub(crate) struct SessionConnection {
+    url: String,
+    pub(crate) conn: PgConnection,
+    /// How long opening the connection took; taken by the first execution on it
+    pub(crate) connect_time: Duration,
+    /// Read once on connecting, since a failed transaction rejects the query
+    pub(crate) backend_pid: i32,
+}
+
+/// A dedicated connection that is kept open between executions, so that
+/// `BEGIN`/`COMMIT`/`ROLLBACK` and savepoints work across runs. Statements run
+/// on it as-is, without the implicit transaction normal executions get.
+/// Cheap to clone; clones share the connection.
+#[derive(Clone, Default)]
+pub struct Session {
+    connection: Arc<AsyncMutex<Option<SessionConnection>>>,
+    status: Arc<Mutex<TransactionStatus>>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> TransactionStatus {
+        *self.status.lock().unwrap()
+    }
+
+    /// Locks the session's connection for an execution, connecting to `url`
+    /// first if there is no connection yet or it was made to another database.
+    pub(crate) async fn acquire(&self, url: &str, connect_timeout: Duration) -> Result<OwnedMutexGuard<Option<SessionConnection>>> {
+        let mut guard = self.connection.clone().lock_owned().await;
+        if guard.as_ref().is_some_and(|c| c.url != url) {
+            if let Some(old) = guard.take() {
+                let _ = old.conn.close().await;
+            }
+            self.set_status(TransactionStatus::Idle);
+        }
+        if guard.is_none() {
+            let connect_start = Instant::now();
+            let mut conn = tokio::time::timeout(connect_timeout, PgConnection::connect(url))
+                .await
+                .map_err(|_| QueryTimedOut::new(TimeoutKind::Connect, Some(connect_timeout)))??;
+            let connect_time = connect_start.elapsed();
+            let backend_pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(&mut conn).await?;
+            *guard = Some(SessionConnection { url: url.to_string(), conn, connect_time, backend_pid });
+        }
+        Ok(guard)
+    }
+
+    /// Asks the server whether the connection is inside a transaction block.
+    /// `now()` is fixed at the start of the transaction, so it only differs
+    /// from the statement's own timestamp inside an explicit transaction; in
+    /// a failed transaction the probe itself is rejected. It has to use the
+    /// simple query protocol: with separate Parse and Bind messages, the two
+    /// timestamps differ even outside a transaction.
+    pub(crate) async fn refresh_status(&self, conn: &mut PgConnection) -> Result<TransactionStatus> {
+        let probe = conn
+            .fetch_one(sqlx::raw_sql("SELECT now() <> statement_timestamp()"))
+            .await
+            .and_then(|row| row.try_get::<bool, _>(0));
+        let status = match probe {
+            Ok(true) => TransactionStatus::InTransaction,
+            Ok(false) => TransactionStatus::Idle,
+            Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("25P02") => TransactionStatus::Failed,
+            Err(e) => return Err(e.into()),
+        };
+        self.set_status(status);
+        Ok(status)
+    }
+
+    fn set_status(&self, status: TransactionStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    pub async fn commit(&self) -> Result<()> {
+        self.end_transaction("COMMIT").await
+    }
+
+    pub async fn rollback(&self) -> Result<()> {
+        self.end_transaction("ROLLBACK").await
+    }
+
+    async fn end_transaction(&self, sql: &str) -> Result<()> {
+        let mut guard = self.connection.lock().await;
+        if let Some(session) = guard.as_mut() {
+            sqlx::raw_sql(sql).execute(&mut session.conn).await?;
+            self.refresh_status(&mut session.conn).await?;
+        }
+        Ok(())
+    }
+
+    /// Closes the connection; the server rolls back any open transaction.
+    pub async fn close(&self) {
+        if let Some(session) = self.connection.lock().await.take() {
+            let _ = session.conn.close().await;
+        }
+        self.set_status(TransactionStatus::Idle);
+    }
+}
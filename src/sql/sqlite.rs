@@ -0,0 +1,406 @@
+use anyhow::{bail, Result};
+use futures::{future::BoxFuture, TryStreamExt};
+use sqlx::{sqlite::{SqliteConnectOptions, SqliteConnection, SqliteRow}, Column, ConnectOptions, Connection, Either, Executor as _, Row, TypeInfo, ValueRef};
+use std::{
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+use super::{backend::{Backend, Target}, cancel::{CancelHandle, QueryCancelled}, interface::{ExecutionOptions, Executor}, params::QueryParams, result::{send, EventSender, QueryResult, QueryResultStream, StreamEvent, STREAM_BUFFER}, schema::{load_sqlite, DatabaseSchema}, session::Session, statement::{changes_rows, command_tag, split_statements}, timeout::{QueryTimedOut, TimeoutKind, Timeouts}, value::Value};
+
+/// How many virtual machine instructions SQLite runs between checks for a cancel or timeout
+const PROGRESS_INTERVAL: i32 = 1000;
+
+/// Whether `url` points at an SQLite database: an `sqlite:` URL, or a path to a database file
+pub fn is_sqlite_url(url: &str) -> bool {
+    if url.starts_with("sqlite:") {
+        return true;
+    }
+    if url.contains("://") {
+        return false;
+    }
+    let path = Path::new(url);
+    path.is_file() || path.extension().is_some_and(|ext| matches!(ext.to_str(), Some("db" | "sqlite" | "sqlite3")))
+}
+
+/// Runs queries on SQLite database files, opening the file for each execution
+#[derive(Clone, Copy, Default)]
+pub struct SqliteBackend;
+
+impl Backend for SqliteBackend {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn handles(&self, url: &str) -> bool {
+        is_sqlite_url(url)
+    }
+
+    fn connect<'a>(&'a self, target: &'a Target, timeouts: Timeouts) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let executor = SqliteExecutor::new(target.url.clone(), String::new(), ExecutionOptions::new().with_timeouts(timeouts));
+            executor.connect().await?.close().await?;
+            Ok(())
+        })
+    }
+
+    fn execute_stream(&self, target: &Target, sql: String, options: ExecutionOptions) -> Result<QueryResultStream> {
+        Ok(SqliteExecutor::new(target.url.clone(), sql, options).execute_stream())
+    }
+
+    fn schema<'a>(&'a self, target: &'a Target, timeouts: Timeouts) -> BoxFuture<'a, Result<DatabaseSchema>> {
+        Box::pin(async move {
+            let executor = SqliteExecutor::new(target.url.clone(), String::new(), ExecutionOptions::new().with_timeouts(timeouts));
+            let mut conn = executor.connect().await?;
+            let schema = load_sqlite(&mut conn).await;
+            let _ = conn.close().await;
+            schema
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteExecutor {
+    /// An `sqlite://` URL or a path to the database file
+    pub url: String,
+    pub sql: String,
+    /// Lets another task cancel this execution while it is running.
+    pub cancel: Option<CancelHandle>,
+    /// Values for the `$1`/`:name` placeholders in `sql`.
+    pub params: QueryParams,
+    /// Sessions need a dedicated server connection, so they are rejected.
+    pub session: Option<Session>,
+    /// The lock timeout is used as SQLite's busy timeout; the statement
+    /// timeout interrupts statements that run too long.
+    pub timeouts: Timeouts,
+    /// The statement stops being stepped once a result set has this many rows.
+    pub max_rows: Option<usize>,
+}
+
+impl Executor for SqliteExecutor {
+    async fn execute(&self) -> Result<Vec<QueryResult>> {
+        self.execute_stream().collect().await
+    }
+
+    fn execute_stream(&self) -> QueryResultStream {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let executor = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = executor.run(&sender).await {
+                let _ = sender.send(Err(e)).await;
+            }
+        });
+        QueryResultStream::new(receiver)
+    }
+}
+
+impl SqliteExecutor {
+    pub fn new(url: String, sql: String, options: ExecutionOptions) -> Self {
+        let ExecutionOptions { params, cancel, session, timeouts, max_rows } = options;
+        Self {
+            url,
+            sql,
+            cancel,
+            params,
+            session,
+            timeouts,
+            max_rows,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelHandle::is_cancelled)
+    }
+
+    fn connect_options(&self) -> Result<SqliteConnectOptions> {
+        let options = if self.url.starts_with("sqlite:") {
+            SqliteConnectOptions::from_str(&self.url)?
+        } else {
+            SqliteConnectOptions::new().filename(&self.url)
+        };
+        Ok(match self.timeouts.lock {
+            Some(lock) => options.busy_timeout(lock),
+            None => options,
+        })
+    }
+
+    async fn connect(&self) -> Result<SqliteConnection> {
+        let connect_timeout = self.timeouts.connect_or_default();
+        let conn = tokio::time::timeout(connect_timeout, self.connect_options()?.connect())
+            .await
+            .map_err(|_| QueryTimedOut::new(TimeoutKind::Connect, Some(connect_timeout)))??;
+        Ok(conn)
+    }
+
+    async fn run(&self, sender: &EventSender) -> Result<()> {
+        if self.session.is_some() {
+            bail!("Session mode is only supported for PostgreSQL connections");
+        }
+
+        let connect_start = Instant::now();
+        let mut conn = self.connect().await?;
+        let connect_time = connect_start.elapsed();
+
+        let outcome = async {
+            let mut tx = conn.begin().await?;
+            self.execute_statements(&mut tx, connect_time, sender).await?;
+            tx.commit().await?;
+            Ok(())
+        }.await;
+
+        let _ = conn.close().await;
+        outcome
+    }
+
+    async fn execute_statements(&self, conn: &mut SqliteConnection, mut connect_time: Duration, sender: &EventSender) -> Result<()> {
+        let statements = split_statements(&self.sql);
+
+        for (i, statement) in statements.iter().enumerate() {
+            if self.is_cancelled() {
+                return Err(QueryCancelled.into());
+            }
+            let deadline = self.timeouts.statement.map(|limit| Instant::now() + limit);
+            self.set_interrupt(conn, deadline).await?;
+            let summary = match execute_statement(conn, statement.text, &self.params, self.max_rows, sender).await {
+                Ok(summary) => summary,
+                // SQLite reports the interrupt as an error on the running statement
+                Err(_) if self.is_cancelled() => return Err(QueryCancelled.into()),
+                Err(_) if deadline.is_some_and(|d| Instant::now() >= d) => {
+                    return Err(QueryTimedOut::new(TimeoutKind::Statement, self.timeouts.statement).into());
+                }
+                Err(e) if self.timeouts.lock.is_some() && is_busy(&e) => {
+                    return Err(e.context(QueryTimedOut::new(TimeoutKind::Lock, self.timeouts.lock)));
+                }
+                Err(e) if statements.len() > 1 => {
+                    return Err(e.context(format!("Statement {} of {} failed", i + 1, statements.len())));
+                }
+                Err(e) => return Err(e),
+            };
+            let fetch_time = summary.fetch_time;
+            let summary = summary.with_timings(std::mem::take(&mut connect_time), fetch_time);
+            send(sender, StreamEvent::Done(summary)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Has SQLite abort the running statement once it is cancelled or passes `deadline`
+    async fn set_interrupt(&self, conn: &mut SqliteConnection, deadline: Option<Instant>) -> Result<()> {
+        if self.cancel.is_none() && deadline.is_none() {
+            return Ok(());
+        }
+        let cancel = self.cancel.clone();
+        conn.lock_handle().await?.set_progress_handler(PROGRESS_INTERVAL, move || {
+            let cancelled = cancel.as_ref().is_some_and(CancelHandle::is_cancelled);
+            let expired = deadline.is_some_and(|d| Instant::now() >= d);
+            !(cancelled || expired)
+        });
+        Ok(())
+    }
+}
+
+fn is_busy(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<sqlx::Error>(), Some(sqlx::Error::Database(e)) if e.code().as_deref() == Some("5"))
+}
+
+/// Streams a statement's rows to `sender`, returning its summary (without rows)
+async fn execute_statement(conn: &mut SqliteConnection, sql: &str, params: &QueryParams, max_rows: Option<usize>, sender: &EventSender) -> Result<QueryResult> {
+    let execute_start = Instant::now();
+    // SQLite converts the text values to the column's type where it needs to
+    let bound = params.bind(sql)?;
+    let mut query = sqlx::query(&bound.sql);
+    for value in bound.values {
+        query = query.bind(value);
+    }
+    let mut execution_time = None;
+    let mut columns = Vec::new();
+    let mut column_types = Vec::new();
+    let mut row_count = 0;
+    let mut rows_affected = 0;
+    let mut truncated = false;
+
+    let mut stream = conn.fetch_many(query);
+    while let Some(step) = stream.try_next().await? {
+        if execution_time.is_none() {
+            execution_time = Some(execute_start.elapsed());
+        }
+        match step {
+            Either::Left(done) => {
+                rows_affected += done.rows_affected();
+            }
+            Either::Right(row) => {
+                if columns.is_empty() {
+                    columns = row.columns()
+                        .iter()
+                        .map(|c| c.name().to_string())
+                        .collect();
+                    column_types = row.columns()
+                        .iter()
+                        .map(|c| c.type_info().name().to_string())
+                        .collect();
+                    send(sender, StreamEvent::Columns { names: columns.clone(), types: column_types.clone() }).await?;
+                }
+                if max_rows.is_some_and(|max| row_count >= max) {
+                    // Dropping the stream stops the statement: sqlx's worker quits stepping
+                    // it once nothing receives its rows, after at most a channel's worth
+                    truncated = true;
+                    break;
+                }
+
+                let values: Vec<Value> = (0..row.columns().len())
+                    .map(|i| convert_sqlite_value(&row, i))
+                    .collect();
+                row_count += 1;
+                send(sender, StreamEvent::Row(values)).await?;
+            }
+        }
+    }
+
+    let total_time = execute_start.elapsed();
+    let execution_time = execution_time.unwrap_or(total_time);
+    let fetch_time = total_time.saturating_sub(execution_time);
+
+    let returned_rows = !columns.is_empty();
+    let count = if returned_rows { row_count as u64 } else { rows_affected };
+    let tag = command_tag(sql, count, returned_rows);
+
+    let mut summary = QueryResult::new(columns, Vec::new(), execution_time)
+        .with_timings(Duration::ZERO, fetch_time)
+        .with_command_tag(tag)
+        .with_rows_affected((changes_rows(sql) && !truncated).then_some(rows_affected))
+        .with_column_types(column_types);
+    summary.row_count = row_count;
+    summary.truncated = truncated;
+    Ok(summary)
+}
+
+/// Decodes a value by its storage class, since SQLite columns may hold any type
+fn convert_sqlite_value(row: &SqliteRow, idx: usize) -> Value {
+    let Ok(raw) = row.try_get_raw(idx) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let storage_class = raw.type_info().name().to_string();
+    let value = match storage_class.as_str() {
+        "INTEGER" => row.try_get_unchecked::<i64, _>(idx).map(Value::Int),
+        "REAL" => row.try_get_unchecked::<f64, _>(idx).map(Value::Float),
+        "BLOB" => row.try_get_unchecked::<Vec<u8>, _>(idx).map(Value::Bytes),
+        _ => row.try_get_unchecked::<String, _>(idx).map(Value::Text),
+    };
+    value.unwrap_or(Value::Unknown(storage_class))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    use crate::sql::backend::{Backend, Target};
+    use crate::sql::cancel::{CancelHandle, QueryCancelled};
+    use crate::sql::interface::Executor;
+    use crate::sql::params::QueryParams;
+    use crate::sql::session::Session;
+    use crate::sql::sqlite::{is_sqlite_url, SqliteBackend, SqliteExecutor};
+    use crate::sql::timeout::{QueryTimedOut, TimeoutKind, Timeouts};
+    use crate::sql::value::Value;
+
+    fn executor(url: &str, sql: &str) -> SqliteExecutor {
+        SqliteExecutor {
+            url: url.to_string(),
+            sql: sql.to_string(),
+            cancel: None,
+            params: QueryParams::new(),
+            session: None,
+            timeouts: Timeouts::new(),
+            max_rows: None,
+        }
+    }
+
+    fn create_test_db() -> Result<(TempDir, String)> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("fixtures.db");
+        std::fs::File::create(&path)?;
+        Ok((dir, path.to_string_lossy().to_string()))
+    }
+
+    #[test]
+    fn test_is_sqlite_url() {
+        assert!(is_sqlite_url("sqlite://fixtures.db"));
+        assert!(is_sqlite_url("sqlite::memory:"));
+        assert!(is_sqlite_url("./data/fixtures.sqlite3"));
+        assert!(!is_sqlite_url("postgresql://localhost/db"));
+        assert!(!is_sqlite_url("mysql://localhost/db"));
+        assert!(!is_sqlite_url("not-a-database"));
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_executor() -> Result<()> {
+        let (_dir, path) = create_test_db()?;
+        let setup = "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, score REAL, avatar BLOB);
+            INSERT INTO users (name, score, avatar) VALUES ('alice', 1.5, x'0102'), ('bob', NULL, NULL);";
+        let results = executor(&path, setup).execute().await?;
+        assert_eq!(results[1].command_tag, "INSERT 0 2");
+
+        let mut select = executor(&format!("sqlite://{}", path), "SELECT id, name, score, avatar FROM users WHERE id >= $1 ORDER BY id");
+        select.params = QueryParams::from_args(&["1".to_string()]);
+        let results = select.execute().await?;
+        assert_eq!(results[0].columns, vec!["id", "name", "score", "avatar"]);
+        assert_eq!(results[0].column_types, vec!["INTEGER", "TEXT", "REAL", "BLOB"]);
+        assert_eq!(results[0].rows, vec![
+            vec![Value::Int(1), "alice".into(), Value::Float(1.5), Value::Bytes(vec![1, 2])],
+            vec![Value::Int(2), "bob".into(), Value::Null, Value::Null],
+        ]);
+        assert_eq!(results[0].command_tag, "SELECT 2");
+
+        // A failing statement rolls back the whole script
+        let err = executor(&path, "DELETE FROM users; SELECT * FROM missing").execute().await.unwrap_err();
+        assert!(format!("{:#}", err).starts_with("Statement 2 of 2 failed: "));
+        let results = executor(&path, "SELECT count(*) AS n FROM users").execute().await?;
+        assert_eq!(results[0].rows[0][0], Value::Int(2));
+
+        let target = Target::new(&path, &path);
+        SqliteBackend.connect(&target, Timeouts::new()).await?;
+        let missing = Target::new("missing", "sqlite:///nonexistent/dir/test.db");
+        assert!(SqliteBackend.connect(&missing, Timeouts::new()).await.is_err());
+
+        executor(&path, "CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users(id), title TEXT UNIQUE)").execute().await?;
+        let schema = SqliteBackend.schema(&target, Timeouts::new()).await?;
+        let posts = schema.find_table("posts").expect("posts is listed");
+        let columns: Vec<(&str, &str, bool)> = posts.columns.iter().map(|c| (c.name.as_str(), c.data_type.as_str(), c.nullable)).collect();
+        assert_eq!(columns, [("id", "INTEGER", true), ("user_id", "INTEGER", true), ("title", "TEXT", true)]);
+        assert_eq!(posts.primary_key().map(|pk| pk.definition.as_str()), Some("PRIMARY KEY (id)"));
+        assert_eq!(posts.foreign_keys().map(|fk| fk.definition.as_str()).collect::<Vec<_>>(), ["FOREIGN KEY (user_id) REFERENCES users(id)"]);
+        assert_eq!(posts.indexes.iter().map(|i| (i.columns.clone(), i.unique)).collect::<Vec<_>>(), [(vec!["title".to_string()], true)]);
+
+        let mut session = executor(&path, "SELECT 1");
+        session.session = Some(Session::new());
+        assert!(session.execute().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_executor_interrupts() -> Result<()> {
+        let (_dir, path) = create_test_db()?;
+        let endless = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT count(*) FROM n";
+
+        let mut slow = executor(&path, endless);
+        slow.timeouts = Timeouts::new().with_statement(Some(Duration::from_millis(100)));
+        let err = slow.execute().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<QueryTimedOut>().map(|t| t.kind), Some(TimeoutKind::Statement));
+
+        let cancel = CancelHandle::new();
+        let mut cancelled = executor(&path, endless);
+        cancelled.cancel = Some(cancel.clone());
+        let running = tokio::spawn(async move { cancelled.execute().await });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        SqliteBackend.cancel(&cancel).await?;
+        assert!(running.await?.unwrap_err().is::<QueryCancelled>());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+
+use super::lexer::{tokenize, TokenKind};
+
+/// A single statement within a larger script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    /// The statement text, without surrounding whitespace/comments or the terminating `;`
+    pub text: &'a str,
+    /// Byte offset of `text` within the original script
+    pub start: usize,
+}
+
+/// Splits a script into statements on `;`, ignoring semicolons inside comments,
+/// string literals, quoted identifiers and dollar-quoted bodies. Statements that
+/// contain only whitespace and comments are dropped.
+pub fn split_statements(sql: &str) -> Vec<Statement<'_>> {
+    let mut statements = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+
+    for token in tokenize(sql) {
+        if token.kind == TokenKind::Punctuation && token.text == ";" {
+            if let Some((start, end)) = current.take() {
+                statements.push(Statement { text: &sql[start..end], start });
+            }
+            continue;
+        }
+        if token.is_trivia() {
+            continue;
+        }
+        current = match current {
+            Some((start, _)) => Some((start, token.end())),
+            None => Some((token.start, token.end())),
+        };
+    }
+
+    if let Some((start, end)) = current {
+        statements.push(Statement { text: &sql[start..end], start });
+    }
+
+    statements
+}
+
+/// Quotes an identifier for use in SQL, e.g. `"First Name"`, so it is taken exactly as written
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Leading keywords of a statement, uppercased, skipping comments and whitespace.
+pub fn leading_keywords(sql: &str, count: usize) -> Vec<String> {
+    tokenize(sql)
+        .into_iter()
+        .filter(|t| !t.is_trivia())
+        .take_while(|t| t.kind == TokenKind::Word)
+        .take(count)
+        .map(|t| t.text.to_uppercase())
+        .collect()
+}
+
+/// Builds a psql-style command tag (e.g. `INSERT 0 3`, `UPDATE 2`, `CREATE TABLE`)
+/// for a statement, given the number of rows it affected or returned.
+pub fn command_tag(sql: &str, rows: u64, returned_rows: bool) -> String {
+    let keywords = leading_keywords(sql, 6);
+    let Some(first) = keywords.first() else {
+        return String::new();
+    };
+
+    match first.as_str() {
+        "SELECT" | "VALUES" | "TABLE" => format!("SELECT {}", rows),
+        "WITH" if returned_rows => format!("SELECT {}", rows),
+        "WITH" => match main_dml_keyword(sql) {
+            Some(keyword) if keyword == "INSERT" => format!("INSERT 0 {}", rows),
+            Some(keyword) => format!("{} {}", keyword, rows),
+            None => format!("SELECT {}", rows),
+        },
+        "INSERT" => format!("INSERT 0 {}", rows),
+        "UPDATE" | "DELETE" | "MERGE" | "MOVE" | "FETCH" | "COPY" => format!("{} {}", first, rows),
+        "CREATE" | "DROP" | "ALTER" => {
+            let object: Vec<&str> = keywords[1..]
+                .iter()
+                .map(String::as_str)
+                .filter(|k| !matches!(*k, "OR" | "REPLACE" | "UNIQUE" | "TEMP" | "TEMPORARY" | "UNLOGGED" | "GLOBAL" | "LOCAL" | "RECURSIVE" | "TRUSTED" | "PROCEDURAL"))
+                .collect();
+            match object.as_slice() {
+                [kind @ ("MATERIALIZED" | "FOREIGN" | "EVENT" | "ACCESS" | "OPERATOR" | "TEXT"), next, ..] => {
+                    format!("{} {} {}", first, kind, next)
+                }
+                [kind, ..] => format!("{} {}", first, kind),
+                [] => first.clone(),
+            }
+        }
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "BEGIN" => "BEGIN".to_string(),
+        "START" => "START TRANSACTION".to_string(),
+        "COMMIT" | "END" => "COMMIT".to_string(),
+        "ROLLBACK" | "ABORT" => "ROLLBACK".to_string(),
+        _ => first.clone(),
+    }
+}
+
+/// Whether a statement inserts, updates, deletes, merges or copies rows, so the
+/// count the server reports for it is of rows affected
+pub fn changes_rows(sql: &str) -> bool {
+    match leading_keywords(sql, 1).first().map(String::as_str) {
+        Some("INSERT" | "UPDATE" | "DELETE" | "MERGE" | "COPY") => true,
+        Some("WITH") => main_dml_keyword(sql).is_some(),
+        _ => false,
+    }
+}
+
+/// What `limit_rows` puts ahead of the statement, on a line of its own
+pub const LIMIT_PREFIX: &str = "SELECT * FROM (\n";
+
+/// Wraps a query in a `LIMIT`, so the server stops once it has produced `limit` rows.
+/// Only plain queries can be nested in a subquery; for anything else, e.g. an
+/// `UPDATE ... RETURNING` or a `SELECT ... INTO`, it returns None.
+pub fn limit_rows(sql: &str, limit: usize) -> Option<String> {
+    if !matches!(leading_keywords(sql, 1).first().map(String::as_str), Some("SELECT" | "VALUES" | "TABLE" | "WITH")) {
+        return None;
+    }
+    let words: Vec<String> = tokenize(sql)
+        .iter()
+        .filter(|t| t.kind == TokenKind::Word)
+        .map(|t| t.text.to_uppercase())
+        .collect();
+    let modifies = words.iter().enumerate().any(|(i, word)| match word.as_str() {
+        "INSERT" | "DELETE" | "MERGE" | "INTO" => true,
+        // `FOR UPDATE` and `FOR NO KEY UPDATE` only lock the rows
+        "UPDATE" => !matches!(i.checked_sub(1).map(|i| words[i].as_str()), Some("FOR" | "KEY")),
+        _ => false,
+    });
+    // The closing parenthesis goes on its own line, in case the query ends in a `--` comment
+    (!modifies).then(|| format!("{LIMIT_PREFIX}{sql}\n) AS limited LIMIT {limit}"))
+}
+
+/// Finds the data-modifying keyword of a `WITH ...` statement at the outermost level.
+fn main_dml_keyword(sql: &str) -> Option<String> {
+    let mut depth = 0;
+    tokenize(sql)
+        .iter()
+        .filter(|t| !t.is_trivia())
+        .find_map(|t| {
+            match (t.kind, t.text) {
+                (TokenKind::Punctuation, "(") => depth += 1,
+                (TokenKind::Punctuation, ")") => depth -= 1,
+                (TokenKind::Word, word) if depth == 0 => {
+                    let word = word.to_uppercase();
+                    if matches!(word.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE") {
+                        return Some(word);
+                    }
+                }
+                _ => {}
+            }
+            None
+        })
+}
+
+/// A statement that destroys data in bulk, flagged so it can be confirmed before running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DestructiveStatement<'a> {
+    pub statement: Statement<'a>,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for DestructiveStatement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MAX_LEN: usize = 60;
+        let first_line = self.statement.text.lines().next().unwrap_or_default();
+        if first_line.chars().count() > MAX_LEN || first_line.len() < self.statement.text.len() {
+            let snippet: String = first_line.chars().take(MAX_LEN).collect();
+            write!(f, "{}: {}...", self.reason, snippet.trim_end())
+        } else {
+            write!(f, "{}: {}", self.reason, first_line)
+        }
+    }
+}
+
+/// The statements in a script that are `DELETE`/`UPDATE` without a `WHERE`
+/// clause, `DROP` or `TRUNCATE`.
+pub fn destructive_statements(sql: &str) -> Vec<DestructiveStatement<'_>> {
+    split_statements(sql)
+        .into_iter()
+        .filter_map(|statement| {
+            destructive_reason(statement.text).map(|reason| DestructiveStatement { statement, reason })
+        })
+        .collect()
+}
+
+fn destructive_reason(sql: &str) -> Option<&'static str> {
+    let tokens: Vec<_> = tokenize(sql).into_iter().filter(|t| !t.is_trivia()).collect();
+    let first = tokens.first()?;
+    if first.is_word("DROP") {
+        return Some("DROP");
+    }
+    if first.is_word("TRUNCATE") {
+        return Some("TRUNCATE");
+    }
+
+    // The statement's own verb and WHERE are outside any parentheses; CTE
+    // bodies and subqueries are always parenthesized.
+    let mut depth = 0;
+    let mut verb = None;
+    let mut has_where = false;
+    for token in &tokens {
+        match (token.kind, token.text) {
+            (TokenKind::Punctuation, "(") => depth += 1,
+            (TokenKind::Punctuation, ")") => depth -= 1,
+            (TokenKind::Word, _) if depth == 0 => {
+                if verb.is_none() && ["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "VALUES", "TABLE"].iter().any(|w| token.is_word(w)) {
+                    verb = Some(token);
+                }
+                has_where |= token.is_word("WHERE");
+            }
+            _ => {}
+        }
+    }
+
+    match verb {
+        Some(verb) if verb.is_word("DELETE") && !has_where => Some("DELETE without WHERE"),
+        Some(verb) if verb.is_word("UPDATE") && !has_where => Some("UPDATE without WHERE"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changes_rows, command_tag, destructive_statements, limit_rows, split_statements};
+
+    fn texts(sql: &str) -> Vec<&str> {
+        split_statements(sql).into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(texts("SELECT 1; SELECT 2;"), vec!["SELECT 1", "SELECT 2"]);
+        assert_eq!(texts("SELECT ';' -- ;\n; /* ; */"), vec!["SELECT ';'"]);
+        assert_eq!(
+            texts("CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql;\nSELECT f()"),
+            vec!["CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql", "SELECT f()"]
+        );
+        assert!(texts("  -- only a comment\n;;").is_empty());
+
+        let statements = split_statements("SELECT 1;\n  SELECT 2");
+        assert_eq!(statements[1].start, 12);
+    }
+
+    #[test]
+    fn test_command_tag() {
+        assert_eq!(command_tag("insert into t values (1), (2), (3)", 3, false), "INSERT 0 3");
+        assert_eq!(command_tag("-- comment\nUPDATE t SET a = 1", 2, false), "UPDATE 2");
+        assert_eq!(command_tag("SELECT * FROM t", 5, true), "SELECT 5");
+        assert_eq!(command_tag("CREATE OR REPLACE FUNCTION f()", 0, false), "CREATE FUNCTION");
+        assert_eq!(command_tag("create materialized view v as select 1", 0, false), "CREATE MATERIALIZED VIEW");
+        assert_eq!(command_tag("WITH x AS (SELECT 1) DELETE FROM t", 4, false), "DELETE 4");
+        assert_eq!(command_tag("truncate t", 0, false), "TRUNCATE TABLE");
+
+        assert!(changes_rows("insert into t values (1) returning id"));
+        assert!(changes_rows("WITH x AS (SELECT 1) DELETE FROM t"));
+        assert!(!changes_rows("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(!changes_rows("CREATE TABLE t (id int)"));
+    }
+
+    #[test]
+    fn test_limit_rows() {
+        assert_eq!(limit_rows("SELECT * FROM t -- all of it", 4).as_deref(), Some("SELECT * FROM (\nSELECT * FROM t -- all of it\n) AS limited LIMIT 4"));
+        assert!(limit_rows("values (1), (2)", 4).is_some());
+        assert!(limit_rows("SELECT * FROM t FOR NO KEY UPDATE", 4).is_some());
+        assert!(limit_rows("WITH x AS (DELETE FROM t RETURNING *) SELECT * FROM x", 4).is_none());
+        assert!(limit_rows("SELECT * INTO copy FROM t", 4).is_none());
+        assert!(limit_rows("UPDATE t SET a = 1 RETURNING id", 4).is_none());
+        assert!(limit_rows("EXPLAIN SELECT 1", 4).is_none());
+    }
+
+    #[test]
+    fn test_destructive_statements() {
+        let reasons = |sql| destructive_statements(sql).iter().map(|d| d.reason).collect::<Vec<_>>();
+
+        assert_eq!(
+            reasons("DELETE FROM t; delete from t where id = 1; UPDATE t SET a = (SELECT b FROM u WHERE u.id = t.id)"),
+            vec!["DELETE without WHERE", "UPDATE without WHERE"]
+        );
+        assert_eq!(reasons("drop table t; TRUNCATE t; WITH x AS (SELECT 1 WHERE true) DELETE FROM t"), vec!["DROP", "TRUNCATE", "DELETE without WHERE"]);
+        assert!(reasons("SELECT * FROM t FOR UPDATE; UPDATE t SET a = 1 WHERE id = 2; SELECT 'DROP TABLE t'").is_empty());
+
+        let flagged = destructive_statements("SELECT 1;\nDELETE FROM users");
+        assert_eq!(flagged[0].statement.start, 10);
+        assert_eq!(flagged[0].to_string(), "DELETE without WHERE: DELETE FROM users");
+    }
+}
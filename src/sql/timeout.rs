@@ -0,0 +1,172 @@
+use anyhow::{anyhow, bail, Result};
+use std::{fmt, time::Duration};
+
+use super::error::QueryError;
+
+/// How long to wait for a connection when no `connect_timeout` is configured
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Limits on how long an execution may take; unset limits use the server's defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Aborts any statement running longer than this (`statement_timeout`)
+    pub statement: Option<Duration>,
+    /// Aborts any statement waiting longer than this for a lock (`lock_timeout`)
+    pub lock: Option<Duration>,
+    /// Gives up on connecting after this long
+    pub connect: Option<Duration>,
+}
+
+impl Timeouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_statement(mut self, timeout: Option<Duration>) -> Self {
+        self.statement = timeout;
+        self
+    }
+
+    /// Fills limits not set here from `fallback`, e.g. a run's limits from its connection's
+    pub fn or(self, fallback: Timeouts) -> Self {
+        Self {
+            statement: self.statement.or(fallback.statement),
+            lock: self.lock.or(fallback.lock),
+            connect: self.connect.or(fallback.connect),
+        }
+    }
+
+    pub fn connect_or_default(&self) -> Duration {
+        self.connect.unwrap_or(DEFAULT_CONNECT_TIMEOUT)
+    }
+
+    /// `SET` statements applying the statement and lock timeouts, if any are set.
+    /// `local` limits them to the current transaction.
+    pub(crate) fn settings_sql(&self, local: bool) -> Option<String> {
+        let scope = if local { "SET LOCAL" } else { "SET" };
+        let settings: Vec<String> = [("statement_timeout", self.statement), ("lock_timeout", self.lock)]
+            .into_iter()
+            .filter_map(|(name, timeout)| timeout.map(|t| format!("{} {} = {}", scope, name, t.as_millis())))
+            .collect();
+        (!settings.is_empty()).then(|| settings.join("; "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+    Statement,
+    Lock,
+    Connect,
+}
+
+/// Returned by an execution that hit one of its `Timeouts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryTimedOut {
+    pub kind: TimeoutKind,
+    pub limit: Option<Duration>,
+}
+
+impl QueryTimedOut {
+    pub fn new(kind: TimeoutKind, limit: Option<Duration>) -> Self {
+        Self { kind, limit }
+    }
+
+    /// Recognizes the server's timeout errors, and the pool's, among execution errors
+    pub(crate) fn from_error(error: &anyhow::Error, timeouts: &Timeouts) -> Option<Self> {
+        if let Some(e) = error.downcast_ref::<QueryError>() {
+            return Self::from_server_error(&e.code, &e.message, timeouts);
+        }
+        match error.downcast_ref::<sqlx::Error>()? {
+            sqlx::Error::PoolTimedOut => Some(Self::new(TimeoutKind::Connect, Some(timeouts.connect_or_default()))),
+            sqlx::Error::Database(e) => Self::from_server_error(e.code().as_deref()?, e.message(), timeouts),
+            _ => None,
+        }
+    }
+
+    fn from_server_error(code: &str, message: &str, timeouts: &Timeouts) -> Option<Self> {
+        match code {
+            "57014" if message.contains("statement timeout") => Some(Self::new(TimeoutKind::Statement, timeouts.statement)),
+            "55P03" if message.contains("lock timeout") => Some(Self::new(TimeoutKind::Lock, timeouts.lock)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for QueryTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = self.limit.map(|l| format!(" of {}", format_duration(l))).unwrap_or_default();
+        match self.kind {
+            TimeoutKind::Statement => write!(f, "Query timed out: it ran longer than the statement timeout{}", limit),
+            TimeoutKind::Lock => write!(f, "Query timed out: it waited longer than the lock timeout{} for a lock", limit),
+            TimeoutKind::Connect => write!(f, "Timed out connecting to the database: no connection within the connect timeout{}", limit),
+        }
+    }
+}
+
+impl std::error::Error for QueryTimedOut {}
+
+/// Parses a duration like `30s`, `500ms`, `2min` or `1h`; a bare number is in seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let invalid = || anyhow!("Invalid duration: '{}'. Expected e.g. 30s, 500ms, 2min", s);
+    let value: f64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "" | "s" | "sec" => value,
+        "ms" => value / 1000.0,
+        "m" | "min" => value * 60.0,
+        "h" => value * 3600.0,
+        other => bail!("Invalid duration unit '{}' in '{}'. Supported units: ms, s, min, h", other, s),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+/// Formats a duration in the largest unit that keeps it whole, e.g. `30s` or `1500ms`
+pub fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_millis();
+    match ms {
+        0 => "0s".to_string(),
+        ms if ms.is_multiple_of(3_600_000) => format!("{}h", ms / 3_600_000),
+        ms if ms.is_multiple_of(60_000) => format!("{}min", ms / 60_000),
+        ms if ms.is_multiple_of(1000) => format!("{}s", ms / 1000),
+        ms => format!("{}ms", ms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_duration, parse_duration, Timeouts};
+
+    #[test]
+    fn test_parse_and_format_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration(" 2min ").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1.5").unwrap(), Duration::from_millis(1500));
+        assert!(parse_duration("10 days").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("99999999999999999999999h").is_err());
+
+        assert_eq!(format_duration(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[test]
+    fn test_timeout_settings() {
+        let timeouts = Timeouts {
+            statement: Some(Duration::from_secs(30)),
+            lock: None,
+            connect: Some(Duration::from_secs(2)),
+        };
+        assert_eq!(timeouts.settings_sql(true).as_deref(), Some("SET LOCAL statement_timeout = 30000"));
+        assert_eq!(Timeouts::new().settings_sql(false), None);
+
+        let run = Timeouts::new().with_statement(Some(Duration::from_secs(1)));
+        assert_eq!(run.or(timeouts).statement, Some(Duration::from_secs(1)));
+        assert_eq!(run.or(timeouts).connect, Some(Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,164 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+use std::fmt;
+
+/// A single cell of a query result, decoded from the database's type
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Arbitrary precision number, kept as text so no digits are lost
+    Numeric(String),
+    Text(String),
+    Json(serde_json::Value),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Bytes(Vec<u8>),
+    /// Shown in Postgres' text form, e.g. `{a,NULL,"b c"}`
+    Array(Vec<Value>),
+    /// A value of a type that can't be decoded, shown by its type name
+    Unknown(String),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", format_float(*v)),
+            Value::Numeric(v) | Value::Text(v) | Value::Unknown(v) => write!(f, "{}", v),
+            Value::Json(v) => write!(f, "{}", v),
+            Value::Date(v) => write_date(f, v, None),
+            Value::Time(v) => write_time(f, v),
+            Value::Timestamp(v) => write_date(f, &v.date(), Some(&v.time())),
+            Value::TimestampTz(v) => {
+                let v = v.naive_utc();
+                write_date(f, &v.date(), Some(&v.time()))?;
+                write!(f, "+00")
+            }
+            Value::Bytes(v) => {
+                write!(f, "\\x")?;
+                v.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+            Value::Array(items) => {
+                write!(f, "{{")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    match item {
+                        Value::Null | Value::Array(_) => write!(f, "{}", item)?,
+                        item => write!(f, "{}", quote_array_element(&item.to_string()))?,
+                    }
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Writes a date (and time) the way Postgres does with the default `ISO` DateStyle,
+/// e.g. `2024-02-29 13:14:15.5`, with a `BC` suffix for years before 1 AD
+fn write_date(f: &mut fmt::Formatter<'_>, date: &NaiveDate, time: Option<&NaiveTime>) -> fmt::Result {
+    let year = date.year();
+    write!(f, "{:04}-{:02}-{:02}", if year > 0 { year } else { 1 - year }, date.month(), date.day())?;
+    if let Some(time) = time {
+        write!(f, " ")?;
+        write_time(f, time)?;
+    }
+    if year <= 0 {
+        write!(f, " BC")?;
+    }
+    Ok(())
+}
+
+/// Writes a time with as many fractional digits as needed, up to microseconds
+fn write_time(f: &mut fmt::Formatter<'_>, time: &NaiveTime) -> fmt::Result {
+    write!(f, "{:02}:{:02}:{:02}", time.hour(), time.minute(), time.second())?;
+    let micros = time.nanosecond() / 1000;
+    if micros != 0 {
+        write!(f, ".{}", format!("{:06}", micros).trim_end_matches('0'))?;
+    }
+    Ok(())
+}
+
+/// Quotes an array element if Postgres would: when it is empty, could be read as
+/// NULL, or contains whitespace or one of the array syntax characters
+fn quote_array_element(s: &str) -> String {
+    let needs_quotes = s.is_empty()
+        || s.eq_ignore_ascii_case("NULL")
+        || s.chars().any(|c| c.is_whitespace() || matches!(c, '{' | '}' | ',' | '"' | '\\'));
+    if !needs_quotes {
+        return s.to_string();
+    }
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Formats a float like Postgres: the shortest exact representation, switching to
+/// exponent notation for very large or small magnitudes, and `Infinity`/`NaN`.
+pub fn format_float(v: f64) -> String {
+    if v.is_nan() {
+        return "NaN".to_string();
+    }
+    if v.is_infinite() {
+        return if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if v == 0.0 {
+        return v.to_string();
+    }
+
+    let scientific = format!("{:e}", v);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    if (-4..15).contains(&exponent) {
+        v.to_string()
+    } else {
+        format!("{}e{}{:02}", mantissa, if exponent < 0 { '-' } else { '+' }, exponent.abs())
+    }
+}
+
+/// Numbers, booleans, JSON and arrays keep their JSON types and NULL becomes `null`;
+/// everything else (including NUMERIC, to preserve precision, and the non-finite
+/// floats JSON has no number for) is written as its text.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Int(v) => serializer.serialize_i64(*v),
+            Value::Float(v) if v.is_finite() => serializer.serialize_f64(*v),
+            Value::Json(v) => v.serialize(serializer),
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            other => serializer.collect_str(other),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
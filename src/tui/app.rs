@@ -1,25 +1,45 @@
 use anyhow::Result;
+use chrono::Local;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
 use crossterm::event::{KeyCode, KeyModifiers};
 use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::layout::Rect;
 use ratatui::widgets::Block;
 use ratatui::widgets::Borders;
-use tui_textarea::TextArea;
+use tokio::sync::mpsc;
+use tui_textarea::{CursorMove, TextArea};
 use tui_tree_widget::{TreeItem, TreeState};
 
 use crate::collection::{CollectionScope, SelectedFile};
-use crate::config::CONFIG_FILE_NAME;
+use crate::config::{Connection, CONFIG_FILE_NAME};
 use crate::file::{get_selected_folder_context, parse_selected_file, FileSystem};
+use crate::history::{History, HistoryEntry};
 use crate::query::{self, execute_query};
 use crate::settings::UserSettings;
+use crate::sql::backend::{Backend, BackendRegistry};
+use crate::sql::cancel::{CancelHandle, QueryCancelled};
+use crate::sql::error::QueryError;
+use crate::sql::explain::{explain_sql, ExplainMode, ExplainPlan, PlanNode};
+use crate::sql::listen::{ListenEvent, Listener, Notification};
+use crate::sql::params::{placeholders, Placeholder, QueryParams};
+use crate::sql::interface::ExecutionOptions;
 use crate::sql::result::QueryResult;
+use crate::sql::schema::{DatabaseSchema, Table};
+use crate::sql::session::{Session, TransactionStatus};
+use crate::sql::statement::destructive_statements;
+use crate::sql::timeout::QueryTimedOut;
 
 use super::modal::{ModalEvent, ModalManager, ModalType};
 use super::navigation::{NavigationManager, PaneId};
 use super::ui::UI;
 use super::widgets::edit_file_modal::EditFileModal;
+use super::widgets::history_modal::HistoryModal;
+use super::widgets::listen_modal::ListenModal;
 use super::widgets::modal::ModalAction;
 use super::widgets::new_file_modal::NewFileModal;
+use super::widgets::params_modal::ParamsModal;
 use super::widgets::password_modal::PasswordModal;
 use super::widgets::searchable_textarea::SearchableTextArea;
 
@@ -29,12 +49,21 @@ pub enum Mode {
     Password,
     NewFile,
     EditFile,
+    Params,
+    EndTransaction,
+    ConfirmDestructive,
+    Listen,
+    TableDdl,
+    History,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AppCommand {
     None,
     ExecuteQuery,
+    ExplainQuery(ExplainMode),
+    CancelQuery,
+    ToggleSession,
     SaveQuery,
     CreateFile,
     EditFile,
@@ -45,6 +74,10 @@ pub enum AppCommand {
 pub struct AsyncCommandResult {
     pub command: AppCommand,
     pub message: Option<String>,
+    pub query_results: Option<Vec<QueryResult>>,
+    pub explain_plan: Option<ExplainPlan>,
+    /// The server's error, when the query failed with one
+    pub query_error: Option<QueryError>,
 }
 
 impl AsyncCommandResult {
@@ -52,6 +85,9 @@ impl AsyncCommandResult {
         Self {
             command,
             message: None,
+            query_results: None,
+            explain_plan: None,
+            query_error: None,
         }
     }
 
@@ -59,8 +95,49 @@ impl AsyncCommandResult {
         Self {
             command,
             message: Some(message),
+            query_results: None,
+            explain_plan: None,
+            query_error: None,
         }
     }
+
+    pub fn with_query_results(command: AppCommand, query_results: Vec<QueryResult>) -> Self {
+        Self {
+            command,
+            message: None,
+            query_results: Some(query_results),
+            explain_plan: None,
+            query_error: None,
+        }
+    }
+
+    pub fn with_explain_plan(command: AppCommand, explain_plan: ExplainPlan) -> Self {
+        Self {
+            command,
+            message: None,
+            query_results: None,
+            explain_plan: Some(explain_plan),
+            query_error: None,
+        }
+    }
+
+    pub fn with_query_error(command: AppCommand, message: String, query_error: Option<QueryError>) -> Self {
+        Self {
+            command,
+            message: Some(message),
+            query_results: None,
+            explain_plan: None,
+            query_error,
+        }
+    }
+}
+
+/// What the left-hand pane lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidebarTab {
+    Collections,
+    /// The selected connection's schemas, tables and their columns
+    Schema,
 }
 
 // UI-specific state
@@ -68,7 +145,14 @@ pub struct UIState<'a> {
     pub message: String,
     pub collection_state: TreeState<String>,
     pub collection_items: Vec<TreeItem<'a, String>>,
+    /// Open and selected nodes of the plan tree, identified by child index
+    pub explain_state: TreeState<usize>,
+    pub sidebar: SidebarTab,
+    /// Open and selected nodes of the schema tree, identified by name
+    pub schema_state: TreeState<String>,
     pub workspace: SearchableTextArea<'a>,
+    /// Path in the collection tree of the file loaded into the workspace
+    pub loaded_file: Option<String>,
     pub search: SearchBox<'a>,
 }
 
@@ -95,14 +179,136 @@ impl Default for SearchBox<'_> {
     }
 }
 
+/// Most notifications kept while listening; older ones are dropped
+pub const MAX_NOTIFICATIONS: usize = 200;
+
+/// Notifications received on the channels being listened to, oldest first
+pub struct ListenState {
+    pub connection: String,
+    pub channels: Vec<String>,
+    pub notifications: VecDeque<Notification>,
+    /// Why the connection was lost, while it is being re-established
+    pub disconnected: Option<String>,
+    /// Events from the listener task; an error means it could not start listening
+    events: Option<mpsc::UnboundedReceiver<Result<ListenEvent, String>>>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl ListenState {
+    pub fn new(connection: impl Into<String>, channels: Vec<String>) -> Self {
+        Self {
+            connection: connection.into(),
+            channels,
+            notifications: VecDeque::new(),
+            disconnected: None,
+            events: None,
+            task: None,
+        }
+    }
+
+    pub fn push(&mut self, notification: Notification) {
+        if self.notifications.len() == MAX_NOTIFICATIONS {
+            self.notifications.pop_front();
+        }
+        self.notifications.push_back(notification);
+    }
+}
+
+/// Stops listening
+impl Drop for ListenState {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
 // Query-related state
 pub struct QueryState {
     pub selected_connection: Option<String>,
     pub available_connections: Vec<String>, 
+    /// Config of each available connection, for showing its safety profile
+    pub connection_configs: HashMap<String, Connection>,
     pub current_password: Option<String>, 
-    pub query_result: QueryResult,
+    /// One result per executed statement
+    pub query_results: Vec<QueryResult>,
+    pub selected_result: usize,
+    /// Set when the last query was stopped by the user rather than finishing
+    pub query_cancelled: bool,
     pub pending_command: AppCommand,
     pub pending_async_operation: Option<tokio::task::JoinHandle<AsyncCommandResult>>,
+    /// Cancels the query in `pending_async_operation`, if one is running
+    pub cancel_handle: Option<CancelHandle>,
+    /// The backend running that query, which `cancel_handle` is passed to
+    pub cancel_backend: Option<Arc<dyn Backend>>,
+    /// Runs queries on the selected connection's database; keeps connections between runs
+    pub backends: BackendRegistry,
+    /// Last parameter values entered for each file, keyed by its collection path
+    pub param_values: HashMap<String, QueryParams>,
+    /// Set while in session mode: queries share one connection and transactions span runs
+    pub session: Option<Session>,
+    /// Set while running an EXPLAIN instead of the query itself
+    pub explain_mode: Option<ExplainMode>,
+    /// The plan from the last EXPLAIN, shown instead of the results
+    pub explain_plan: Option<ExplainPlan>,
+    /// The server's error from the last run, shown instead of the results
+    pub query_error: Option<QueryError>,
+    /// Set while listening for notifications on the selected connection
+    pub listen: Option<ListenState>,
+    /// Channels to listen on once the password being asked for is entered
+    pub pending_listen: Option<Vec<String>>,
+    /// The selected connection's schema, shown in the schema browser
+    pub schema: Option<Arc<DatabaseSchema>>,
+    /// Set while the schema is being introspected
+    pub schema_load: Option<tokio::task::JoinHandle<Result<Arc<DatabaseSchema>, String>>>,
+    /// Set to load the schema once the password being asked for is entered; true to refresh it
+    pub pending_schema_load: Option<bool>,
+    /// Set while the newly selected connection is being checked, with its name
+    pub connection_check: Option<(String, tokio::task::JoinHandle<Result<(), String>>)>,
+}
+
+impl QueryState {
+    pub fn current_result(&self) -> Option<&QueryResult> {
+        self.query_results.get(self.selected_result)
+    }
+
+    pub fn set_results(&mut self, results: Vec<QueryResult>) {
+        self.query_results = results;
+        self.selected_result = 0;
+        self.query_cancelled = false;
+        self.explain_plan = None;
+        self.query_error = None;
+    }
+
+    pub fn is_query_running(&self) -> bool {
+        self.cancel_handle.is_some()
+    }
+
+    pub fn transaction_status(&self) -> Option<TransactionStatus> {
+        self.session.as_ref().map(Session::status)
+    }
+
+    pub fn has_open_transaction(&self) -> bool {
+        self.transaction_status().is_some_and(|status| status.is_open())
+    }
+
+    pub fn next_result(&mut self) -> bool {
+        if self.selected_result + 1 < self.query_results.len() {
+            self.selected_result += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn previous_result(&mut self) -> bool {
+        if self.selected_result > 0 {
+            self.selected_result -= 1;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -170,7 +376,7 @@ impl App<'_> {
         let collection_items = crate::collection::build_collection_tree(&collections, &fs);
         
         let mut navigation = NavigationManager::new();
-        navigation.register_pane(PaneId::Header, 2);
+        navigation.register_pane(PaneId::Header, 3);
         navigation.register_pane(PaneId::Collections, 1);
         navigation.register_pane(PaneId::Workspace, 1);
         navigation.register_pane(PaneId::Results, 1);
@@ -182,18 +388,39 @@ impl App<'_> {
             ui_state: UIState {
                 message: String::new(),
                 collection_state: TreeState::default(),
+                explain_state: TreeState::default(),
+                sidebar: SidebarTab::Collections,
+                schema_state: TreeState::default(),
                 collection_items,
                 workspace,
+                loaded_file: None,
                 search: SearchBox::default(),
             },
             
             query_state: QueryState {
                 selected_connection: None,
                 available_connections: Vec::new(),
+                connection_configs: HashMap::new(),
                 current_password: None,
-                query_result: QueryResult::default(),
+                query_results: Vec::new(),
+                selected_result: 0,
+                query_cancelled: false,
                 pending_command: AppCommand::None,
                 pending_async_operation: None,
+                cancel_handle: None,
+                cancel_backend: None,
+                backends: BackendRegistry::with_defaults(),
+                param_values: HashMap::new(),
+                session: None,
+                explain_mode: None,
+                explain_plan: None,
+                query_error: None,
+                listen: None,
+                pending_listen: None,
+                schema: None,
+                schema_load: None,
+                pending_schema_load: None,
+                connection_check: None,
             },
             
             navigation,
@@ -209,6 +436,12 @@ impl App<'_> {
         Ok(app)
     }
 
+    /// Replaces the backends queries run on, e.g. with a fake one in tests
+    pub fn with_backends(mut self, backends: BackendRegistry) -> Self {
+        self.query_state.backends = backends;
+        self
+    }
+
     pub fn is_header_active(&self) -> bool {
         self.navigation.is_active(PaneId::Header)
     }
@@ -247,6 +480,11 @@ impl App<'_> {
         let connections = config_manager.list_connections()?;
         
         if !connections.is_empty() {
+            for name in &connections {
+                if let Some(conn) = config_manager.get_connection(name)? {
+                    self.query_state.connection_configs.insert(name.clone(), conn);
+                }
+            }
             self.query_state.available_connections = connections;
             if self.query_state.selected_connection.is_none() {
                 self.query_state.selected_connection = Some(self.query_state.available_connections[0].clone());
@@ -273,7 +511,7 @@ impl App<'_> {
             .unwrap_or(0);
 
         let next_idx = (current_idx + 1) % self.query_state.available_connections.len();
-        self.query_state.selected_connection = Some(self.query_state.available_connections[next_idx].clone());
+        self.select_connection(next_idx);
     }
 
     pub fn previous_connection(&mut self) {
@@ -292,12 +530,90 @@ impl App<'_> {
             current_idx - 1
         };
         
-        self.query_state.selected_connection = Some(self.query_state.available_connections[prev_idx].clone());
+        self.select_connection(prev_idx);
+    }
+
+    fn select_connection(&mut self, idx: usize) {
+        if self.query_state.has_open_transaction() {
+            self.ui_state.message = "Commit or roll back the open transaction before switching connections".to_string();
+            return;
+        }
+        let next = self.query_state.available_connections[idx].clone();
+        let previous = self.query_state.selected_connection.replace(next.clone());
+        let Some(previous) = previous.filter(|previous| *previous != next) else {
+            return;
+        };
+        self.query_state.backends.close(&query::pool_key(None, Some(&previous)));
+        self.query_state.current_password = None;
+        // Notifications are only shown for the selected connection
+        if self.query_state.listen.take().is_some() {
+            self.ui_state.message = format!("Stopped listening on {}", previous);
+        }
+        self.clear_schema();
+        // Loading the schema connects too
+        if self.ui_state.sidebar == SidebarTab::Schema {
+            self.load_schema(false);
+        } else {
+            self.check_connection();
+        }
+    }
+
+    /// Connects to the selected connection in the background, so an unreachable database
+    /// shows up when switching to it. One needing a password isn't asked for it until it's used.
+    fn check_connection(&mut self) {
+        let Some(connection) = self.query_state.selected_connection.clone() else {
+            return;
+        };
+        if self.current_connection_config().is_none_or(Connection::requires_password) || tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
+        let backends = self.query_state.backends.clone();
+        let check = tokio::spawn({
+            let connection = connection.clone();
+            async move {
+                query::check_connection(None, Some(connection), None, &backends)
+                    .await
+                    .map_err(|e| format!("{:#}", e))
+            }
+        });
+        if let Some((_, previous)) = self.query_state.connection_check.replace((connection, check)) {
+            previous.abort();
+        }
+    }
+
+    /// Closes all pooled connections and the session, if any; called when the TUI exits.
+    pub fn shutdown(&mut self) {
+        let backends = self.query_state.backends.clone();
+        let session = self.query_state.session.take();
+        self.query_state.listen = None;
+        if tokio::runtime::Handle::try_current().is_ok() {
+            tokio::task::block_in_place(|| futures::executor::block_on(async {
+                if let Some(session) = session {
+                    session.close().await;
+                }
+                backends.close_all().await;
+            }));
+        }
     }
 
     pub fn get_current_connection(&self) -> Option<String> {
         self.query_state.selected_connection.clone()
     }
+
+    fn current_connection_config(&self) -> Option<&Connection> {
+        self.query_state.selected_connection
+            .as_ref()
+            .and_then(|name| self.query_state.connection_configs.get(name))
+    }
+
+    /// Whether the selected connection is marked as dangerous (e.g. production)
+    pub fn is_danger_connection(&self) -> bool {
+        self.current_connection_config().is_some_and(|conn| conn.danger)
+    }
+
+    pub fn is_read_only_connection(&self) -> bool {
+        self.current_connection_config().is_some_and(|conn| conn.read_only)
+    }
 }
 
 // Query execution and management
@@ -329,15 +645,83 @@ impl App<'_> {
     }
 
     fn execute_query_with_password(&mut self, password: Option<String>) {
+        if self.query_state.is_query_running() {
+            self.ui_state.message = "A query is already running".to_string();
+            return;
+        }
+
         let sql = self.ui_state.workspace.get_content();
+        let destructive: Vec<String> = destructive_statements(&sql).iter().map(|d| d.to_string()).collect();
+        // A plain EXPLAIN doesn't run the statement
+        if !destructive.is_empty() && self.query_state.explain_mode != Some(ExplainMode::Plan) {
+            self.show_confirm_destructive_modal(destructive);
+            return;
+        }
+        self.execute_confirmed_query(sql, password);
+    }
+
+    /// Runs a query the user has agreed to run, asking for parameter values first if it has any
+    fn execute_confirmed_query(&mut self, sql: String, password: Option<String>) {
+        let placeholders = placeholders(&sql);
+        if !placeholders.is_empty() {
+            self.show_params_modal(placeholders);
+            return;
+        }
+        self.run_query(sql, QueryParams::new(), password);
+    }
+
+    /// Runs `sql` in the background, adding the run to the query history once it's done
+    fn run_query(&mut self, sql: String, params: QueryParams, password: Option<String>) {
+        let explain_mode = self.query_state.explain_mode;
+        // The history keeps the query as written, not wrapped in EXPLAIN
+        let history_sql = sql.clone();
+        let sql = match explain_mode.map(|mode| explain_sql(&sql, mode)).transpose() {
+            Ok(explained) => explained.unwrap_or(sql),
+            Err(e) => {
+                self.ui_state.message = format!("Explain error: {}", e);
+                return;
+            }
+        };
         let connection = self.query_state.selected_connection.clone();
+        let backends = self.query_state.backends.clone();
+        let cancel = CancelHandle::new();
+        self.query_state.cancel_handle = Some(cancel.clone());
+        // If there's no backend for the connection, the query fails before it could be cancelled
+        self.query_state.cancel_backend = query::connection_backend(None, connection.clone(), &backends).ok();
+        let options = ExecutionOptions::new()
+            .with_params(params)
+            .with_cancel(cancel)
+            .with_session(self.query_state.session.clone());
+        let history = History::new(&self.fs);
+        self.ui_state.message = "Running query...".to_string();
 
         let handle = tokio::spawn(async move {
-            match execute_query(sql, None, connection, password).await {
-                Ok(_) => AsyncCommandResult::new(AppCommand::ExecuteQuery),
-                Err(e) => AsyncCommandResult::with_message(
+            let started_at = Local::now();
+            let started = Instant::now();
+            let results = execute_query(sql, None, connection.clone(), password, &backends, options).await;
+            // Failing to save it shouldn't hide how the query went, so it's ignored
+            if let Ok(history) = &history {
+                let _ = history.append(&HistoryEntry::new(history_sql, connection, started_at, started.elapsed(), &results));
+            }
+            match results {
+                Ok(query_results) if explain_mode.is_some() => match ExplainPlan::from_results(&query_results) {
+                    Ok(plan) => AsyncCommandResult::with_explain_plan(AppCommand::ExecuteQuery, plan),
+                    Err(e) => AsyncCommandResult::with_message(AppCommand::ExecuteQuery, format!("Explain error: {:#}", e)),
+                },
+                Ok(query_results) => AsyncCommandResult::with_query_results(AppCommand::ExecuteQuery, query_results),
+                Err(e) if e.is::<QueryCancelled>() => AsyncCommandResult::with_message(
                     AppCommand::ExecuteQuery,
-                    format!("Query error: {}", e)
+                    e.to_string()
+                ),
+                // The timeout says more than the server's "canceling statement" error beneath it
+                Err(e) if e.is::<QueryTimedOut>() => AsyncCommandResult::with_message(
+                    AppCommand::ExecuteQuery,
+                    e.to_string()
+                ),
+                Err(e) => AsyncCommandResult::with_query_error(
+                    AppCommand::ExecuteQuery,
+                    format!("Query error: {:#}", e),
+                    e.downcast_ref::<QueryError>().cloned()
                 ),
             }
         });
@@ -345,6 +729,271 @@ impl App<'_> {
         self.query_state.pending_async_operation = Some(handle);
     }
 
+    pub fn cancel_query(&mut self) {
+        let Some(cancel) = self.query_state.cancel_handle.clone() else {
+            self.ui_state.message = "No query is running".to_string();
+            return;
+        };
+
+        let Some(backend) = self.query_state.cancel_backend.clone() else {
+            return;
+        };
+
+        self.ui_state.message = "Cancelling query...".to_string();
+        tokio::spawn(async move {
+            // If the cancel request fails the query simply runs to completion
+            let _ = backend.cancel(&cancel).await;
+        });
+    }
+
+    /// Starts or ends session mode. A session can't end while its transaction is open.
+    pub fn toggle_session(&mut self) {
+        match self.query_state.session.take() {
+            Some(session) if session.status().is_open() => {
+                self.query_state.session = Some(session);
+                self.ui_state.message = "Commit or roll back the open transaction before ending the session".to_string();
+            }
+            Some(session) => {
+                tokio::spawn(async move { session.close().await });
+                self.ui_state.message = "Session ended".to_string();
+            }
+            None => {
+                self.query_state.session = Some(Session::new());
+                self.ui_state.message = "Session started: transactions now span query runs".to_string();
+            }
+        }
+    }
+
+    /// Listens on `channels` on the selected connection, replacing any channels listened on so far.
+    /// Asks for the connection's password first if it needs one.
+    fn start_listening(&mut self, channels: Vec<String>) {
+        let Some(connection) = self.query_state.selected_connection.clone() else {
+            self.ui_state.message = "Select a connection to listen on".to_string();
+            return;
+        };
+        let config = match query::get_connection(&connection) {
+            Ok(Some(config)) => config,
+            Ok(None) => {
+                self.ui_state.message = format!("Connection '{}' not found", connection);
+                return;
+            }
+            Err(e) => {
+                self.ui_state.message = format!("Error checking connection: {}", e);
+                return;
+            }
+        };
+        if config.requires_password() && self.query_state.current_password.is_none() {
+            self.show_password_prompt();
+            self.query_state.pending_listen = Some(channels);
+            return;
+        }
+        let connect_timeout = match config.timeouts() {
+            Ok(timeouts) => timeouts.connect_or_default(),
+            Err(e) => {
+                self.ui_state.message = format!("Listen error: {}", e);
+                return;
+            }
+        };
+        let url = config.to_url(self.query_state.current_password.clone());
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let listened = channels.clone();
+        let task = tokio::spawn(async move {
+            let mut listener = match Listener::connect(&url, listened, connect_timeout).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    let _ = sender.send(Err(format!("{:#}", e)));
+                    return;
+                }
+            };
+            loop {
+                let event = listener.next().await;
+                if sender.send(Ok(event)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        self.ui_state.message = format!("Listening on {}", channels.join(", "));
+        let mut listen = ListenState::new(connection, channels);
+        listen.events = Some(receiver);
+        listen.task = Some(task);
+        self.query_state.listen = Some(listen);
+    }
+
+    pub fn stop_listening(&mut self) {
+        if let Some(listen) = self.query_state.listen.take() {
+            self.ui_state.message = format!("Stopped listening on {}", listen.channels.join(", "));
+        }
+    }
+
+    /// Commits or rolls back the session's transaction and quits
+    fn end_transaction_and_quit(&mut self, commit: bool) {
+        let Some(session) = self.query_state.session.clone() else {
+            self.should_quit = true;
+            return;
+        };
+
+        let ended = tokio::task::block_in_place(|| futures::executor::block_on(async {
+            if commit {
+                session.commit().await
+            } else {
+                session.rollback().await
+            }
+        }));
+        match ended {
+            // Committing a failed transaction rolls it back, leaving it closed either way
+            Ok(()) => self.should_quit = true,
+            Err(e) => self.ui_state.message = format!("Error ending transaction: {}", e),
+        }
+    }
+
+    /// Shows a plan in the results pane with every node expanded
+    pub fn set_explain_plan(&mut self, plan: ExplainPlan) {
+        fn open_all(state: &mut TreeState<usize>, node: &PlanNode, path: &mut Vec<usize>) {
+            state.open(path.clone());
+            for (i, child) in node.children.iter().enumerate() {
+                path.push(i);
+                open_all(state, child, path);
+                path.pop();
+            }
+        }
+
+        self.query_state.set_results(Vec::new());
+        self.ui_state.explain_state = TreeState::default();
+        open_all(&mut self.ui_state.explain_state, &plan.root, &mut vec![0]);
+        self.ui_state.explain_state.select(vec![0]);
+        self.query_state.explain_plan = Some(plan);
+    }
+
+    /// Shows the server's error in the results pane, and points the editor at the
+    /// position it reported, if any
+    pub fn set_query_error(&mut self, error: Option<QueryError>) {
+        if let Some(position) = error.as_ref().and_then(|e| e.position.as_ref()) {
+            self.ui_state.workspace.mark_error(position.line, position.column);
+        }
+        self.query_state.query_error = error;
+    }
+
+    /// Switches the left-hand pane between collections and the schema browser,
+    /// loading the schema the first time it is shown
+    pub fn toggle_sidebar(&mut self) {
+        self.ui_state.sidebar = match self.ui_state.sidebar {
+            SidebarTab::Collections => SidebarTab::Schema,
+            SidebarTab::Schema => SidebarTab::Collections,
+        };
+        if self.ui_state.sidebar == SidebarTab::Schema && self.query_state.schema.is_none() && self.query_state.schema_load.is_none() {
+            self.load_schema(false);
+        }
+    }
+
+    /// Introspects the selected connection's schema in the background; `refresh` skips the cached one.
+    /// Asks for the connection's password first if it needs one.
+    pub fn load_schema(&mut self, refresh: bool) {
+        let Some(connection) = self.query_state.selected_connection.clone() else {
+            self.ui_state.message = "Select a connection to browse its schema".to_string();
+            return;
+        };
+        match query::get_connection(&connection) {
+            Ok(Some(config)) if config.requires_password() && self.query_state.current_password.is_none() => {
+                self.show_password_prompt();
+                self.query_state.pending_schema_load = Some(refresh);
+                return;
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                self.ui_state.message = format!("Connection '{}' not found", connection);
+                return;
+            }
+            Err(e) => {
+                self.ui_state.message = format!("Error checking connection: {}", e);
+                return;
+            }
+        }
+
+        let password = self.query_state.current_password.clone();
+        let backends = self.query_state.backends.clone();
+        self.ui_state.message = "Loading schema...".to_string();
+        self.query_state.schema_load = Some(tokio::spawn(async move {
+            query::load_schema(None, Some(connection), password, &backends, refresh)
+                .await
+                .map_err(|e| format!("{:#}", e))
+        }));
+    }
+
+    /// Offers completions for the word at the workspace's cursor. Table and column names come
+    /// from the selected connection's schema, which starts loading here if it hasn't yet.
+    pub fn complete_at_cursor(&mut self) {
+        let needs_password = self.current_connection_config().is_some_and(|c| c.requires_password())
+            && self.query_state.current_password.is_none();
+        // Not worth interrupting typing to ask for a password
+        let can_load = self.query_state.selected_connection.is_some() && !needs_password;
+        if self.query_state.schema.is_none() && self.query_state.schema_load.is_none() && can_load {
+            self.load_schema(false);
+        }
+        let schema = self.query_state.schema.clone();
+        self.ui_state.workspace.open_completions(schema.as_deref());
+    }
+
+    /// Shows `schema` in the schema browser, keeping the open nodes that still exist
+    pub fn set_schema(&mut self, schema: Arc<DatabaseSchema>) {
+        if self.ui_state.schema_state.selected().is_empty() {
+            if let Some(first) = schema.schemas.first() {
+                self.ui_state.schema_state.select(vec![first.name.clone()]);
+            }
+        }
+        self.query_state.schema = Some(schema);
+    }
+
+    /// Forgets the schema of the previously selected connection
+    fn clear_schema(&mut self) {
+        if let Some(load) = self.query_state.schema_load.take() {
+            load.abort();
+        }
+        self.query_state.schema = None;
+        self.ui_state.schema_state = TreeState::default();
+    }
+
+    /// The table or view the schema browser's selection is on or under
+    pub fn selected_schema_table(&self) -> Option<&Table> {
+        let schema = self.query_state.schema.as_ref()?;
+        let [schema_name, table_name, ..] = self.ui_state.schema_state.selected() else {
+            return None;
+        };
+        schema.tables().find(|t| &t.schema == schema_name && &t.name == table_name)
+    }
+
+    /// Inserts a query for the first rows of the selected table at the workspace's cursor
+    pub fn insert_select_for_selected_table(&mut self) {
+        let Some(table) = self.selected_schema_table() else {
+            self.ui_state.message = "Select a table to query".to_string();
+            return;
+        };
+        let sql = format!("SELECT * FROM {} LIMIT 100;", table.qualified_name());
+        self.ui_state.message = format!("Inserted a query for {}", table.qualified_name());
+        self.ui_state.workspace.insert_str(sql);
+    }
+
+    pub fn show_selected_table_ddl(&mut self) {
+        let Some(table) = self.selected_schema_table() else {
+            self.ui_state.message = "Select a table to show its DDL".to_string();
+            return;
+        };
+        let ddl = table.ddl();
+        self.modal_manager.show_modal(ModalType::TableDdl { ddl });
+        self.mode = Mode::TableDdl;
+    }
+
+    /// Formats the workspace's selected SQL, or all of it if nothing is selected
+    pub fn format_workspace(&mut self) {
+        let selected = self.ui_state.workspace.is_selecting();
+        self.ui_state.message = match (self.ui_state.workspace.format(), selected) {
+            (true, true) => "Formatted the selection".to_string(),
+            (true, false) => "Formatted the query".to_string(),
+            (false, _) => "Already formatted".to_string(),
+        };
+    }
+
     pub fn save_query(&mut self) {
         let content = self.ui_state.workspace.get_content();
         if content.is_empty() {
@@ -383,8 +1032,19 @@ impl App<'_> {
         if self.query_state.pending_command != AppCommand::None {
             match self.query_state.pending_command {
                 AppCommand::ExecuteQuery => {
+                    self.query_state.explain_mode = None;
+                    self.check_and_execute_query();
+                },
+                AppCommand::ExplainQuery(mode) => {
+                    self.query_state.explain_mode = Some(mode);
                     self.check_and_execute_query();
                 },
+                AppCommand::CancelQuery => {
+                    self.cancel_query();
+                },
+                AppCommand::ToggleSession => {
+                    self.toggle_session();
+                },
                 AppCommand::SaveQuery => {
                     self.save_query();
                 },
@@ -407,6 +1067,12 @@ impl App<'_> {
     pub fn handle_key(&mut self, ui: &mut UI, key_event: KeyEvent) -> Result<bool> {
         match (key_event.code, key_event.modifiers) {
             (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                // Ask once what to do with an open transaction; a second ^C quits regardless
+                if self.query_state.has_open_transaction() && self.mode != Mode::EndTransaction {
+                    self.modal_manager.show_modal(ModalType::EndTransaction);
+                    self.mode = Mode::EndTransaction;
+                    return Ok(false);
+                }
                 self.should_quit = true;
                 Ok(true)
             }
@@ -418,10 +1084,27 @@ impl App<'_> {
                 _ = self.navigation.cycle_pane(false);
                 Ok(false)
             }
+            (KeyCode::Char('g'), KeyModifiers::CONTROL) if !self.modal_manager.is_modal_active() => {
+                self.query_state.pending_command = AppCommand::CancelQuery;
+                Ok(false)
+            }
+            (KeyCode::Char('t'), KeyModifiers::CONTROL) if !self.modal_manager.is_modal_active() => {
+                self.query_state.pending_command = AppCommand::ToggleSession;
+                Ok(false)
+            }
             (KeyCode::Char('n'), KeyModifiers::CONTROL) if !self.modal_manager.is_modal_active() => {
                 self.show_new_file_modal();
                 Ok(false)
             }
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) if !self.modal_manager.is_modal_active() => {
+                self.show_listen_modal();
+                Ok(false)
+            }
+            // As in shells, for searching back through what has been run
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) if !self.modal_manager.is_modal_active() => {
+                self.show_history_modal();
+                Ok(false)
+            }
             (KeyCode::Char('e'), KeyModifiers::CONTROL) if !self.modal_manager.is_modal_active() => {
                 if let Some(selected_file) = self.get_selected_file_info() {
                     if selected_file.name.starts_with(CONFIG_FILE_NAME) {
@@ -434,7 +1117,7 @@ impl App<'_> {
             _ => {
                 if self.modal_manager.is_modal_active() {
                     self.handle_modal_key_event(key_event)?;
-                    return Ok(false);
+                    return Ok(self.should_quit);
                 }
                 
                 ui.handle_key_event(self, key_event)
@@ -449,7 +1132,7 @@ impl App<'_> {
                 .unwrap_or(Rect::new(0, 0, 80, 24));
 
             self.handle_modal_mouse_event(mouse_event, terminal_area)?;
-            return Ok(false);
+            return Ok(self.should_quit);
         }
         ui.handle_mouse_event(self, mouse_event)
     }
@@ -479,6 +1162,28 @@ impl App<'_> {
                     "cancel" => {
                         self.close_modal();
                     }
+                    "commit" | "rollback" => {
+                        self.close_modal();
+                        self.end_transaction_and_quit(action == "commit");
+                    }
+                    "run_anyway" => {
+                        self.close_modal();
+                        self.execute_confirmed_query(self.ui_state.workspace.get_content(), self.query_state.current_password.clone());
+                    }
+                    "listen" => {
+                        self.dispatch_listen();
+                    }
+                    "stop_listening" => {
+                        self.close_modal();
+                        self.stop_listening();
+                    }
+                    "insert_ddl" => {
+                        self.close_modal();
+                        self.insert_selected_table_ddl();
+                    }
+                    "load_history" => {
+                        self.dispatch_load_history();
+                    }
                     _ => {}
                 }
             }
@@ -510,6 +1215,28 @@ impl App<'_> {
                         "cancel" => {
                             self.close_modal();
                         }
+                        "commit" | "rollback" => {
+                            self.close_modal();
+                            self.end_transaction_and_quit(action == "commit");
+                        }
+                        "run_anyway" => {
+                            self.close_modal();
+                            self.execute_confirmed_query(self.ui_state.workspace.get_content(), self.query_state.current_password.clone());
+                        }
+                        "listen" => {
+                            self.dispatch_listen();
+                        }
+                        "stop_listening" => {
+                            self.close_modal();
+                            self.stop_listening();
+                        }
+                        "insert_ddl" => {
+                            self.close_modal();
+                            self.insert_selected_table_ddl();
+                        }
+                        "load_history" => {
+                            self.dispatch_load_history();
+                        }
                         _ => {}
                     }
                 }
@@ -522,14 +1249,75 @@ impl App<'_> {
     fn close_modal(&mut self) {
         self.modal_manager.close_modal();
         self.mode = Mode::Normal;
+        self.query_state.pending_listen = None;
+        self.query_state.pending_schema_load = None;
     }
 
     fn handle_submit(&mut self) {
         if let Some(modal) = self.modal_manager.get_active_modal_as::<PasswordModal>() {
             self.query_state.current_password = modal.get_password();
-            self.execute_query_with_password(self.query_state.current_password.clone());
+            let pending_listen = self.query_state.pending_listen.take();
+            let pending_schema_load = self.query_state.pending_schema_load.take();
+            // Closed first, since running the query may open the parameters modal
+            self.close_modal();
+            match (pending_listen, pending_schema_load) {
+                (Some(channels), _) => self.start_listening(channels),
+                (None, Some(refresh)) => self.load_schema(refresh),
+                (None, None) => self.execute_query_with_password(self.query_state.current_password.clone()),
+            }
+        } else if let Some(modal) = self.modal_manager.get_active_modal_as::<ParamsModal>() {
+            let params = modal.get_params();
+            self.close_modal();
+            self.query_state.param_values.insert(self.current_file_key(), params.clone());
+            let sql = self.ui_state.workspace.get_content();
+            self.run_query(sql, params, self.query_state.current_password.clone());
+        } else {
+            self.close_modal();
         }
+    }
+
+    /// Identifies the file open in the workspace, for remembering per-file state
+    fn current_file_key(&self) -> String {
+        self.ui_state.loaded_file.clone().unwrap_or_default()
+    }
+
+    fn insert_selected_table_ddl(&mut self) {
+        if let Some(ddl) = self.selected_schema_table().map(Table::ddl) {
+            self.ui_state.workspace.insert_str(ddl);
+        }
+    }
+
+    fn dispatch_listen(&mut self) {
+        if let Some(modal) = self.modal_manager.get_active_modal_as::<ListenModal>() {
+            let channels = modal.get_channels();
+            if channels.is_empty() {
+                self.ui_state.message = "Enter at least one channel to listen on".to_string();
+                return;
+            }
+            self.close_modal();
+            self.start_listening(channels);
+        }
+    }
+
+    /// Inserts the query picked from the history at the cursor, on a line of its own,
+    /// so that nothing in the workspace is lost
+    fn dispatch_load_history(&mut self) {
+        let sql = self.modal_manager
+            .get_active_modal_as::<HistoryModal>()
+            .and_then(|modal| modal.selected_sql())
+            .map(str::to_string);
+        let Some(sql) = sql else {
+            return;
+        };
         self.close_modal();
+        let workspace = &mut self.ui_state.workspace;
+        let (row, _) = workspace.cursor();
+        if !workspace.lines()[row].trim().is_empty() {
+            workspace.move_cursor(CursorMove::End);
+            workspace.insert_newline();
+        }
+        workspace.insert_str(sql);
+        self.ui_state.message = "Inserted a query from the history".to_string();
     }
 
     fn dispatch_new(&mut self) {
@@ -657,12 +1445,11 @@ impl App<'_> {
                 return;
             }
             
-            let file_path = if !selected_file.is_folder && selected_file.collection_name.is_some() {
-                format!("{}/{}", 
-                    selected_file.collection_name.as_ref().unwrap(),
-                    selected_file.name)
-            } else {
-                selected_file.name.clone()
+            let file_path = match &selected_file.collection_name {
+                Some(collection) if !selected_file.is_folder => {
+                    format!("{}/{}", collection, selected_file.name)
+                },
+                _ => selected_file.name.clone(),
             };
             
             self.file_operation_state = Some(FileOperationState::Delete {
@@ -695,6 +1482,41 @@ impl App<'_> {
         self.mode = Mode::Password;
     }
 
+    fn show_params_modal(&mut self, placeholders: Vec<Placeholder>) {
+        let values = self.query_state.param_values
+            .get(&self.current_file_key())
+            .cloned()
+            .unwrap_or_default();
+        self.modal_manager.show_modal(ModalType::Params { placeholders, values });
+        self.mode = Mode::Params;
+    }
+
+    fn show_listen_modal(&mut self) {
+        let (channels, listening) = match &self.query_state.listen {
+            Some(listen) => (listen.channels.clone(), true),
+            None => (Vec::new(), false),
+        };
+        self.modal_manager.show_modal(ModalType::Listen { channels, listening });
+        self.mode = Mode::Listen;
+    }
+
+    fn show_history_modal(&mut self) {
+        match History::new(&self.fs).and_then(|history| history.load()) {
+            Ok(entries) => {
+                self.modal_manager.show_modal(ModalType::History { entries });
+                self.mode = Mode::History;
+            }
+            Err(e) => {
+                self.ui_state.message = format!("Error loading the query history: {}", e);
+            }
+        }
+    }
+
+    fn show_confirm_destructive_modal(&mut self, statements: Vec<String>) {
+        self.modal_manager.show_modal(ModalType::ConfirmDestructive { statements });
+        self.mode = Mode::ConfirmDestructive;
+    }
+
     fn show_new_file_modal(&mut self) {
         let folder_context = get_selected_folder_context(self.ui_state.collection_state.selected());
         let parent_folder = folder_context.map(|(folder, _)| folder);
@@ -754,39 +1576,53 @@ impl App<'_> {
 // Async operation handling
 impl App<'_> {
     pub fn process_async_results(&mut self) {
+        self.process_listen_events();
+        self.process_schema_load();
+        self.process_connection_check();
+
         if let Some(handle) = &mut self.query_state.pending_async_operation {
             if handle.is_finished() {
                 let handle = std::mem::take(&mut self.query_state.pending_async_operation).unwrap();
+                let cancel = self.query_state.cancel_handle.take();
+                self.query_state.cancel_backend = None;
                 
                 match tokio::task::block_in_place(|| futures::executor::block_on(handle)) {
                     Ok(result) => {
                         match result.command {
+                            AppCommand::ExecuteQuery if result.explain_plan.is_some() => {
+                                if let Some(plan) = result.explain_plan {
+                                    self.ui_state.message = match plan.execution_time {
+                                        Some(ms) => format!("Query plan ready; execution took {:.3}ms", ms),
+                                        None => "Query plan ready".to_string(),
+                                    };
+                                    self.set_explain_plan(plan);
+                                }
+                            },
                             AppCommand::ExecuteQuery => {
-                                let sql = self.ui_state.workspace.get_content();
-                                let connection = self.query_state.selected_connection.clone();
-                                match tokio::task::block_in_place(|| {
-                                    futures::executor::block_on(async {
-                                        execute_query(
-                                            sql,
-                                            None,
-                                            connection,
-                                            self.query_state.current_password.clone(),
-                                        ).await
-                                    })
-                                }) {
-                                    Ok(query_result) => {
-                                        self.query_state.query_result = query_result;
-                                        self.ui_state.message = format!(
-                                            "Query executed successfully in {}ms",
-                                            self.query_state.query_result.execution_time.as_millis()
-                                        );
+                                match result.query_results {
+                                    Some(query_results) => {
+                                        let total_ms: u128 = query_results.iter()
+                                            .map(|r| r.query_time().as_millis())
+                                            .sum();
+                                        self.ui_state.message = if query_results.len() > 1 {
+                                            format!("{} statements executed successfully in {}ms", query_results.len(), total_ms)
+                                        } else {
+                                            format!("Query executed successfully in {}ms", total_ms)
+                                        };
+                                        self.query_state.set_results(query_results);
+                                    }
+                                    None if cancel.as_ref().is_some_and(CancelHandle::is_cancelled) => {
+                                        self.query_state.set_results(Vec::new());
+                                        self.query_state.query_cancelled = true;
                                     }
-                                    Err(e) => {
-                                        if e.to_string().contains("password authentication failed") {
+                                    None => {
+                                        let error = result.message.clone().unwrap_or_default();
+                                        if error.contains("password authentication failed") {
+                                            self.query_state.current_password = None;
                                             self.show_password_prompt();
                                         } else {
-                                            self.ui_state.message = format!("Error executing query: {}", e);
-                                            self.query_state.query_result = QueryResult::empty();
+                                            self.query_state.set_results(Vec::new());
+                                            self.set_query_error(result.query_error.clone());
                                         }
                                     }
                                 }
@@ -808,4 +1644,71 @@ impl App<'_> {
             }
         }
     }
+
+    /// Shows the schema once it has been introspected
+    fn process_connection_check(&mut self) {
+        if !self.query_state.connection_check.as_ref().is_some_and(|(_, check)| check.is_finished()) {
+            return;
+        }
+        let (connection, check) = self.query_state.connection_check.take().unwrap();
+        match tokio::task::block_in_place(|| futures::executor::block_on(check)) {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => self.ui_state.message = format!("Could not connect to {}: {}", connection, error),
+            Err(e) => self.ui_state.message = format!("Error in async operation: {}", e),
+        }
+    }
+
+    fn process_schema_load(&mut self) {
+        if !self.query_state.schema_load.as_ref().is_some_and(|load| load.is_finished()) {
+            return;
+        }
+        let load = self.query_state.schema_load.take().unwrap();
+        match tokio::task::block_in_place(|| futures::executor::block_on(load)) {
+            Ok(Ok(schema)) => {
+                self.ui_state.message = format!("Loaded the schema: {} tables and views", schema.tables().count());
+                self.set_schema(schema);
+            }
+            Ok(Err(error)) => {
+                if error.contains("password authentication failed") {
+                    self.query_state.current_password = None;
+                }
+                self.ui_state.message = format!("Schema error: {}", error);
+            }
+            Err(e) => self.ui_state.message = format!("Error in async operation: {}", e),
+        }
+    }
+
+    /// Takes in the notifications received since the last tick
+    fn process_listen_events(&mut self) {
+        let Some(listen) = &mut self.query_state.listen else {
+            return;
+        };
+        let mut received = Vec::new();
+        if let Some(events) = &mut listen.events {
+            while let Ok(event) = events.try_recv() {
+                received.push(event);
+            }
+        }
+        for event in received {
+            match event {
+                Ok(ListenEvent::Notification(notification)) => listen.push(notification),
+                Ok(ListenEvent::Disconnected(reason)) => {
+                    self.ui_state.message = format!("Listen connection lost ({}); reconnecting...", reason);
+                    listen.disconnected = Some(reason);
+                }
+                Ok(ListenEvent::Reconnected) => {
+                    self.ui_state.message = format!("Reconnected; listening on {}", listen.channels.join(", "));
+                    listen.disconnected = None;
+                }
+                Err(error) => {
+                    if error.contains("password authentication failed") {
+                        self.query_state.current_password = None;
+                    }
+                    self.ui_state.message = format!("Listen error: {}", error);
+                    self.query_state.listen = None;
+                    return;
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
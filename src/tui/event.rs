@@ -38,20 +38,14 @@ impl EventHandler {
                 match event::poll(timeout) {
                     Ok(true) => {
                         match event::read() {
-                            Ok(event::Event::Key(key)) => {
-                                if event_sender.send(Event::Key(key)).is_err() {
-                                    break;
-                                }
+                            Ok(event::Event::Key(key)) if event_sender.send(Event::Key(key)).is_err() => {
+                                break;
                             }
-                            Ok(event::Event::Mouse(mouse)) => {
-                                if event_sender.send(Event::Mouse(mouse)).is_err() {
-                                    break;
-                                }
+                            Ok(event::Event::Mouse(mouse)) if event_sender.send(Event::Mouse(mouse)).is_err() => {
+                                break;
                             }
-                            Ok(event::Event::Resize(w, h)) => {
-                                if event_sender.send(Event::Resize(w, h)).is_err() {
-                                    break;
-                                }
+                            Ok(event::Event::Resize(w, h)) if event_sender.send(Event::Resize(w, h)).is_err() => {
+                                break;
                             }
                             _ => {}
                         }
@@ -3,9 +3,9 @@ use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::{prelude::*, Frame};
 use std::any::Any;
 
-use crate::collection::CollectionScope;
+use crate::{collection::CollectionScope, history::HistoryEntry, sql::params::{Placeholder, QueryParams}};
 
-use super::widgets::{edit_file_modal::EditFileModal, modal::{ModalAction, ModalHandler}, new_file_modal::NewFileModal, password_modal::PasswordModal};
+use super::widgets::{button::{GREEN, LIGHT_GREY, RED}, confirm_modal::{ConfirmChoice, ConfirmModal}, edit_file_modal::EditFileModal, history_modal::HistoryModal, listen_modal::ListenModal, modal::{ModalAction, ModalHandler}, new_file_modal::NewFileModal, params_modal::ParamsModal, password_modal::PasswordModal};
 
 pub enum ModalType {
     Password,
@@ -17,6 +17,29 @@ pub enum ModalType {
         is_folder: bool,
         current_scope: CollectionScope,
     },
+    Params {
+        placeholders: Vec<Placeholder>,
+        values: QueryParams,
+    },
+    /// Asks what to do with the session's open transaction before quitting
+    EndTransaction,
+    /// Asks before running DELETE/UPDATE without WHERE, DROP or TRUNCATE
+    ConfirmDestructive {
+        statements: Vec<String>,
+    },
+    /// Asks which channels to listen on; `listening` offers to stop listening
+    Listen {
+        channels: Vec<String>,
+        listening: bool,
+    },
+    /// Shows a table's DDL, offering to insert it into the workspace
+    TableDdl {
+        ddl: String,
+    },
+    /// Searches the queries run so far, to load one into the workspace
+    History {
+        entries: Vec<HistoryEntry>,
+    },
 }
 
 pub struct ModalManager {
@@ -52,6 +75,46 @@ impl ModalManager {
             ModalType::EditFile { name, is_folder, current_scope } => {
                 Box::new(EditFileModal::new(&name, is_folder, current_scope))
             }
+            ModalType::Params { placeholders, values } => {
+                Box::new(ParamsModal::new(placeholders, &values))
+            }
+            ModalType::EndTransaction => {
+                Box::new(ConfirmModal::new(
+                    "Open Transaction",
+                    "The session has an open transaction. Commit or roll it back before quitting?",
+                    vec![
+                        ConfirmChoice::new("Cancel", "cancel", LIGHT_GREY),
+                        ConfirmChoice::new("Rollback", "rollback", RED),
+                        ConfirmChoice::new("Commit", "commit", GREEN),
+                    ],
+                ))
+            }
+            ModalType::ConfirmDestructive { statements } => {
+                Box::new(ConfirmModal::new(
+                    "Destructive Query",
+                    format!("This query will run:\n{}\n\nRun it anyway?", statements.join("\n")),
+                    vec![
+                        ConfirmChoice::new("Cancel", "cancel", LIGHT_GREY),
+                        ConfirmChoice::new("Run Anyway", "run_anyway", RED),
+                    ],
+                ))
+            }
+            ModalType::Listen { channels, listening } => {
+                Box::new(ListenModal::new(&channels, listening))
+            }
+            ModalType::TableDdl { ddl } => {
+                Box::new(ConfirmModal::new(
+                    "Table DDL",
+                    ddl,
+                    vec![
+                        ConfirmChoice::new("Close", "cancel", LIGHT_GREY),
+                        ConfirmChoice::new("Insert", "insert_ddl", GREEN),
+                    ],
+                ).with_dimensions(70, 70).preformatted())
+            }
+            ModalType::History { entries } => {
+                Box::new(HistoryModal::new(entries))
+            }
         };
         self.active_modal = Some(modal);
     }
@@ -4,18 +4,20 @@ use ratatui::{
     prelude::*,
     style::{Color, Style},
     text::Line,
-    widgets::{Scrollbar, ScrollbarOrientation},
+    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, Wrap},
     Frame,
 };
+use tui_tree_widget::TreeItem;
 
 use crate::{
     collection::SelectedFile,
     config::CONFIG_FILE_NAME,
     file::{self, FileSystem},
+    sql::schema::{DatabaseSchema, Table, TableKind},
     tui::{
         widgets::file_tree::FileTree,
         navigation::PaneId,
-        app::App,
+        app::{App, SidebarTab},
     },
 };
 
@@ -66,6 +68,7 @@ impl CollectionsPane {
             Ok(content) => {
                 app.ui_state.workspace.clear();
                 app.ui_state.workspace.insert_str(&content);
+                app.ui_state.loaded_file = Some(selected.join("/"));
             },
             Err(err) => {
                 app.ui_state.message = format!("Error loading file: {}", err);
@@ -81,12 +84,19 @@ impl Pane for CollectionsPane {
         PaneId::Collections
     }
 
-    fn title(&self) -> &'static str {
-        "Collections"
+    fn title(&self, app: &App) -> &'static str {
+        match app.ui_state.sidebar {
+            SidebarTab::Collections => "Collections",
+            SidebarTab::Schema => "Schema",
+        }
     }
 
     fn render_content(&mut self, app: &mut App, frame: &mut Frame, area: Rect) {
         self.last_area = Some(area);
+        if app.ui_state.sidebar == SidebarTab::Schema {
+            render_schema(app, frame, area);
+            return;
+        }
 
         let tree = FileTree::new(&app.ui_state.collection_items)
             .expect("all item identifiers are unique")
@@ -106,7 +116,38 @@ impl Pane for CollectionsPane {
         frame.render_stateful_widget(tree, area, &mut app.ui_state.collection_state);
     }
 
-    fn get_custom_instructions(&self, _app: &App, is_editing: bool) -> Line<'static> {
+    fn get_custom_instructions(&self, app: &App, is_editing: bool) -> Line<'static> {
+        if app.ui_state.sidebar == SidebarTab::Schema {
+            return if is_editing {
+                Line::from(vec![
+                    " Esc ".blue().bold(),
+                    "Return ".white(),
+                    " ↑/↓ ".blue().bold(),
+                    "Navigate ".white(),
+                    " Space ".blue().bold(),
+                    "Expand ".white(),
+                    " i ".blue().bold(),
+                    "Insert SELECT ".white(),
+                    " d ".blue().bold(),
+                    "DDL ".white(),
+                    " r ".blue().bold(),
+                    "Refresh ".white(),
+                    " s ".blue().bold(),
+                    "Collections ".white(),
+                ])
+            } else {
+                Line::from(vec![
+                    " Tab ".blue().bold(),
+                    "Switch Panel ".white(),
+                    " Space ".blue().bold(),
+                    "Select ".white(),
+                    " s ".blue().bold(),
+                    "Collections ".white(),
+                    " ^C ".blue().bold(),
+                    "Quit ".white(),
+                ])
+            };
+        }
         if is_editing {
             Line::from(vec![
                 " Esc ".blue().bold(),
@@ -119,6 +160,8 @@ impl Pane for CollectionsPane {
                 "New ".white(),
                 " ^E ".blue().bold(),
                 "Edit ".white(),
+                " s ".blue().bold(),
+                "Schema ".white(),
                 " ^C ".blue().bold(),
                 "Quit ".white(),
             ])
@@ -128,6 +171,8 @@ impl Pane for CollectionsPane {
                 "Switch Panel ".white(),
                 " Space ".blue().bold(),
                 "Select ".white(),
+                " s ".blue().bold(),
+                "Schema ".white(),
                 " ^C ".blue().bold(),
                 "Quit ".white(),
             ])
@@ -135,6 +180,23 @@ impl Pane for CollectionsPane {
     }
 
     fn handle_edit_mode_key(&mut self, app: &mut App, key: KeyEvent) -> Result<bool> {
+        if app.ui_state.sidebar == SidebarTab::Schema {
+            let state = &mut app.ui_state.schema_state;
+            match key.code {
+                KeyCode::Esc => return self.deactivate(app),
+                KeyCode::Up => { state.key_up(); },
+                KeyCode::Down => { state.key_down(); },
+                KeyCode::Left => { state.key_left(); },
+                KeyCode::Right => { state.key_right(); },
+                KeyCode::Enter | KeyCode::Char(' ') => { state.toggle_selected(); },
+                KeyCode::Char('i') => app.insert_select_for_selected_table(),
+                KeyCode::Char('d') => app.show_selected_table_ddl(),
+                KeyCode::Char('r') => app.load_schema(true),
+                KeyCode::Char('s') => app.toggle_sidebar(),
+                _ => {}
+            }
+            return Ok(false);
+        }
         match key.code {
             KeyCode::Esc => {
                 self.deactivate(app)
@@ -160,6 +222,10 @@ impl Pane for CollectionsPane {
                 app.ui_state.collection_state.key_up();
                 Ok(false)
             },
+            KeyCode::Char('s') => {
+                app.toggle_sidebar();
+                Ok(false)
+            },
             _ => Ok(false)
         }
     }
@@ -177,6 +243,10 @@ impl Pane for CollectionsPane {
                 app.navigation.activate_pane(PaneId::Workspace)?;
                 Ok(false)
             },
+            KeyCode::Char('s') => {
+                app.toggle_sidebar();
+                Ok(false)
+            },
             _ => Ok(false)
         }
     }
@@ -184,6 +254,18 @@ impl Pane for CollectionsPane {
     fn handle_custom_mouse_event(&mut self, app: &mut App, mouse_event: MouseEvent) -> Result<bool> {
         if let MouseEventKind::Down(MouseButton::Left) = mouse_event.kind {
             if let Some(area) = self.last_area {
+                if app.ui_state.sidebar == SidebarTab::Schema {
+                    let Some(schema) = app.query_state.schema.clone() else {
+                        return Ok(false);
+                    };
+                    let items = schema_tree_items(&schema);
+                    let tree = FileTree::new(&items).expect("schema tree identifiers are unique");
+                    if tree.handle_mouse_event(&mut app.ui_state.schema_state, mouse_event, area)? {
+                        app.navigation.start_editing(PaneId::Collections)?;
+                        return Ok(true);
+                    }
+                    return Ok(false);
+                }
                 let tree = FileTree::new(&app.ui_state.collection_items).expect("all item identifiers are unique");
                 
                 if tree.handle_mouse_event(&mut app.ui_state.collection_state, mouse_event, area)? {
@@ -195,4 +277,94 @@ impl Pane for CollectionsPane {
         }
         Ok(false)
     }
-}
\ No newline at end of file
+}
+
+/// Draws the schema tree, or what is keeping it from being shown
+fn render_schema(app: &mut App, frame: &mut Frame, area: Rect) {
+    let Some(schema) = app.query_state.schema.clone() else {
+        let message = if app.query_state.selected_connection.is_none() {
+            "Select a connection to browse its schema"
+        } else if app.query_state.schema_load.is_some() {
+            "Loading schema..."
+        } else {
+            "Press r to load the schema"
+        };
+        let message = Paragraph::new(message)
+            .style(Style::default().fg(Color::DarkGray))
+            .wrap(Wrap { trim: true });
+        frame.render_widget(message, area);
+        return;
+    };
+
+    let items = schema_tree_items(&schema);
+    let tree = FileTree::new(&items)
+        .expect("schema tree identifiers are unique")
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightBlue)
+                .bold()
+        )
+        .experimental_scrollbar(Some(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .track_symbol(None)
+                .end_symbol(None)
+        ));
+    frame.render_stateful_widget(tree, area, &mut app.ui_state.schema_state);
+}
+
+/// Schemas, then their tables and views, then each one's columns, indexes and constraints.
+/// Nodes are identified by name, so open nodes stay open when the schema is refreshed.
+fn schema_tree_items(schema: &DatabaseSchema) -> Vec<TreeItem<'static, String>> {
+    schema.schemas
+        .iter()
+        .map(|s| {
+            let tables = s.tables.iter().map(table_tree_item).collect();
+            TreeItem::new(s.name.clone(), s.name.clone(), tables).expect("table names are unique within a schema")
+        })
+        .collect()
+}
+
+fn table_tree_item(table: &Table) -> TreeItem<'static, String> {
+    let detail = Style::default().fg(Color::DarkGray);
+
+    let columns: Vec<_> = table.columns
+        .iter()
+        .map(|c| {
+            let mut spans = vec![Span::raw(c.name.clone()), Span::styled(format!(" {}", c.data_type), detail)];
+            if !c.nullable {
+                spans.push(Span::styled(" not null", detail));
+            }
+            TreeItem::new_leaf(c.name.clone(), Line::from(spans))
+        })
+        .collect();
+
+    let indexes: Vec<_> = table.indexes
+        .iter()
+        .map(|i| {
+            let mut spans = vec![Span::raw(i.name.clone()), Span::styled(format!(" ({})", i.columns.join(", ")), detail)];
+            if i.unique {
+                spans.push(Span::styled(" unique", detail));
+            }
+            TreeItem::new_leaf(i.name.clone(), Line::from(spans))
+        })
+        .collect();
+
+    let constraints: Vec<_> = table.constraints
+        .iter()
+        .map(|c| TreeItem::new_leaf(c.name.clone(), Line::from(vec![Span::raw(c.name.clone()), Span::styled(format!(" {}", c.kind), detail)])))
+        .collect();
+
+    let children = [("columns", "Columns", columns), ("indexes", "Indexes", indexes), ("constraints", "Constraints", constraints)]
+        .into_iter()
+        .filter(|(_, _, items)| !items.is_empty())
+        .map(|(id, label, items)| TreeItem::new(id.to_string(), label, items).expect("names are unique within a table"))
+        .collect();
+
+    let mut text = vec![Span::raw(table.name.clone())];
+    if table.kind != TableKind::Table {
+        text.push(Span::styled(format!(" {}", table.kind), detail));
+    }
+    TreeItem::new(table.name.clone(), Line::from(text), children).expect("group identifiers are unique")
+}
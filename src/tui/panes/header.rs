@@ -9,14 +9,15 @@ use ratatui::{
     Frame,
 };
 
-use crate::tui::{
-    app::{App, AppCommand}, navigation::PaneId, widgets::button::{Button, State, BLUE}
-};
+use crate::{sql::session::TransactionStatus, tui::{
+    app::{App, AppCommand}, navigation::PaneId, widgets::button::{Button, State, BLUE, LIGHT_GREY, RED}
+}};
 
 use super::pane::{Pane, PaneExt};
 
 pub struct HeaderPane {
     run_query_button: Button<'static>,
+    cancel_query_button: Button<'static>,
 }
 
 impl Default for HeaderPane {
@@ -29,6 +30,7 @@ impl HeaderPane {
     pub fn new() -> Self {
         Self {
             run_query_button: Button::new("Run Query"),
+            cancel_query_button: Button::new("Cancel"),
         }
     }
 
@@ -40,31 +42,39 @@ impl HeaderPane {
         }
     }
 
-    fn render_connection_button(&mut self, frame: &mut Frame<'_>, area: Rect) {
+    fn render_connection_button(&mut self, frame: &mut Frame<'_>, area: Rect, is_query_running: bool, is_danger: bool) {
         let horizontal = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Min(1), 
                 Constraint::Length(15),
                 Constraint::Length(1),
+                Constraint::Length(10),
+                Constraint::Length(1),
             ])
             .split(area);
     
         self.run_query_button.set_area(horizontal[1]);
         frame.render_widget(
-            self.run_query_button.clone().theme(BLUE),
+            self.run_query_button.clone().theme(if is_danger { RED } else { BLUE }),
             horizontal[1]
         );
+
+        // Greyed out while there is nothing to cancel
+        let cancel_theme = if is_query_running { RED } else { LIGHT_GREY };
+        self.cancel_query_button.set_area(horizontal[3]);
+        frame.render_widget(
+            self.cancel_query_button.clone().theme(cancel_theme),
+            horizontal[3]
+        );
     }
 
-    fn is_mouse_event_on_button(&self, mouse_event: MouseEvent) -> bool {
-        let terminal_width = crossterm::terminal::size().unwrap_or((80, 24)).0;
-    
-        let button_width = 15;
-        let button_x_start = terminal_width.saturating_sub(button_width + 1);
-        let button_x_end = terminal_width.saturating_sub(2);
-    
-        mouse_event.column >= button_x_start && mouse_event.column <= button_x_end
+    fn button_state(is_editing: bool, is_focused: bool) -> State {
+        match (is_editing, is_focused) {
+            (true, true) => State::Selected,
+            (false, true) => State::Hover,
+            _ => State::Normal,
+        }
     }
 }
 
@@ -73,10 +83,14 @@ impl Pane for HeaderPane {
         PaneId::Header
     }
 
-    fn title(&self) -> &'static str {
+    fn title(&self, _app: &App) -> &'static str {
         "Connection"
     }
 
+    fn border_color(&self, app: &App) -> Option<Color> {
+        app.is_danger_connection().then_some(Color::Red)
+    }
+
     fn render_content(&mut self, app: &mut App, frame: &mut Frame, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -88,7 +102,10 @@ impl Pane for HeaderPane {
         let focused_element = self.get_focused_element(app);
         let is_editing = app.is_pane_in_edit_mode(self.pane_id());
 
-        let connection_style = if is_editing && focused_element == 0 {
+        let is_danger = app.is_danger_connection();
+        let connection_style = if is_danger {
+            Style::default().fg(Color::Red).bold()
+        } else if is_editing && focused_element == 0 {
             Style::default().fg(Color::LightBlue).bold()
         } else if focused_element == 0 {
             Style::default().fg(Color::LightBlue)
@@ -103,23 +120,33 @@ impl Pane for HeaderPane {
             Some(name) => format!("{prefix}{name}{suffix}"),
             None => "No connection selected".to_string(),
         };
-
-        if is_editing && focused_element == 1 {
-            self.run_query_button.set_state(State::Selected);
-        } else if focused_element == 1 {
-            self.run_query_button.set_state(State::Hover);
-        } else {
-            self.run_query_button.set_state(State::Normal);
+        let mut connection_line = Line::from(Span::styled(connection_name, connection_style));
+        if is_danger {
+            connection_line.push_span(Span::styled("  ⚠ PRODUCTION", Style::default().fg(Color::Red).bold()));
+        }
+        if app.is_read_only_connection() {
+            connection_line.push_span(Span::styled("  [read-only]", Style::default().fg(Color::Cyan)));
+        }
+        if let Some(status) = app.query_state.transaction_status() {
+            let status_color = match status {
+                TransactionStatus::Idle => Color::Green,
+                TransactionStatus::InTransaction => Color::Yellow,
+                TransactionStatus::Failed => Color::Red,
+            };
+            connection_line.push_span(Span::raw("  "));
+            connection_line.push_span(Span::styled(format!("[session: {}]", status), Style::default().fg(status_color).bold()));
         }
 
+        self.run_query_button.set_state(Self::button_state(is_editing, focused_element == 1));
+        self.cancel_query_button.set_state(Self::button_state(is_editing, focused_element == 2));
+
         frame.render_widget(
-            Paragraph::new(connection_name)
-                .style(connection_style)
+            Paragraph::new(connection_line)
                 .alignment(Alignment::Left),
             chunks[0]
         );
 
-        self.render_connection_button(frame, chunks[0]);
+        self.render_connection_button(frame, chunks[0], app.query_state.is_query_running(), is_danger);
     }
 
     fn get_custom_instructions(&self, app: &App, is_editing: bool) -> Line<'static> {
@@ -137,7 +164,7 @@ impl Pane for HeaderPane {
                     " ^C ".blue().bold(),
                     "Quit ".white(),
                 ])
-            } else {
+            } else if focused_element == 1 {
                 Line::from(vec![
                     " Esc ".blue().bold(),
                     "Return ".white(),
@@ -148,6 +175,17 @@ impl Pane for HeaderPane {
                     " ^C ".blue().bold(),
                     "Quit ".white(),
                 ])
+            } else {
+                Line::from(vec![
+                    " Esc ".blue().bold(),
+                    "Return ".white(),
+                    " Tab ".blue().bold(),
+                    "Next Element ".white(),
+                    " Space ".blue().bold(),
+                    "Cancel Query ".white(),
+                    " ^C ".blue().bold(),
+                    "Quit ".white(),
+                ])
             }
         } else {
             Line::from(vec![
@@ -181,12 +219,12 @@ impl Pane for HeaderPane {
                 Ok(false)
             },
             KeyCode::Enter | KeyCode::Char(' ') => {
-                if focused_element == 1 {
-                    app.query_state.pending_command = AppCommand::ExecuteQuery;
-                    Ok(false)
-                } else {
-                    Ok(false)
+                match focused_element {
+                    1 => app.query_state.pending_command = AppCommand::ExecuteQuery,
+                    2 => app.query_state.pending_command = AppCommand::CancelQuery,
+                    _ => {}
                 }
+                Ok(false)
             },
             KeyCode::Left => {
                 if focused_element == 0 {
@@ -220,14 +258,16 @@ impl Pane for HeaderPane {
     fn handle_custom_mouse_event(&mut self, app: &mut App, mouse_event: MouseEvent) -> Result<bool> {
         match mouse_event.kind {
             MouseEventKind::Down(MouseButton::Left) => {
-                if self.is_mouse_event_on_button(mouse_event) {
+                if self.run_query_button.handle_mouse_event(mouse_event) {
                     app.query_state.pending_command = AppCommand::ExecuteQuery;
-                    return Ok(false);
+                } else if self.cancel_query_button.handle_mouse_event(mouse_event) {
+                    app.query_state.pending_command = AppCommand::CancelQuery;
                 }
                 Ok(false)
             },
             _ => {
                 self.run_query_button.handle_mouse_event(mouse_event);
+                self.cancel_query_button.handle_mouse_event(mouse_event);
                 Ok(false)
             }
         }
@@ -13,10 +13,14 @@ use crate::tui::{
 
 pub trait Pane {
     fn pane_id(&self) -> PaneId;
-    fn title(&self) -> &'static str;
+    fn title(&self, app: &App) -> &'static str;
     fn title_bottom(&self, _app: &App) -> String {
         String::new()
     }
+    /// Overrides the border color, e.g. to warn about the selected connection
+    fn border_color(&self, _app: &App) -> Option<Color> {
+        None
+    }
     fn render_content(&mut self, app: &mut App, frame: &mut Frame, area: Rect);
     fn get_custom_instructions(&self, app: &App, is_editing: bool) -> Line<'static>;
     fn handle_activate(&mut self, _app: &mut App) -> Result<bool> {
@@ -45,14 +49,17 @@ pub trait PaneExt: Pane {
             FocusType::Inactive
         };
 
-        let focus_style = match focus_type {
+        let mut focus_style = match focus_type {
             FocusType::Editing => Style::default().fg(Color::LightBlue).bold(),
             FocusType::Active => Style::default().fg(Color::LightBlue),
             FocusType::Inactive => Style::default().fg(Color::White),
         };
+        if let Some(color) = self.border_color(app) {
+            focus_style = focus_style.fg(color);
+        }
 
         let mut block = Block::default()
-            .title(self.title())
+            .title(self.title(app))
             .title_style(focus_style)
             .borders(Borders::ALL)
             .border_style(focus_style);
@@ -5,15 +5,17 @@ use ratatui::{
     layout::{Alignment, Constraint, Rect},
     style::{Color, Modifier, Style},
     text::Line,
-    widgets::{Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState},
+    widgets::{Block, Borders, Cell, Paragraph, Wrap, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState},
     Frame,
 };
 
+use tui_tree_widget::TreeItem;
+
 use crate::{
-    sql::result::QueryResult, 
+    sql::{error::QueryError, explain::{ExplainPlan, PlanNode}, notice::{Notice, NoticeSeverity}, result::QueryResult},
     tui::{
-        app::App,
-        navigation::PaneId, widgets::wide_table::{WideTable, WideTableState},
+        app::{App, ListenState},
+        navigation::PaneId, widgets::{file_tree::FileTree, wide_table::{WideTable, WideTableState}},
     }
 };
 
@@ -91,6 +93,11 @@ impl ResultsPane {
             state.scroll_right();
         }
     }
+
+    fn reset_table_state(&mut self) {
+        self.table_state = TableState::default().with_selected(0);
+        self.wide_table_state = None;
+    }
 }
 
 impl Pane for ResultsPane {
@@ -98,49 +105,110 @@ impl Pane for ResultsPane {
         PaneId::Results
     }
 
-    fn title(&self) -> &'static str {
+    fn title(&self, _app: &App) -> &'static str {
         "Results"
     }
 
     fn title_bottom(&self, app: &App) -> String {
-        let execution_time_ms = app.query_state.query_result.execution_time.as_millis();
-        let status_text = format!(
-            "Query time: {}ms | {} rows",
-            execution_time_ms,
-            app.query_state.query_result.row_count,
+        if app.query_state.query_cancelled {
+            return "Cancelled".to_string();
+        }
+        if let Some(error) = &app.query_state.query_error {
+            return format!("{} | SQLSTATE {}", error.class(), error.code);
+        }
+        if let Some(plan) = &app.query_state.explain_plan {
+            let mut status_text = format!("Total cost: {:.2}", plan.root.total_cost);
+            if let Some(ms) = plan.planning_time {
+                status_text = format!("{} | Planning: {:.3}ms", status_text, ms);
+            }
+            if let Some(ms) = plan.execution_time {
+                status_text = format!("{} | Execution: {:.3}ms", status_text, ms);
+            }
+            return status_text;
+        }
+        let empty = QueryResult::empty();
+        let result = app.query_state.current_result().unwrap_or(&empty);
+        // Statements that return no rows are summed up by their tag, e.g. `UPDATE 2`
+        let outcome = if result.columns.is_empty() && !result.command_tag.is_empty() {
+            match result.rows_affected {
+                Some(1) => format!("{} | 1 row affected", result.command_tag),
+                Some(n) => format!("{} | {} rows affected", result.command_tag, n),
+                None => result.command_tag.clone(),
+            }
+        } else {
+            format!("{} rows", result.row_count)
+        };
+        let mut status_text = format!(
+            "Query time: {}ms | {}",
+            result.query_time().as_millis(),
+            outcome,
         );
+        if !result.connect_time.is_zero() {
+            status_text = format!("Connect: {}ms | {}", result.connect_time.as_millis(), status_text);
+        }
+        let result_count = app.query_state.query_results.len();
+        if result_count > 1 {
+            status_text = format!("Result {}/{} | {}", app.query_state.selected_result + 1, result_count, status_text);
+        }
         status_text
     }
 
     fn render_content(&mut self, app: &mut App, frame: &mut Frame, area: Rect) {
-        // let header_cells = app.query_state.query_result.columns.iter()
-        //     .map(|h| Cell::from(h.as_str()).style(Style::default().bold()));
-        // let header = Row::new(header_cells)
-        //     .style(Style::default().bg(Color::DarkGray))
-        //     .height(1);
-    
-        // let constraints = calculate_column_constraints(&app.query_state.query_result);
-        let column_count = app.query_state.query_result.columns.len();
-        let row_count = app.query_state.query_result.rows.len();
-
-        if row_count == 0 || app.query_state.query_result.columns.is_empty() {
-            let empty_message = Paragraph::new("No query results to display. Run a query using the button above.")
+        let area = match &app.query_state.listen {
+            Some(listen) => render_notifications(frame, area, listen),
+            None => area,
+        };
+        if app.query_state.query_cancelled {
+            let cancelled_message = Paragraph::new("Query cancelled")
                 .alignment(Alignment::Center)
-                .style(Style::default().fg(Color::DarkGray));
-            
-            frame.render_widget(empty_message, area);
+                .style(Style::default().fg(Color::Yellow));
+            frame.render_widget(cancelled_message, area);
+            return;
+        }
+        if let Some(error) = &app.query_state.query_error {
+            render_query_error(frame, area, error);
+            return;
+        }
+        if let Some(plan) = &app.query_state.explain_plan {
+            let items = vec![plan_tree_item(plan, &plan.root, 0)];
+            let tree = FileTree::new(&items)
+                .expect("plan node identifiers are unique")
+                .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+                .experimental_scrollbar(Some(
+                    Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                        .begin_symbol(None)
+                        .track_symbol(None)
+                        .end_symbol(None)
+                ));
+            frame.render_stateful_widget(tree, area, &mut app.ui_state.explain_state);
+            return;
+        }
+        let Some(result) = app.query_state.current_result() else {
+            render_empty_message(frame, area, "No query results to display. Run a query using the button above.");
+            return;
+        };
+        let area = render_messages(frame, area, &result.notices);
+        let column_count = result.columns.len();
+        let row_count = result.rows.len();
+
+        if row_count == 0 || result.columns.is_empty() {
+            if result.command_tag.is_empty() {
+                render_empty_message(frame, area, "No query results to display. Run a query using the button above.");
+            } else {
+                render_empty_message(frame, area, &result.command_tag);
+            }
             return;
         }
 
         self.use_wide_table = column_count > 8;
             
         if self.use_wide_table {
-            if self.wide_table_state.is_none() {
+            if self.wide_table_state.as_ref().is_none_or(|state| state.column_count != column_count) {
                 self.wide_table_state = Some(WideTableState::new(column_count));
             }
 
             if let Some(state) = &mut self.wide_table_state {
-                let wide_table = WideTable::new(&app.query_state.query_result)
+                let wide_table = WideTable::new(result)
                     .highlight_style(
                         Style::default()
                             .bg(Color::LightBlue)
@@ -152,16 +220,23 @@ impl Pane for ResultsPane {
                 frame.render_stateful_widget(wide_table, area, state);
             }
         } else {
-            let header_cells = app.query_state.query_result.columns.iter()
+            let header_cells = result.columns.iter()
                 .map(|h| Cell::from(h.as_str()).style(Style::default().bold()));
             let header = Row::new(header_cells)
                 .style(Style::default().bg(Color::DarkGray))
                 .height(1);
         
-            let constraints = calculate_column_constraints(&app.query_state.query_result);
+            let constraints = calculate_column_constraints(result);
             
-            let rows = app.query_state.query_result.rows.iter().map(|item| {
-                let cells = item.iter().map(|c| Cell::from(c.as_str()));
+            let rows = result.rows.iter().map(|item| {
+                let cells = item.iter().map(|value| {
+                    let cell = Cell::from(value.to_string());
+                    if value.is_null() {
+                        cell.style(Style::default().fg(Color::DarkGray))
+                    } else {
+                        cell
+                    }
+                });
                 Row::new(cells).height(1)
             });
             
@@ -190,29 +265,48 @@ impl Pane for ResultsPane {
         }
     }
 
-    fn get_custom_instructions(&self, _app: &App, is_editing: bool) -> Line<'static> {
+    fn get_custom_instructions(&self, app: &App, is_editing: bool) -> Line<'static> {
+        if is_editing && app.query_state.explain_plan.is_some() {
+            return Line::from(vec![
+                " Esc ".blue().bold(),
+                "Stop Editing ".white(),
+                " ↑/↓ ".blue().bold(),
+                "Navigate ".white(),
+                " ←/→ ".blue().bold(),
+                "Collapse/Expand ".white(),
+                " ^C ".blue().bold(),
+                "Quit ".white(),
+            ]);
+        }
         if is_editing {
-            if self.use_wide_table {
-                Line::from(vec![
+            let mut spans = if self.use_wide_table {
+                vec![
                     " Esc ".blue().bold(),
                     "Stop Editing ".white(),
                     " ↑/↓ ".blue().bold(),
                     "Navigate Rows ".white(),
                     " ←/→ ".blue().bold(),
                     "Scroll Columns ".white(),
-                    " ^C ".blue().bold(),
-                    "Quit ".white(),
-                ])
+                ]
             } else {
-                Line::from(vec![
+                vec![
                     " Esc ".blue().bold(),
                     "Stop Editing ".white(),
                     " ↑/↓ ".blue().bold(),
                     "Navigate ".white(),
-                    " ^C ".blue().bold(),
-                    "Quit ".white(),
-                ])
+                ]
+            };
+            if app.query_state.query_results.len() > 1 {
+                spans.extend([
+                    " [/] ".blue().bold(),
+                    "Result Set ".white(),
+                ]);
             }
+            spans.extend([
+                " ^C ".blue().bold(),
+                "Quit ".white(),
+            ]);
+            Line::from(spans)
         } else {
             Line::from(vec![
                 " Tab ".blue().bold(),
@@ -226,16 +320,41 @@ impl Pane for ResultsPane {
     }
 
     fn handle_edit_mode_key(&mut self, app: &mut App, key: KeyEvent) -> Result<bool> {
+        if app.query_state.explain_plan.is_some() {
+            let state = &mut app.ui_state.explain_state;
+            match key.code {
+                KeyCode::Esc => return self.deactivate(app),
+                KeyCode::Up => { state.key_up(); },
+                KeyCode::Down => { state.key_down(); },
+                KeyCode::Left => { state.key_left(); },
+                KeyCode::Right => { state.key_right(); },
+                KeyCode::Enter | KeyCode::Char(' ') => { state.toggle_selected(); },
+                _ => {}
+            }
+            return Ok(false);
+        }
         match key.code {
             KeyCode::Esc => {
                 self.deactivate(app)
             },
             KeyCode::Up => {
-                self.previous_row(app.query_state.query_result.rows.len().saturating_sub(1));
+                self.previous_row(current_row_count(app).saturating_sub(1));
                 Ok(false)
             },
             KeyCode::Down => {
-                self.next_row(app.query_state.query_result.rows.len().saturating_sub(1));
+                self.next_row(current_row_count(app).saturating_sub(1));
+                Ok(false)
+            },
+            KeyCode::Char('[') => {
+                if app.query_state.previous_result() {
+                    self.reset_table_state();
+                }
+                Ok(false)
+            },
+            KeyCode::Char(']') => {
+                if app.query_state.next_result() {
+                    self.reset_table_state();
+                }
                 Ok(false)
             },
             KeyCode::Left if self.use_wide_table => {
@@ -268,6 +387,148 @@ impl Pane for ResultsPane {
     }
 }
 
+/// A plan node and its children, with its most expensive nodes in red and
+/// bad row estimates in yellow
+fn plan_tree_item(plan: &ExplainPlan, node: &PlanNode, id: usize) -> TreeItem<'static, usize> {
+    let summary_style = if plan.is_hot(node) {
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().add_modifier(Modifier::BOLD)
+    };
+    // Notes go before the stats so they stay visible in narrow panes
+    let mut spans = vec![Span::styled(node.summary(), summary_style)];
+    for note in plan.annotations(node) {
+        let color = if note.starts_with("rows") { Color::Yellow } else { Color::Red };
+        spans.push(Span::styled(format!("  [{}]", note), Style::default().fg(color)));
+    }
+    spans.push(Span::styled(format!("  {}", node.stats()), Style::default().fg(Color::DarkGray)));
+
+    let children = node.children
+        .iter()
+        .enumerate()
+        .map(|(i, child)| plan_tree_item(plan, child, i))
+        .collect();
+    TreeItem::new(id, Line::from(spans), children).expect("plan node identifiers are unique")
+}
+
+fn current_row_count(app: &App) -> usize {
+    app.query_state.current_result().map(|r| r.rows.len()).unwrap_or(0)
+}
+
+/// Most notices shown below the results; the rest are cut off
+const MAX_MESSAGE_LINES: usize = 5;
+
+/// Shows the statement's notices in a section at the bottom of `area`, returning the area left for the results
+fn render_messages(frame: &mut Frame, area: Rect, notices: &[Notice]) -> Rect {
+    if notices.is_empty() {
+        return area;
+    }
+    let [results_area, messages_area] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(notices.len().min(MAX_MESSAGE_LINES) as u16 + 1),
+    ]).areas(area);
+
+    let lines: Vec<Line> = notices
+        .iter()
+        .map(|notice| {
+            let color = match notice.severity {
+                NoticeSeverity::Warning => Color::Yellow,
+                _ => Color::Cyan,
+            };
+            Line::from(vec![
+                Span::styled(format!("{}: ", notice.severity), Style::default().fg(color).bold()),
+                Span::raw(notice.message.clone()),
+            ])
+        })
+        .collect();
+    let messages = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(format!(" Messages ({}) ", notices.len()))
+    );
+    frame.render_widget(messages, messages_area);
+    results_area
+}
+
+/// Most notifications shown below the results, newest last
+const MAX_NOTIFICATION_LINES: usize = 6;
+
+/// Shows the latest notifications in a section at the bottom of `area`, returning the area left for the results
+fn render_notifications(frame: &mut Frame, area: Rect, listen: &ListenState) -> Rect {
+    let shown = listen.notifications.len().clamp(1, MAX_NOTIFICATION_LINES);
+    let [results_area, notifications_area] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(shown as u16 + 1),
+    ]).areas(area);
+
+    let lines: Vec<Line> = if listen.notifications.is_empty() {
+        vec![Line::styled("Waiting for notifications...", Style::default().fg(Color::DarkGray))]
+    } else {
+        listen.notifications
+            .iter()
+            .skip(listen.notifications.len() - shown)
+            .map(|notification| {
+                Line::from(vec![
+                    Span::styled(format!("{} ", notification.received_at.format("%H:%M:%S%.3f")), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{}: ", notification.channel), Style::default().fg(Color::Magenta).bold()),
+                    Span::raw(notification.payload.clone()),
+                ])
+            })
+            .collect()
+    };
+    let title = match &listen.disconnected {
+        Some(_) => format!(" Notifications on {} (reconnecting...) ", listen.channels.join(", ")),
+        None => format!(" Notifications on {} ({}) ", listen.channels.join(", "), listen.notifications.len()),
+    };
+    let notifications = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(title)
+    );
+    frame.render_widget(notifications, notifications_area);
+    results_area
+}
+
+/// Shows the server's error like psql does, with a caret under the error position
+fn render_query_error(frame: &mut Frame, area: Rect, error: &QueryError) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{}: ", error.severity), Style::default().fg(Color::Red).bold()),
+        Span::styled(error.message.clone(), Style::default().bold()),
+    ])];
+    if let Some(position) = &error.position {
+        let prefix = format!("LINE {}: ", position.line + 1);
+        lines.push(Line::from(vec![
+            Span::styled(prefix.clone(), Style::default().fg(Color::DarkGray)),
+            Span::raw(position.line_text.clone()),
+        ]));
+        lines.push(Line::styled(
+            format!("{}^", " ".repeat(prefix.len() + position.column)),
+            Style::default().fg(Color::Red).bold(),
+        ));
+    }
+    let fields = [("DETAIL", &error.detail), ("HINT", &error.hint), ("CONTEXT", &error.context), ("CONSTRAINT", &error.constraint)];
+    for (label, value) in fields {
+        if let Some(value) = value {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}: ", label), Style::default().fg(Color::Yellow).bold()),
+                Span::raw(value.clone()),
+            ]));
+        }
+    }
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+}
+
+fn render_empty_message(frame: &mut Frame, area: Rect, message: &str) {
+    let empty_message = Paragraph::new(message.to_string())
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::DarkGray));
+
+    frame.render_widget(empty_message, area);
+}
+
 fn calculate_column_constraints(query_result: &QueryResult) -> Vec<Constraint> {
     if query_result.columns.is_empty() {
         return vec![];
@@ -280,7 +541,7 @@ fn calculate_column_constraints(query_result: &QueryResult) -> Vec<Constraint> {
     for row in &query_result.rows {
         for (i, cell) in row.iter().enumerate() {
             if i < max_widths.len() {
-                max_widths[i] = max_widths[i].max(cell.len());
+                max_widths[i] = max_widths[i].max(cell.to_string().len());
             }
         }
     }
@@ -6,9 +6,12 @@ use ratatui::{
     Frame,
 };
 
-use crate::tui::{
-    app::{App, AppCommand},
-    navigation::PaneId,
+use crate::{
+    sql::explain::ExplainMode,
+    tui::{
+        app::{App, AppCommand},
+        navigation::PaneId,
+    },
 };
 
 use super::pane::{Pane, PaneExt};
@@ -36,17 +39,17 @@ impl Pane for WorkspacePane {
         PaneId::Workspace
     }
 
-    fn title(&self) -> &'static str {
+    fn title(&self, _app: &App) -> &'static str {
         "Workspace"
     }
 
     fn render_content(&mut self, app: &mut App, frame: &mut Frame, area: Rect) {
         let search_height = if app.ui_state.search.open { 3 } else { 0 };
         
-        let workspace_widget = app.ui_state.workspace.clone();
+        let workspace_widget = &app.ui_state.workspace;
         
         if !app.ui_state.search.open {
-            frame.render_widget(&workspace_widget, area);
+            frame.render_widget(workspace_widget, area);
             return;
         }
         
@@ -57,7 +60,7 @@ impl Pane for WorkspacePane {
             area.height.saturating_sub(search_height)
         );
         
-        frame.render_widget(&workspace_widget, workspace_area);
+        frame.render_widget(workspace_widget, workspace_area);
     }
 
     fn get_custom_instructions(&self, _app: &App, is_editing: bool) -> Line<'static> {
@@ -69,6 +72,14 @@ impl Pane for WorkspacePane {
                 "Save ".white(),
                 " ^Space ".blue().bold(),
                 "Run ".white(),
+                " Tab ".blue().bold(),
+                "Complete ".white(),
+                " ^G ".blue().bold(),
+                "Cancel ".white(),
+                " ^L ".blue().bold(),
+                "Explain ".white(),
+                " ^T ".blue().bold(),
+                "Session ".white(),
                 " ^C ".blue().bold(),
                 "Quit ".white(),
             ])
@@ -85,6 +96,29 @@ impl Pane for WorkspacePane {
     }
 
     fn handle_edit_mode_key(&mut self, app: &mut App, key: KeyEvent) -> Result<bool> {
+        // The error mark is a selection, which the next key would otherwise act on
+        app.ui_state.workspace.clear_error_mark();
+        if app.ui_state.workspace.is_completing() {
+            match key.code {
+                KeyCode::Up => {
+                    app.ui_state.workspace.select_previous_completion();
+                    return Ok(false);
+                }
+                KeyCode::Down => {
+                    app.ui_state.workspace.select_next_completion();
+                    return Ok(false);
+                }
+                KeyCode::Enter | KeyCode::Tab => {
+                    app.ui_state.workspace.accept_completion();
+                    return Ok(false);
+                }
+                KeyCode::Esc => {
+                    app.ui_state.workspace.close_completions();
+                    return Ok(false);
+                }
+                _ => {}
+            }
+        }
         match key.code {
             KeyCode::Esc => {
                 self.deactivate(app)
@@ -98,9 +132,30 @@ impl Pane for WorkspacePane {
                 self.deactivate(app)?;
                 Ok(false)
             }
+            // ^L shows the plan; Alt+L runs the query to show actual times too
+            KeyCode::Char('l') if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                let mode = if key.modifiers.contains(KeyModifiers::ALT) { ExplainMode::Analyze } else { ExplainMode::Plan };
+                app.query_state.pending_command = AppCommand::ExplainQuery(mode);
+                self.deactivate(app)?;
+                Ok(false)
+            }
+            // Alt+Shift+F, as in many editors
+            KeyCode::Char('f' | 'F') if key.modifiers.contains(KeyModifiers::ALT | KeyModifiers::SHIFT) => {
+                app.format_workspace();
+                Ok(false)
+            }
+            // Tab completes a word, and indents anywhere else
+            KeyCode::Tab if app.ui_state.workspace.is_after_word() => {
+                app.complete_at_cursor();
+                Ok(false)
+            }
             _ => {
                 let input = tui_textarea::Input::from(key);
                 app.ui_state.workspace.input(input);
+                // Completions follow the word as it is typed, and open after a `.`
+                if app.ui_state.workspace.is_completing() || key.code == KeyCode::Char('.') {
+                    app.complete_at_cursor();
+                }
                 Ok(false)
             }
         }
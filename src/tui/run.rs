@@ -25,6 +25,7 @@ pub fn run_tui(settings: Option<UserSettings>) -> Result<()> {
     let mut ui = UI::new();
     let events = EventHandler::new(250);
     let res = run_app(&mut terminal, &mut app, &events, &mut ui);
+    app.shutdown();
 
     disable_raw_mode()?;
     execute!(
@@ -143,13 +143,9 @@ impl<'a> Button<'a> {
                     false
                 }
             },
-            MouseEventKind::Up(MouseButton::Left) => {
-                if self.state == State::Active {
-                    self.state = State::Normal;
-                    true
-                } else {
-                    false
-                }
+            MouseEventKind::Up(MouseButton::Left) if self.state == State::Active => {
+                self.state = State::Normal;
+                true
             },
             _ => false
         }
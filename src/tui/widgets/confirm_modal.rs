@@ -0,0 +1,130 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+use std::any::Any;
+
+use super::{
+    button::Theme,
+    modal::{DialogButton, DialogContent, FocusableArea, ModalAction, ModalDialog, ModalHandler},
+};
+
+/// A choice offered by a `ConfirmModal`; picking it emits `ModalAction::Custom(action)`
+pub struct ConfirmChoice {
+    pub label: &'static str,
+    pub action: &'static str,
+    pub theme: Theme,
+}
+
+impl ConfirmChoice {
+    pub fn new(label: &'static str, action: &'static str, theme: Theme) -> Self {
+        Self { label, action, theme }
+    }
+}
+
+/// Shows a message and asks the user to pick one of a few choices
+pub struct ConfirmModal {
+    title: &'static str,
+    message: String,
+    choices: Vec<ConfirmChoice>,
+    focus_idx: usize,
+    /// Width and height in percent of the screen
+    dimensions: (u16, u16),
+    /// Keeps the message's indentation, e.g. for SQL
+    preformatted: bool,
+}
+
+impl ConfirmModal {
+    /// The first choice is focused initially, so it should be the safe one
+    pub fn new(title: &'static str, message: impl Into<String>, choices: Vec<ConfirmChoice>) -> Self {
+        Self {
+            title,
+            message: message.into(),
+            choices,
+            focus_idx: 0,
+            dimensions: (50, 35),
+            preformatted: false,
+        }
+    }
+
+    pub fn with_dimensions(mut self, width: u16, height: u16) -> Self {
+        self.dimensions = (width, height);
+        self
+    }
+
+    pub fn preformatted(mut self) -> Self {
+        self.preformatted = true;
+        self
+    }
+
+    fn dialog(&self) -> ModalDialog<'_, Paragraph<'_>> {
+        let content = DialogContent {
+            title: self.title,
+            content_widget: Paragraph::new(self.message.as_str())
+                .style(Style::default().fg(Color::White))
+                .wrap(Wrap { trim: !self.preformatted }),
+            buttons: self.choices
+                .iter()
+                .map(|choice| DialogButton::new(choice.label, choice.action).with_theme(choice.theme))
+                .collect(),
+        };
+
+        ModalDialog::new(content)
+            .with_dimensions(self.dimensions.0, self.dimensions.1)
+            .with_content_element_count(0)
+            .with_focused_area(FocusableArea::Button(self.focus_idx))
+    }
+}
+
+impl ModalHandler for ConfirmModal {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<ModalAction> {
+        match key_event.code {
+            KeyCode::Tab | KeyCode::Right => {
+                self.handle_tab(key_event.modifiers.contains(KeyModifiers::SHIFT))
+            },
+            KeyCode::BackTab | KeyCode::Left => {
+                self.handle_tab(true)
+            },
+            KeyCode::Enter => {
+                match self.choices.get(self.focus_idx) {
+                    Some(choice) => Ok(ModalAction::Custom(choice.action.to_string())),
+                    None => Ok(ModalAction::None),
+                }
+            },
+            KeyCode::Esc => Ok(ModalAction::Close),
+            _ => Ok(ModalAction::None),
+        }
+    }
+
+    fn handle_tab(&mut self, reverse: bool) -> Result<ModalAction> {
+        let count = self.choices.len().max(1);
+        if reverse {
+            self.focus_idx = if self.focus_idx == 0 { count - 1 } else { self.focus_idx - 1 };
+        } else {
+            self.focus_idx = (self.focus_idx + 1) % count;
+        }
+        Ok(ModalAction::None)
+    }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent, area: Rect) -> Result<ModalAction> {
+        let result = self.dialog().handle_mouse_event(mouse_event, area)?;
+        if let ModalAction::Custom(ref action) = result {
+            if let Some(idx) = self.choices.iter().position(|c| c.action == action) {
+                self.focus_idx = idx;
+            }
+        }
+        Ok(result)
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(self.dialog(), area);
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+    Frame,
+};
+use std::any::Any;
+use tui_textarea::TextArea;
+
+use crate::{
+    history::{search, HistoryEntry},
+    tui::widgets::button::{GREEN, LIGHT_GREY},
+};
+
+use super::modal::{DialogButton, DialogContent, FocusableArea, ModalAction, ModalDialog, ModalHandler};
+
+const INPUT_HEIGHT: u16 = 3;
+const CONNECTION_WIDTH: usize = 12;
+
+struct HistoryContent<'a> {
+    input: &'a TextArea<'static>,
+    entries: &'a [HistoryEntry],
+    matches: &'a [usize],
+    selected: usize,
+}
+
+impl HistoryContent<'_> {
+    fn line(entry: &HistoryEntry, selected: bool) -> Line<'static> {
+        let connection = entry.connection.as_deref().unwrap_or("-");
+        let status = if entry.is_success() { " ".into() } else { "✗".red() };
+        let line = Line::from(vec![
+            Span::raw(format!("{} ", entry.timestamp())).dark_gray(),
+            Span::raw(format!("{:<width$.width$} ", connection, width = CONNECTION_WIDTH)).light_blue(),
+            status,
+            Span::raw(format!(" {}", entry.summary())),
+        ]);
+        if selected {
+            line.style(Style::default().bg(Color::Blue))
+        } else {
+            line
+        }
+    }
+}
+
+impl Widget for HistoryContent<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(INPUT_HEIGHT), Constraint::Min(1)])
+            .split(area);
+        Widget::render(self.input, chunks[0], buf);
+
+        let list = chunks[1];
+        if self.matches.is_empty() {
+            let message = if self.entries.is_empty() { "No queries have been run yet" } else { "No matching queries" };
+            Paragraph::new(message).dark_gray().render(list, buf);
+            return;
+        }
+
+        // Scroll so the selected entry stays visible
+        let visible = (list.height as usize).max(1);
+        let first = (self.selected + 1).saturating_sub(visible);
+        let lines: Vec<Line> = self.matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .map(|(i, &idx)| Self::line(&self.entries[idx], i == self.selected))
+            .collect();
+        Paragraph::new(lines).render(list, buf);
+    }
+}
+
+/// Fuzzy-searches the query history, to load a query back into the workspace
+pub struct HistoryModal {
+    entries: Vec<HistoryEntry>,
+    input: TextArea<'static>,
+    /// Indexes into `entries` of the queries matching the search, best first
+    matches: Vec<usize>,
+    selected: usize,
+    focus_idx: usize,
+}
+
+impl HistoryModal {
+    /// `entries` are oldest first, as the history keeps them
+    pub fn new(entries: Vec<HistoryEntry>) -> Self {
+        let mut input = TextArea::default();
+        input.set_style(Style::default().bg(Color::Black));
+        input.set_cursor_line_style(Style::default());
+        input.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightBlue))
+                .title("Search")
+        );
+        let matches = search(&entries, "");
+
+        Self {
+            entries,
+            input,
+            matches,
+            selected: 0,
+            focus_idx: 0,
+        }
+    }
+
+    /// The SQL of the selected query, if any match the search
+    pub fn selected_sql(&self) -> Option<&str> {
+        self.matches.get(self.selected).map(|&idx| self.entries[idx].sql.as_str())
+    }
+
+    fn update_matches(&mut self) {
+        let query = self.input.lines().first().cloned().unwrap_or_default();
+        self.matches = search(&self.entries, &query);
+        self.selected = 0;
+    }
+
+    fn buttons(&self) -> Vec<DialogButton<'static>> {
+        vec![
+            DialogButton::new("Cancel", "cancel").with_theme(LIGHT_GREY),
+            DialogButton::new("Load", "load_history").with_theme(GREEN),
+        ]
+    }
+
+    fn element_count(&self) -> usize {
+        1 + self.buttons().len()
+    }
+
+    fn dialog(&self) -> ModalDialog<'_, HistoryContent<'_>> {
+        let content = DialogContent {
+            title: "Query History",
+            content_widget: HistoryContent {
+                input: &self.input,
+                entries: &self.entries,
+                matches: &self.matches,
+                selected: self.selected,
+            },
+            buttons: self.buttons(),
+        };
+        let focused_area = match self.focus_idx {
+            0 => FocusableArea::Content(0),
+            idx => FocusableArea::Button(idx - 1),
+        };
+
+        ModalDialog::new(content)
+            .with_dimensions(80, 70)
+            .with_content_element_count(1)
+            .with_focused_area(focused_area)
+    }
+}
+
+impl ModalHandler for HistoryModal {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<ModalAction> {
+        match key_event.code {
+            KeyCode::Tab => {
+                if key_event.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.handle_tab(true)
+                } else {
+                    self.handle_tab(false)
+                }
+            },
+            KeyCode::BackTab => {
+                self.handle_tab(true)
+            },
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                Ok(ModalAction::None)
+            },
+            KeyCode::Down => {
+                if self.selected + 1 < self.matches.len() {
+                    self.selected += 1;
+                }
+                Ok(ModalAction::None)
+            },
+            KeyCode::Enter => {
+                let action = match self.focus_idx {
+                    0 => "load_history".to_string(),
+                    idx => self.buttons()[idx - 1].action.clone(),
+                };
+                Ok(ModalAction::Custom(action))
+            },
+            KeyCode::Esc => Ok(ModalAction::Close),
+            _ => {
+                if self.focus_idx == 0 && self.input.input(tui_textarea::Input::from(key_event)) {
+                    self.update_matches();
+                }
+                Ok(ModalAction::None)
+            }
+        }
+    }
+
+    fn handle_tab(&mut self, reverse: bool) -> Result<ModalAction> {
+        let count = self.element_count();
+        if reverse {
+            self.focus_idx = if self.focus_idx == 0 { count - 1 } else { self.focus_idx - 1 };
+        } else {
+            self.focus_idx = (self.focus_idx + 1) % count;
+        }
+        Ok(ModalAction::None)
+    }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent, area: Rect) -> Result<ModalAction> {
+        let result = self.dialog().handle_mouse_event(mouse_event, area)?;
+        if let ModalAction::Custom(ref action) = result {
+            if let Some(idx) = self.buttons().iter().position(|button| button.action == *action) {
+                self.focus_idx = idx + 1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(self.dialog(), area);
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{layout::Rect, style::{Color, Style}, widgets::{Block, Borders}, Frame};
+use std::any::Any;
+use tui_textarea::TextArea;
+
+use crate::tui::widgets::button::{GREEN, LIGHT_GREY, RED};
+use super::modal::{DialogButton, DialogContent, FocusableArea, ModalAction, ModalDialog, ModalHandler};
+
+/// Asks which channels to LISTEN on; while listening, it can also stop
+pub struct ListenModal {
+    textarea: TextArea<'static>,
+    listening: bool,
+    focus_idx: usize,
+}
+
+impl ListenModal {
+    /// The input is pre-filled with `channels`, e.g. those currently listened on
+    pub fn new(channels: &[String], listening: bool) -> Self {
+        let mut textarea = TextArea::new(vec![channels.join(" ")]);
+        textarea.set_style(Style::default().bg(Color::Black));
+        textarea.set_block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::LightBlue))
+                .title("Channels (separated by spaces or commas)")
+        );
+        textarea.move_cursor(tui_textarea::CursorMove::End);
+
+        Self {
+            textarea,
+            listening,
+            focus_idx: 0,
+        }
+    }
+
+    pub fn get_channels(&self) -> Vec<String> {
+        self.textarea
+            .lines()
+            .iter()
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|channel| !channel.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn buttons(&self) -> Vec<DialogButton<'static>> {
+        let mut buttons = vec![DialogButton::new("Cancel", "cancel").with_theme(LIGHT_GREY)];
+        if self.listening {
+            buttons.push(DialogButton::new("Stop", "stop_listening").with_theme(RED));
+        }
+        buttons.push(DialogButton::new("Listen", "listen").with_theme(GREEN));
+        buttons
+    }
+
+    fn element_count(&self) -> usize {
+        1 + self.buttons().len()
+    }
+
+    fn dialog(&self) -> ModalDialog<'_, &TextArea<'static>> {
+        let content = DialogContent {
+            title: "Listen for Notifications",
+            content_widget: &self.textarea,
+            buttons: self.buttons(),
+        };
+        let focused_area = match self.focus_idx {
+            0 => FocusableArea::Content(0),
+            idx => FocusableArea::Button(idx - 1),
+        };
+
+        ModalDialog::new(content)
+            .with_dimensions(50, 30)
+            .with_content_element_count(1)
+            .with_focused_area(focused_area)
+    }
+}
+
+impl ModalHandler for ListenModal {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<ModalAction> {
+        match key_event.code {
+            KeyCode::Tab => {
+                if key_event.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.handle_tab(true)
+                } else {
+                    self.handle_tab(false)
+                }
+            },
+            KeyCode::BackTab => {
+                self.handle_tab(true)
+            },
+            KeyCode::Enter => {
+                let action = match self.focus_idx {
+                    0 => "listen".to_string(),
+                    idx => self.buttons()[idx - 1].action.clone(),
+                };
+                Ok(ModalAction::Custom(action))
+            },
+            KeyCode::Esc => Ok(ModalAction::Close),
+            _ => {
+                if self.focus_idx == 0 {
+                    self.textarea.input(tui_textarea::Input::from(key_event));
+                }
+                Ok(ModalAction::None)
+            }
+        }
+    }
+
+    fn handle_tab(&mut self, reverse: bool) -> Result<ModalAction> {
+        let count = self.element_count();
+        if reverse {
+            self.focus_idx = if self.focus_idx == 0 { count - 1 } else { self.focus_idx - 1 };
+        } else {
+            self.focus_idx = (self.focus_idx + 1) % count;
+        }
+        Ok(ModalAction::None)
+    }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent, area: Rect) -> Result<ModalAction> {
+        let result = self.dialog().handle_mouse_event(mouse_event, area)?;
+        if let ModalAction::Custom(ref action) = result {
+            if let Some(idx) = self.buttons().iter().position(|button| button.action == *action) {
+                self.focus_idx = idx + 1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(self.dialog(), area);
+    }
+}
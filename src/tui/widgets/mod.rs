@@ -5,6 +5,10 @@ pub mod searchable_textarea;
 pub mod wide_table;
 
 pub mod modal;
+pub mod confirm_modal;
 pub mod edit_file_modal;
+pub mod history_modal;
+pub mod listen_modal;
 pub mod new_file_modal;
+pub mod params_modal;
 pub mod password_modal;
\ No newline at end of file
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Widget},
+    Frame,
+};
+use std::any::Any;
+use tui_textarea::TextArea;
+
+use crate::{
+    sql::params::{Placeholder, QueryParams},
+    tui::widgets::button::{GREEN, LIGHT_GREY},
+};
+
+use super::modal::{DialogButton, DialogContent, FocusableArea, ModalAction, ModalDialog, ModalHandler};
+
+const INPUT_HEIGHT: u16 = 3;
+
+struct ParamsContent<'a> {
+    inputs: &'a [TextArea<'static>],
+    focused_input: Option<usize>,
+}
+
+impl Widget for ParamsContent<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Scroll so the focused input stays visible when they don't all fit
+        let visible = (area.height / INPUT_HEIGHT).max(1) as usize;
+        let first = self.focused_input
+            .map(|idx| (idx + 1).saturating_sub(visible))
+            .unwrap_or(0);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(INPUT_HEIGHT); visible])
+            .split(area);
+
+        for (i, (input, chunk)) in self.inputs.iter().skip(first).zip(chunks.iter()).enumerate() {
+            Widget::render(input, *chunk, buf);
+            if self.focused_input == Some(first + i) {
+                buf.set_style(*chunk, Style::default().fg(Color::Yellow));
+            }
+        }
+    }
+}
+
+/// Asks for a value for each placeholder in the query about to run
+pub struct ParamsModal {
+    placeholders: Vec<Placeholder>,
+    inputs: Vec<TextArea<'static>>,
+    focus_idx: usize,
+}
+
+impl ParamsModal {
+    /// Inputs are pre-filled from `values`, e.g. the last values used for the file
+    pub fn new(placeholders: Vec<Placeholder>, values: &QueryParams) -> Self {
+        let inputs = placeholders
+            .iter()
+            .map(|placeholder| {
+                let mut input = TextArea::new(values.get(placeholder).map(|v| vec![v.to_string()]).unwrap_or_default());
+                input.set_style(Style::default().bg(Color::Black));
+                input.set_block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::LightBlue))
+                        .title(placeholder.to_string())
+                );
+                input.move_cursor(tui_textarea::CursorMove::End);
+                input
+            })
+            .collect();
+
+        Self {
+            placeholders,
+            inputs,
+            focus_idx: 0,
+        }
+    }
+
+    pub fn get_params(&self) -> QueryParams {
+        let mut params = QueryParams::new();
+        for (placeholder, input) in self.placeholders.iter().zip(&self.inputs) {
+            let value = input.lines().first().cloned().unwrap_or_default();
+            params.set(placeholder.clone(), value);
+        }
+        params
+    }
+
+    fn element_count(&self) -> usize {
+        self.inputs.len() + 2
+    }
+
+    fn focused_area(&self) -> FocusableArea {
+        if self.focus_idx < self.inputs.len() {
+            FocusableArea::Content(self.focus_idx)
+        } else {
+            FocusableArea::Button(self.focus_idx - self.inputs.len())
+        }
+    }
+
+    /// Grows the dialog with the number of parameters, up to most of the screen
+    fn height_percent(&self, area: Rect) -> u16 {
+        let rows = self.inputs.len() as u16 * INPUT_HEIGHT + 10;
+        (rows * 100 / area.height.max(1)).clamp(30, 90)
+    }
+
+    fn dialog(&self, area: Rect) -> ModalDialog<'_, ParamsContent<'_>> {
+        let focused_input = (self.focus_idx < self.inputs.len()).then_some(self.focus_idx);
+        let content = DialogContent {
+            title: "Query Parameters",
+            content_widget: ParamsContent {
+                inputs: &self.inputs,
+                focused_input,
+            },
+            buttons: vec![
+                DialogButton::new("Cancel", "cancel").with_theme(LIGHT_GREY),
+                DialogButton::new("Run", "submit").with_theme(GREEN),
+            ],
+        };
+
+        ModalDialog::new(content)
+            .with_dimensions(50, self.height_percent(area))
+            .with_content_element_count(self.inputs.len())
+            .with_focused_area(self.focused_area())
+    }
+}
+
+impl ModalHandler for ParamsModal {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<ModalAction> {
+        let cancel_idx = self.inputs.len();
+        match key_event.code {
+            KeyCode::Tab => {
+                if key_event.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.handle_tab(true)
+                } else {
+                    self.handle_tab(false)
+                }
+            },
+            KeyCode::BackTab => {
+                self.handle_tab(true)
+            },
+            KeyCode::Enter => {
+                // Enter moves through the inputs and runs the query from the last one
+                if self.focus_idx + 1 < self.inputs.len() {
+                    self.focus_idx += 1;
+                    Ok(ModalAction::None)
+                } else if self.focus_idx == cancel_idx {
+                    Ok(ModalAction::Custom("cancel".to_string()))
+                } else {
+                    Ok(ModalAction::Custom("submit".to_string()))
+                }
+            },
+            KeyCode::Esc => Ok(ModalAction::Close),
+            _ => {
+                if let Some(input) = self.inputs.get_mut(self.focus_idx) {
+                    input.input(tui_textarea::Input::from(key_event));
+                }
+                Ok(ModalAction::None)
+            }
+        }
+    }
+
+    fn handle_tab(&mut self, reverse: bool) -> Result<ModalAction> {
+        let count = self.element_count();
+        if reverse {
+            self.focus_idx = if self.focus_idx == 0 { count - 1 } else { self.focus_idx - 1 };
+        } else {
+            self.focus_idx = (self.focus_idx + 1) % count;
+        }
+        Ok(ModalAction::None)
+    }
+
+    fn handle_mouse_event(&mut self, mouse_event: MouseEvent, area: Rect) -> Result<ModalAction> {
+        let result = self.dialog(area).handle_mouse_event(mouse_event, area)?;
+        if let ModalAction::Custom(ref action) = result {
+            if action == "cancel" {
+                self.focus_idx = self.inputs.len();
+            } else if action == "submit" {
+                self.focus_idx = self.inputs.len() + 1;
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn render(&mut self, frame: &mut Frame, area: Rect) {
+        frame.render_widget(self.dialog(area), area);
+    }
+}
@@ -86,8 +86,7 @@ impl<'a> RadioGroup<'a> {
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints(
-                    std::iter::repeat(Constraint::Ratio(1, self.options.len().try_into().unwrap()))
-                        .take(self.options.len())
+                    std::iter::repeat_n(Constraint::Ratio(1, self.options.len().try_into().unwrap()), self.options.len())
                         .collect::<Vec<_>>()
                 )
                 .split(area);
@@ -108,8 +107,7 @@ impl Widget for RadioGroup<'_> {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(
-                std::iter::repeat(Constraint::Ratio(1, self.options.len().try_into().unwrap()))
-                    .take(self.options.len())
+                std::iter::repeat_n(Constraint::Ratio(1, self.options.len().try_into().unwrap()), self.options.len())
                     .collect::<Vec<_>>()
             )
             .split(area);
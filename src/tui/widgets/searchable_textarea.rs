@@ -1,15 +1,98 @@
-use ratatui::{style::{Color, Style}, widgets::{Block, Widget}};
-use std::ops::{Deref, DerefMut};
-use tui_textarea::TextArea;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, StatefulWidget, Widget},
+};
+use std::{
+    cell::{Cell, RefCell},
+    ops::{Deref, DerefMut},
+};
+use tui_textarea::{CursorMove, Input, Key, TextArea};
+use unicode_width::UnicodeWidthChar;
+
+use crate::sql::{
+    complete::{complete, Completion},
+    format::format_sql,
+    highlight::{Highlight, Highlighter},
+    schema::DatabaseSchema,
+};
 
 const LINE_OFFSET: i32 = 10;
 
+/// Most completions shown at once; the list scrolls to show the rest
+const COMPLETION_ROWS: usize = 8;
+const COMPLETION_MAX_WIDTH: u16 = 48;
+
+/// How `mark_error` underlines the text at an error
+const ERROR_MARK_STYLE: Style = Style::new().fg(Color::Red).add_modifier(Modifier::UNDERLINED);
+
+/// Colors for each kind of SQL token
+#[derive(Debug, Clone, Copy)]
+pub struct SyntaxTheme {
+    pub keyword: Style,
+    pub identifier: Style,
+    pub string: Style,
+    pub dollar_string: Style,
+    pub number: Style,
+    pub comment: Style,
+    pub parameter: Style,
+    pub operator: Style,
+}
+
+pub const DARK: SyntaxTheme = SyntaxTheme {
+    keyword: Style::new().fg(Color::Rgb(96, 160, 240)).add_modifier(Modifier::BOLD),
+    identifier: Style::new().fg(Color::Rgb(220, 220, 220)),
+    string: Style::new().fg(Color::Rgb(152, 200, 120)),
+    dollar_string: Style::new().fg(Color::Rgb(200, 180, 120)),
+    number: Style::new().fg(Color::Rgb(208, 144, 96)),
+    comment: Style::new().fg(Color::Rgb(128, 128, 128)).add_modifier(Modifier::ITALIC),
+    parameter: Style::new().fg(Color::Rgb(200, 120, 220)),
+    operator: Style::new().fg(Color::Rgb(112, 192, 192)),
+};
+
+impl SyntaxTheme {
+    pub fn style(&self, highlight: Highlight) -> Style {
+        match highlight {
+            Highlight::Keyword => self.keyword,
+            Highlight::Identifier => self.identifier,
+            Highlight::String => self.string,
+            Highlight::DollarString => self.dollar_string,
+            Highlight::Number => self.number,
+            Highlight::Comment => self.comment,
+            Highlight::Parameter => self.parameter,
+            Highlight::Operator => self.operator,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct SearchableTextArea<'a> {
     inner: TextArea<'a>,
     search_pattern: String,
     last_search_pos: (usize, usize), // (line, column)
     initialized_height: u16,
+    /// Set while an error position is marked, with the selection style it replaced
+    error_mark: Option<Style>,
+    /// Set while completions are offered for the word at the cursor
+    completion: Option<CompletionPopup>,
+    /// Colors SQL as it is drawn, when set
+    syntax_theme: Option<SyntaxTheme>,
+    highlighter: RefCell<Highlighter>,
+    /// The (row, column) the text area last scrolled to. `TextArea` keeps this to itself,
+    /// so it is worked out the same way here; see `update_scroll_top`.
+    scroll_top: Cell<(usize, usize)>,
+    /// Rows of text shown when last drawn, for paging
+    page_height: Cell<usize>,
+}
+
+/// Completions for the word at the cursor, shown in a popup below it
+struct CompletionPopup {
+    items: Vec<Completion>,
+    selected: usize,
+    /// Column where the word being completed starts, on the cursor's line
+    start: usize,
 }
 
 
@@ -27,9 +110,17 @@ impl DerefMut for SearchableTextArea<'_> {
     }
 }
 
-impl<'a> Widget for &'a SearchableTextArea<'a> {
-    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
-        Widget::render(&self.inner, area, buf)
+impl Widget for &SearchableTextArea<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Widget::render(&self.inner, area, buf);
+        let area = self.inner.block().map_or(area, |block| block.inner(area));
+        let scroll_top = self.update_scroll_top(area);
+        if let Some(theme) = &self.syntax_theme {
+            self.render_highlights(theme, area, scroll_top, buf);
+        }
+        if let Some(popup) = &self.completion {
+            self.render_completions(popup, area, scroll_top, buf);
+        }
     }
 }
 
@@ -40,6 +131,7 @@ impl SearchableTextArea<'_> {
         );
         self.set_line_number_style(Style::default().bg(Color::DarkGray));
         self.set_cursor_line_style(Style::default());
+        self.syntax_theme = Some(DARK);
     }
 
     pub fn get_content(&self) -> String {
@@ -78,6 +170,276 @@ impl SearchableTextArea<'_> {
         
         self.search_pattern = String::new();
         self.last_search_pos = (0, 0);
+        self.error_mark = None;
+        self.completion = None;
+        self.scroll_top.set((0, 0));
+    }
+
+    /// Handles a key like `TextArea::input`, except that paging and mouse scrolling move the
+    /// cursor rather than scroll, so the view only ever follows the cursor (see `update_scroll_top`)
+    pub fn input(&mut self, input: impl Into<Input>) -> bool {
+        match input.into() {
+            Input { key: Key::PageDown, shift, .. } | Input { key: Key::Char('v'), ctrl: true, alt: false, shift } => {
+                self.move_rows(self.page_height.get().max(1) as isize, shift);
+                false
+            }
+            Input { key: Key::PageUp, shift, .. } | Input { key: Key::Char('v'), ctrl: false, alt: true, shift } => {
+                self.move_rows(-(self.page_height.get().max(1) as isize), shift);
+                false
+            }
+            Input { key: Key::MouseScrollDown, shift, .. } => {
+                self.move_rows(1, shift);
+                false
+            }
+            Input { key: Key::MouseScrollUp, shift, .. } => {
+                self.move_rows(-1, shift);
+                false
+            }
+            input => self.inner.input(input),
+        }
+    }
+
+    /// Moves the cursor `rows` down (or up, if negative), selecting on the way if `shift` is held
+    fn move_rows(&mut self, rows: isize, shift: bool) {
+        if shift && !self.inner.is_selecting() {
+            self.inner.start_selection();
+        } else if !shift {
+            self.inner.cancel_selection();
+        }
+        let (row, col) = self.inner.cursor();
+        let row = row.saturating_add_signed(rows).min(self.inner.lines().len() - 1);
+        self.inner.move_cursor(CursorMove::Jump(row as u16, col as u16));
+    }
+
+    /// Moves the cursor to `(row, column)` and underlines the word starting there,
+    /// e.g. where the server reported an error. The mark is drawn as a selection,
+    /// so it should be cleared with `clear_error_mark` before the next edit.
+    pub fn mark_error(&mut self, row: usize, column: usize) {
+        self.clear_error_mark();
+        let Some(line) = self.inner.lines().get(row) else {
+            return;
+        };
+        let chars: Vec<char> = line.chars().collect();
+        let column = column.min(chars.len());
+        let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+        let end = match chars.get(column) {
+            Some(c) if is_word(c) => column + chars[column..].iter().take_while(|c| is_word(c)).count(),
+            Some(_) => column + 1,
+            None => column,
+        };
+
+        self.error_mark = Some(self.inner.selection_style());
+        self.inner.cancel_selection();
+        self.inner.set_selection_style(ERROR_MARK_STYLE);
+        // Selected backwards, so the cursor ends up at the error
+        self.inner.move_cursor(CursorMove::Jump(row as u16, end as u16));
+        self.inner.start_selection();
+        self.inner.move_cursor(CursorMove::Jump(row as u16, column as u16));
+    }
+
+    pub fn clear_error_mark(&mut self) {
+        if let Some(style) = self.error_mark.take() {
+            self.inner.cancel_selection();
+            self.inner.set_selection_style(style);
+        }
+    }
+
+    /// Formats the selected SQL in place, or all of it if nothing is selected.
+    /// Returns whether it changed anything.
+    pub fn format(&mut self) -> bool {
+        let lines = self.inner.lines();
+        let (start, end) = match self.inner.selection_range() {
+            Some(range) => range,
+            // Up to the end of the last line of text, leaving the blank lines that pad the editor
+            None => match lines.iter().rposition(|line| !line.trim().is_empty()) {
+                Some(last) => ((0, 0), (last, lines[last].chars().count())),
+                None => return false,
+            },
+        };
+        let text = (start.0..=end.0)
+            .map(|row| {
+                let from = if row == start.0 { start.1 } else { 0 };
+                let to = if row == end.0 { end.1 } else { usize::MAX };
+                lines[row].chars().skip(from).take(to.saturating_sub(from)).collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        // Whitespace around a selection stays, so it still lines up with what's around it
+        let body = text.trim();
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+        let formatted = format!("{}{}{}", leading, format_sql(body), trailing);
+        if body.is_empty() || formatted == text {
+            return false;
+        }
+
+        let yank = self.inner.yank_text();
+        self.inner.cancel_selection();
+        self.inner.move_cursor(CursorMove::Jump(start.0 as u16, start.1 as u16));
+        self.inner.start_selection();
+        self.inner.move_cursor(CursorMove::Jump(end.0 as u16, end.1 as u16));
+        self.inner.cut();
+        self.inner.insert_str(&formatted);
+        self.inner.set_yank_text(yank);
+        true
+    }
+
+    /// Whether the cursor is just past a word or a `.`, where completing makes sense
+    pub fn is_after_word(&self) -> bool {
+        let (row, col) = self.inner.cursor();
+        col > 0 && self.inner.lines()[row]
+            .chars()
+            .nth(col - 1)
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    }
+
+    /// Offers completions for the word at the cursor, using `schema` for table and
+    /// column names. Returns whether there were any; the popup is closed if not.
+    pub fn open_completions(&mut self, schema: Option<&DatabaseSchema>) -> bool {
+        let (row, col) = self.inner.cursor();
+        let lines = self.inner.lines();
+        let line_start: usize = lines[..row].iter().map(|line| line.len() + 1).sum();
+        let col_offset = lines[row].char_indices().nth(col).map_or(lines[row].len(), |(i, _)| i);
+        let completions = complete(&lines.join("\n"), line_start + col_offset, schema);
+        if completions.items.is_empty() {
+            self.completion = None;
+            return false;
+        }
+        let start = lines[row][..completions.start - line_start].chars().count();
+        self.completion = Some(CompletionPopup { items: completions.items, selected: 0, start });
+        true
+    }
+
+    pub fn is_completing(&self) -> bool {
+        self.completion.is_some()
+    }
+
+    pub fn close_completions(&mut self) {
+        self.completion = None;
+    }
+
+    pub fn select_next_completion(&mut self) {
+        if let Some(popup) = &mut self.completion {
+            popup.selected = (popup.selected + 1) % popup.items.len();
+        }
+    }
+
+    pub fn select_previous_completion(&mut self) {
+        if let Some(popup) = &mut self.completion {
+            popup.selected = popup.selected.checked_sub(1).unwrap_or(popup.items.len() - 1);
+        }
+    }
+
+    /// Replaces the word at the cursor with the selected completion
+    pub fn accept_completion(&mut self) {
+        let Some(popup) = self.completion.take() else {
+            return;
+        };
+        let (row, col) = self.inner.cursor();
+        self.inner.move_cursor(CursorMove::Jump(row as u16, popup.start as u16));
+        self.inner.delete_str(col.saturating_sub(popup.start));
+        self.inner.insert_str(&popup.items[popup.selected].text);
+    }
+
+    /// Draws the completions below the word being completed, or above it if there is no room below
+    fn render_completions(&self, popup: &CompletionPopup, area: Rect, (top_row, left_col): (usize, usize), buf: &mut Buffer) {
+        let (row, _) = self.inner.cursor();
+        let start_x = self.gutter_width() + self.display_width(&self.inner.lines()[row], popup.start);
+
+        let rows = popup.items.len().min(COMPLETION_ROWS) as u16 + 2;
+        let width = popup.items
+            .iter()
+            .map(|item| item.text.chars().count() + item.detail.as_ref().map_or(0, |d| d.chars().count() + 2))
+            .max()
+            .unwrap_or(0) as u16 + 2;
+        let width = width.min(COMPLETION_MAX_WIDTH).min(area.width);
+        let cursor_y = area.y + (row - top_row) as u16;
+        let y = if cursor_y + 1 + rows <= area.bottom() { cursor_y + 1 } else { cursor_y.saturating_sub(rows).max(area.y) };
+        let x = (area.x + start_x.saturating_sub(left_col) as u16).min(area.right().saturating_sub(width));
+        let popup_area = Rect::new(x, y, width, rows.min(area.height)).intersection(area);
+
+        let items: Vec<Line> = popup.items
+            .iter()
+            .map(|item| {
+                let mut spans = vec![Span::raw(item.text.clone())];
+                if let Some(detail) = &item.detail {
+                    spans.push(Span::styled(format!("  {}", detail), Style::default().fg(Color::DarkGray)));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray)))
+            .style(Style::default().bg(Color::Black))
+            .highlight_style(Style::default().fg(Color::Black).bg(Color::LightBlue).bold());
+        let mut state = ListState::default().with_selected(Some(popup.selected));
+        Clear.render(popup_area, buf);
+        StatefulWidget::render(list, popup_area, buf, &mut state);
+    }
+
+    /// Width of the line numbers, including their margins
+    fn gutter_width(&self) -> usize {
+        match self.inner.line_number_style() {
+            Some(_) => self.inner.lines().len().to_string().len() + 2,
+            None => 0,
+        }
+    }
+
+    /// Width on screen of the first `chars` characters of `line`, with tabs expanded
+    fn display_width(&self, line: &str, chars: usize) -> usize {
+        let tab = self.inner.tab_length() as usize;
+        line.chars().take(chars).fold(0, |width, c| width + char_width(c, width, tab))
+    }
+
+    /// Works out where the text area scrolled to when drawn in `area`, as (top row, left column),
+    /// the way it does: only as far as needed to keep the cursor in view. The line numbers
+    /// count towards the cursor's column, doubled at first so they slide out of view smoothly.
+    fn update_scroll_top(&self, area: Rect) -> (usize, usize) {
+        let (row, col) = self.inner.cursor();
+        let gutter = self.gutter_width();
+        let cursor_x = if gutter > 0 && col <= gutter { col * 2 } else { col + gutter };
+        let (top_row, top_col) = self.scroll_top.get();
+        let scroll_top = (
+            next_scroll_top(top_row, row, area.height as usize),
+            next_scroll_top(top_col, cursor_x, area.width as usize),
+        );
+        self.page_height.set(area.height as usize);
+        self.scroll_top.set(scroll_top);
+        scroll_top
+    }
+
+    /// Colors the visible lines by their SQL tokens. Cells already styled by the
+    /// text area, like the error mark, are left as they are.
+    fn render_highlights(&self, theme: &SyntaxTheme, area: Rect, (top_row, left_col): (usize, usize), buf: &mut Buffer) {
+        let lines = self.inner.lines();
+        let bottom_row = (top_row + area.height as usize).min(lines.len());
+        let mut highlighter = self.highlighter.borrow_mut();
+        highlighter.update(lines, bottom_row);
+
+        let gutter = self.gutter_width();
+        let tab = self.inner.tab_length() as usize;
+        let right_col = left_col + area.width as usize;
+        for (y, row) in (area.y..).zip(top_row..bottom_row) {
+            let mut spans = highlighter.line(row).iter().peekable();
+            let mut x = gutter;
+            for (offset, c) in lines[row].char_indices() {
+                if x >= right_col {
+                    break;
+                }
+                let width = char_width(c, x - gutter, tab);
+                while spans.next_if(|span| span.end <= offset).is_some() {}
+                if let Some(span) = spans.peek().filter(|span| span.start <= offset) {
+                    let style = theme.style(span.highlight);
+                    for col in (x..x + width).filter(|col| (left_col..right_col).contains(col)) {
+                        let cell = &mut buf[(area.x + (col - left_col) as u16, y)];
+                        if cell.fg == Color::Reset {
+                            cell.set_style(style);
+                        }
+                    }
+                }
+                x += width;
+            }
+        }
     }
 
     pub fn update_dimensions(&mut self, height: u16) {
@@ -256,4 +618,24 @@ impl SearchableTextArea<'_> {
 
         count
     }
-}
\ No newline at end of file
+}
+
+/// Where a view of `len` rows (or columns) starting at `top` scrolls to to show `cursor`
+fn next_scroll_top(top: usize, cursor: usize, len: usize) -> usize {
+    if cursor < top {
+        cursor
+    } else if top + len <= cursor {
+        cursor + 1 - len
+    } else {
+        top
+    }
+}
+
+/// Width on screen of `c` at column `x`; tabs stretch to the next tab stop
+fn char_width(c: char, x: usize, tab: usize) -> usize {
+    match c {
+        '\t' if tab > 0 => tab - x % tab,
+        '\t' => 0,
+        c => c.width().unwrap_or(0),
+    }
+}
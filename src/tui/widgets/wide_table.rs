@@ -141,7 +141,7 @@ impl<'a> WideTable<'a> {
         let visible_rows: Vec<Vec<String>> = self.result.rows
             .iter()
             .map(|row| {
-                row[start..end].to_vec()
+                row[start..end].iter().map(|value| value.to_string()).collect()
             })
             .collect();
 
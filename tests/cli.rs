@@ -314,6 +314,11 @@ fn test_case_insensitive_lookup() {
         database: "testdb".to_string(),
         user: "postgres".to_string(),
         password: None,
+        read_only: false,
+        danger: false,
+        statement_timeout: None,
+        lock_timeout: None,
+        connect_timeout: None,
         server_ca: None,
         client_cert: None,
         client_key: None,
@@ -328,4 +333,619 @@ fn test_case_insensitive_lookup() {
     let connections = config_manager.list_connections().unwrap();
     assert_eq!(connections.len(), 1);
     assert_eq!(connections[0], "PostgreSQL");
-}
\ No newline at end of file
+}
+#[test]
+fn test_query_streams_csv_with_max_rows() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("SELECT n FROM generate_series(1, 10) AS n")
+        .arg("--format")
+        .arg("csv")
+        .arg("--max-rows")
+        .arg("3")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("n\n1\n2\n3\n")
+        .stderr(predicate::str::contains("first 3 rows shown"));
+}
+
+#[test]
+fn test_query_binds_params() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("SELECT $1 + 1 AS n, :name AS name")
+        .arg("--param")
+        .arg("41")
+        .arg("--param")
+        .arg("name=o'brien")
+        .arg("--format")
+        .arg("csv")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("n,name\n42,o'brien\n");
+
+    // `:n` in a slice is a column, and \N binds NULL
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("SELECT (ARRAY[1, 2, 3])[2:n] AS s, $1 + 1 AS missing FROM (SELECT 3 AS n) t")
+        .arg("--param")
+        .arg("\\N")
+        .arg("--format")
+        .arg("csv")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("s,missing\n\"{2,3}\",NULL\n");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("SELECT $1, :name")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No value given for parameter(s) $1, :name"));
+}
+
+#[test]
+fn test_query_refuses_destructive_statements_without_yes() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("CREATE TABLE t (a int); DELETE FROM t")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Refusing to run destructive statement(s) without --yes"))
+        .stderr(predicate::str::contains("DELETE without WHERE: DELETE FROM t"));
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("CREATE TABLE t (a int); DELETE FROM t; DROP TABLE t")
+        .arg("--yes")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_read_only_connection_rejects_writes() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    env.create_config(&format!(r#"
+connections:
+  - name: prod
+    conn: postgresql
+    host: localhost
+    port: {}
+    database: {}
+    user: {}
+    password: {}
+    read_only: true
+    production: true
+"#, db.db_port(), db.db_name(), db.db_user(), db.db_pass())).unwrap();
+
+    let query = |sql: &str| {
+        let mut cmd = AssertCommand::cargo_bin("sqli").unwrap();
+        cmd.arg("query")
+            .arg("--conn")
+            .arg("prod")
+            .arg("--sql")
+            .arg(sql)
+            .arg("--format")
+            .arg("csv")
+            .env("SQLI_CONFIG_DIR", env.temp_dir.path().join("sqli"))
+            .current_dir(&env.temp_dir);
+        cmd
+    };
+
+    query("SELECT 1 AS n").assert().success().stdout("n\n1\n");
+    query("CREATE TABLE t (a int)")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("read-only transaction"));
+}
+
+#[test]
+fn test_query_explain() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("SELECT * FROM generate_series(1, 10) AS g WHERE g > $1")
+        .arg("--param")
+        .arg("3")
+        .arg("--explain")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Function Scan on generate_series g  (cost="))
+        .stdout(predicate::str::contains("actual").not());
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("SELECT count(*) FROM generate_series(1, 10)")
+        .arg("--explain=analyze")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Aggregate  (cost="))
+        .stdout(predicate::str::contains("└─ Function Scan on generate_series"))
+        .stdout(predicate::str::contains("(actual time="))
+        .stdout(predicate::str::contains("Execution Time:"));
+}
+
+#[test]
+fn test_query_timeouts() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    env.create_config(&format!(r#"
+connections:
+  - name: slow
+    conn: postgresql
+    host: localhost
+    port: {}
+    database: {}
+    user: {}
+    password: {}
+    statement_timeout: 200ms
+"#, db.db_port(), db.db_name(), db.db_user(), db.db_pass())).unwrap();
+
+    let query = |sql: &str| {
+        let mut cmd = AssertCommand::cargo_bin("sqli").unwrap();
+        cmd.arg("query")
+            .arg("--conn")
+            .arg("slow")
+            .arg("--sql")
+            .arg(sql)
+            .arg("--format")
+            .arg("csv")
+            .env("SQLI_CONFIG_DIR", env.temp_dir.path().join("sqli"))
+            .current_dir(&env.temp_dir);
+        cmd
+    };
+
+    query("SELECT pg_sleep(5)")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Query timed out: it ran longer than the statement timeout of 200ms"));
+
+    // --timeout takes precedence over the connection's statement_timeout
+    query("SELECT 1 AS n FROM pg_sleep(0.5)")
+        .arg("--timeout")
+        .arg("10s")
+        .assert()
+        .success()
+        .stdout("n\n1\n");
+
+    query("SELECT 1")
+        .arg("--timeout")
+        .arg("soon")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid duration"));
+}
+
+#[test]
+fn test_sqlite_connection() {
+    let env = TestEnv::new();
+    let db_path = env.temp_dir.path().join("fixtures.db");
+    std::fs::File::create(&db_path).unwrap();
+
+    let sqli = || {
+        let mut cmd = AssertCommand::cargo_bin("sqli").unwrap();
+        cmd.env("SQLI_CONFIG_DIR", env.temp_dir.path().join("sqli"))
+            .current_dir(&env.temp_dir);
+        cmd
+    };
+
+    sqli()
+        .args(["config", "set", "--name", "fixtures", "--conn", "sqlite", "--database"])
+        .arg(&db_path)
+        .assert()
+        .success();
+
+    sqli()
+        .args(["query", "--conn", "fixtures", "--format", "csv", "--sql"])
+        .arg("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT); INSERT INTO users (name) VALUES ('alice'), ('bob'); SELECT * FROM users WHERE name = $1")
+        .args(["--param", "bob"])
+        .assert()
+        .success()
+        .stdout("id,name\n2,bob\n");
+
+    // A bare path works as the URL too
+    sqli()
+        .args(["query", "--format", "csv", "--sql", "SELECT count(*) AS n FROM users", "--url"])
+        .arg(&db_path)
+        .assert()
+        .success()
+        .stdout("n\n2\n");
+
+    sqli()
+        .args(["query", "--url", "mysql://localhost/app", "--sql", "SELECT 1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unsupported database URL scheme 'mysql'"));
+}
+
+#[test]
+fn test_query_prints_notices_to_stderr() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .arg("query")
+        .arg("--url")
+        .arg(db.connection_uri())
+        .arg("--sql")
+        .arg("DO $$ BEGIN RAISE NOTICE 'checked % rows', 3; END $$; SELECT 1 AS n")
+        .arg("--format")
+        .arg("csv")
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("n\n1\n")
+        .stderr("NOTICE:  checked 3 rows\n");
+}
+
+#[test]
+fn test_listen_prints_notifications() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    let mut listen = std::process::Command::new(assert_cmd::cargo::cargo_bin("sqli"))
+        .args(["listen", "--url", &db.connection_uri(), "--format", "raw", "jobs", "events"])
+        .current_dir(&env.temp_dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stderr = std::io::BufReader::new(listen.stderr.take().unwrap());
+    let mut stdout = std::io::BufReader::new(listen.stdout.take().unwrap());
+
+    let mut line = String::new();
+    std::io::BufRead::read_line(&mut stderr, &mut line).unwrap();
+    assert_eq!(line, "Listening on jobs, events (Ctrl+C to stop)\n");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "NOTIFY events, 'job 7 done'"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success();
+
+    line.clear();
+    std::io::BufRead::read_line(&mut stdout, &mut line).unwrap();
+    listen.kill().unwrap();
+    listen.wait().unwrap();
+    let fields: Vec<&str> = line.trim_end().split('\t').collect();
+    assert_eq!(fields[1..], ["events", "job 7 done"]);
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["listen", "--url", "sqlite://jobs.db", "jobs"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("LISTEN/NOTIFY is only supported for PostgreSQL connections"));
+}
+
+#[test]
+fn test_query_reports_rows_affected() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+    let sql = "CREATE TABLE jobs (id int, done bool); INSERT INTO jobs VALUES (1, false), (2, false); UPDATE jobs SET done = true WHERE id < 3";
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", sql])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("CREATE TABLE\n\nINSERT 0 2\n\nUPDATE 2\n");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "DELETE FROM jobs WHERE id = 1", "--format", "json"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("{\n  \"command_tag\": \"DELETE 1\",\n  \"rows_affected\": 1\n}\n");
+
+    let output = AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "INSERT INTO jobs VALUES (3, true) RETURNING id; SELECT count(*) AS n FROM jobs", "--format", "json", "--yes"])
+        .current_dir(&env.temp_dir)
+        .output()
+        .unwrap();
+    let statements: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(statements, serde_json::json!([
+        {"rows": [{"id": 3}], "command_tag": "INSERT 0 1", "rows_affected": 1},
+        {"rows": [{"n": 2}], "command_tag": "SELECT 1"},
+    ]));
+}
+
+#[test]
+fn test_query_error_position_and_exit_code() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "SELECT 1;\nSELEC 2"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .code(4)
+        .stderr("Statement 2 of 2 failed\nERROR:  42601: syntax error at or near \"SELEC\"\nLINE 2: SELEC 2\n        ^\n");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "CREATE TABLE jobs (id int PRIMARY KEY); INSERT INTO jobs VALUES (1), (1)"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .code(6)
+        .stderr(predicate::str::contains("ERROR:  23505: duplicate key value violates unique constraint \"jobs_pkey\""))
+        .stderr(predicate::str::contains("DETAIL:  Key (id)=(1) already exists."))
+        .stderr(predicate::str::contains("CONSTRAINT:  jobs_pkey"));
+}
+
+#[test]
+fn test_import_csv_and_json_lines() {
+    let env = TestEnv::new();
+    let db = pgtemp::PgTempDB::new();
+    let csv = env.temp_dir.path().join("users.csv");
+    fs::write(&csv, "id,name,zip,joined\n1,Ada,02134,2024-01-31\n2,\"Grace, H\",94105,\n").unwrap();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["import", "--url", &db.connection_uri(), "--table", "users", "--create"])
+        .arg(&csv)
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("CREATE TABLE users (\"id\" bigint, \"name\" text, \"zip\" text, \"joined\" date)\nImported 2 rows into users in "));
+
+    let jsonl = env.temp_dir.path().join("more.jsonl");
+    fs::write(&jsonl, "{\"id\": 3, \"full_name\": \"Edsger\", \"extra\": true}\n{\"id\": 4, \"full_name\": null}\n").unwrap();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["import", "--url", &db.connection_uri(), "--table", "users", "--map", "full_name=name", "--map", "extra="])
+        .arg(&jsonl)
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Imported 2 rows into users in "));
+
+    // `--format tsv` means tab-separated whatever the extension
+    let tsv = env.temp_dir.path().join("more.txt");
+    fs::write(&tsv, "id\tname\n5\tBarbara, L\n").unwrap();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["import", "--url", &db.connection_uri(), "--table", "users", "--format", "tsv"])
+        .arg(&tsv)
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Imported 1 rows into users in "));
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "SELECT id, name, zip FROM users ORDER BY id", "--format", "csv"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("id,name,zip\n1,Ada,02134\n2,\"Grace, H\",94105\n3,Edsger,NULL\n4,NULL,NULL\n5,\"Barbara, L\",NULL\n");
+
+    // Bad data rolls the whole import back, and exits with the code for data errors
+    let bad = env.temp_dir.path().join("bad.csv");
+    fs::write(&bad, "id,name\n5,Barbara\nsix,Frances\n").unwrap();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["import", "--url", &db.connection_uri(), "--table", "users"])
+        .arg(&bad)
+        .current_dir(&env.temp_dir)
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("ERROR:  22P02: invalid input syntax for type bigint: \"six\""));
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", &db.connection_uri(), "--sql", "SELECT count(*) FROM users", "--format", "raw"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("5"));
+}
+
+#[test]
+fn test_schema_lists_and_describes_tables() {
+    let env = TestEnv::new();
+    let db_path = env.temp_dir.path().join("fixtures.db");
+    fs::File::create(&db_path).unwrap();
+    let db = db_path.to_str().unwrap();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["query", "--url", db, "--sql", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT 'anon'); CREATE VIEW names AS SELECT name FROM users"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["schema", "--url", db, "--format", "csv"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("schema,name,type\nmain,names,view\nmain,users,table\n");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["schema", "--url", db, "users", "--format", "csv"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("column,type,nullable,default\nid,INTEGER,true,NULL\nname,TEXT,false,'anon'\nconstraint,type,definition\nusers_pkey,PRIMARY KEY,PRIMARY KEY (id)\n");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["schema", "--url", db, "users", "--format", "json"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"name\": \"users\"").and(predicate::str::contains("\"kind\": \"primary_key\"")));
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["schema", "--url", db, "missing"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Table 'missing' not found"));
+}
+
+#[test]
+fn test_history_records_queries() {
+    let env = TestEnv::new();
+    let db_path = env.temp_dir.path().join("fixtures.db");
+    fs::File::create(&db_path).unwrap();
+
+    let sqli = || {
+        let mut cmd = AssertCommand::cargo_bin("sqli").unwrap();
+        cmd.env("SQLI_CONFIG_DIR", env.temp_dir.path().join("sqli"))
+            .current_dir(&env.temp_dir);
+        cmd
+    };
+
+    sqli()
+        .args(["config", "set", "--name", "fixtures", "--conn", "sqlite", "--database"])
+        .arg(&db_path)
+        .assert()
+        .success();
+    sqli()
+        .args(["query", "--conn", "fixtures", "--sql", "CREATE TABLE users (id INTEGER); INSERT INTO users VALUES (1), (2)"])
+        .assert()
+        .success();
+    sqli()
+        .args(["query", "--conn", "fixtures", "--sql", "SELECT * FROM missing"])
+        .assert()
+        .failure();
+    sqli()
+        .args(["query", "--sql", "SELECT * FROM users", "--url"])
+        .arg(&db_path)
+        .assert()
+        .success();
+
+    let output = sqli().args(["history", "--format", "json"]).output().unwrap();
+    let history: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = history.as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0]["connection"], "fixtures");
+    assert_eq!(entries[0]["rows"], 2);
+    assert!(entries[0]["error"].is_null());
+    assert_eq!(entries[1]["sql"], "SELECT * FROM missing");
+    assert!(entries[1]["rows"].is_null());
+    assert!(entries[1]["error"].as_str().unwrap().contains("no such table"));
+    // The URL isn't kept, as it may hold a password
+    assert!(entries[2]["connection"].is_null());
+    assert_eq!(entries[2]["rows"], 2);
+
+    sqli()
+        .args(["history", "--conn", "FIXTURES", "--grep", "select", "--format", "csv"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("timestamp,connection,duration_ms,rows,error,sql\n")
+            .and(predicate::str::contains("SELECT * FROM missing"))
+            .and(predicate::str::contains("CREATE TABLE").not())
+            .and(predicate::str::contains("SELECT * FROM users").not()));
+}
+
+#[test]
+fn test_fmt_checks_and_formats_collections() {
+    let env = TestEnv::new();
+    let workspace = env.temp_dir.path().join(".sqli");
+    fs::create_dir_all(workspace.join("reports")).unwrap();
+    fs::write(workspace.join("reports/active.sql"), "select id from users where active and id > 1;").unwrap();
+    fs::write(workspace.join("reports/formatted.sql"), "SELECT 1;\n").unwrap();
+    fs::write(workspace.join("reports/notes.txt"), "select not sql").unwrap();
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["fmt", "--check"])
+        .env("SQLI_WORKSPACE_DIR", &workspace)
+        .current_dir(&env.temp_dir)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("active.sql").and(predicate::str::contains("formatted.sql").not()))
+        .stderr(predicate::str::contains("1 of 2 files are not formatted"));
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["fmt"])
+        .env("SQLI_WORKSPACE_DIR", &workspace)
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Formatted").and(predicate::str::contains("active.sql")));
+    assert_eq!(
+        fs::read_to_string(workspace.join("reports/active.sql")).unwrap(),
+        "SELECT id\nFROM users\nWHERE active\n    AND id > 1;\n"
+    );
+    assert_eq!(fs::read_to_string(workspace.join("reports/notes.txt")).unwrap(), "select not sql");
+
+    AssertCommand::cargo_bin("sqli")
+        .unwrap()
+        .args(["fmt", "--check", ".sqli/reports/active.sql"])
+        .current_dir(&env.temp_dir)
+        .assert()
+        .success()
+        .stdout("");
+}
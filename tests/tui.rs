@@ -1,19 +1,38 @@
 use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use insta::assert_snapshot;
 use ratatui::{
     backend::TestBackend,
+    style::Style,
     Terminal,
 };
-use std::time::Duration;
+use chrono::{Local, TimeZone};
+use std::{sync::Arc, time::Duration};
+use tui_textarea::CursorMove;
 use sqli::{
-    collection::CollectionScope, settings::UserSettings, sql::result::QueryResult, tui::{
-        app::App, navigation::PaneId, ui::UI
+    collection::CollectionScope, history::{History, HistoryEntry}, settings::UserSettings, sql::{error::{ErrorPosition, QueryError}, explain::ExplainPlan, listen::Notification, notice::{Notice, NoticeSeverity}, params::{Placeholder, QueryParams}, result::QueryResult, schema::{Column, Constraint, ConstraintKind, DatabaseSchema, Index, Schema, Table, TableKind}, session::TransactionStatus}, tui::{
+        app::{App, AppCommand, ListenState}, navigation::PaneId, ui::UI, widgets::{history_modal::HistoryModal, listen_modal::ListenModal, searchable_textarea::DARK}
     }
 };
 
 mod helpers;
 use helpers::TestEnv;
 
+/// The style of the first cell on screen showing `text`
+fn style_of(terminal: &Terminal<TestBackend>, text: &str) -> Option<Style> {
+    let buffer = terminal.backend().buffer();
+    let len = text.chars().count() as u16;
+    for y in 0..buffer.area.height {
+        for x in 0..buffer.area.width.saturating_sub(len) {
+            let shown: String = (x..x + len).map(|x| buffer[(x, y)].symbol()).collect();
+            if shown == text {
+                return Some(buffer[(x, y)].style());
+            }
+        }
+    }
+    None
+}
+
 #[test]
 fn test_main_ui_layout() -> Result<()> {
     let env = TestEnv::new();
@@ -114,6 +133,159 @@ fn test_workspace_pane_with_content() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_workspace_autocomplete() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+    ui.update_dimensions(&mut app, 30);
+
+    let column = |name: &str, data_type: &str| Column {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        nullable: true,
+        default: None,
+    };
+    let users = Table {
+        schema: "public".to_string(),
+        name: "users".to_string(),
+        kind: TableKind::Table,
+        columns: vec![column("id", "integer"), column("name", "text"), column("email", "text")],
+        constraints: Vec::new(),
+        indexes: Vec::new(),
+    };
+    app.set_schema(Arc::new(DatabaseSchema {
+        schemas: vec![Schema { name: "public".to_string(), tables: vec![users], ..Schema::default() }],
+    }));
+
+    app.navigation.activate_pane(PaneId::Workspace)?;
+    app.navigation.start_editing(PaneId::Workspace)?;
+    let send = |app: &mut App, ui: &mut UI, code: KeyCode| app.handle_key(ui, KeyEvent::new(code, KeyModifiers::NONE));
+    for c in "SELECT * FROM us".chars() {
+        send(&mut app, &mut ui, KeyCode::Char(c))?;
+    }
+    // Tab after a word completes it; with a single match it still asks first
+    send(&mut app, &mut ui, KeyCode::Tab)?;
+    assert!(app.ui_state.workspace.is_completing());
+    send(&mut app, &mut ui, KeyCode::Enter)?;
+    assert_eq!(app.ui_state.workspace.get_content(), "SELECT * FROM users");
+
+    for c in " u WHERE u.".chars() {
+        send(&mut app, &mut ui, KeyCode::Char(c))?;
+    }
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!(terminal.backend());
+
+    // Typing narrows the list down
+    send(&mut app, &mut ui, KeyCode::Char('e'))?;
+    send(&mut app, &mut ui, KeyCode::Tab)?;
+    assert_eq!(app.ui_state.workspace.get_content(), "SELECT * FROM users u WHERE u.email");
+    assert!(!app.ui_state.workspace.is_completing());
+
+    // Esc closes the list without leaving the editor
+    for c in " AND u.".chars() {
+        send(&mut app, &mut ui, KeyCode::Char(c))?;
+    }
+    send(&mut app, &mut ui, KeyCode::Esc)?;
+    assert!(!app.ui_state.workspace.is_completing());
+    assert!(app.is_pane_in_edit_mode(PaneId::Workspace));
+
+    Ok(())
+}
+
+#[test]
+fn test_workspace_syntax_highlighting() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+    ui.update_dimensions(&mut app, 30);
+
+    app.ui_state.workspace.insert_str(
+        "-- active users\n\
+         SELECT name, 42 FROM users\n\
+         WHERE status = 'active' AND id > $1;"
+    );
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_eq!(style_of(&terminal, "-- active").map(|s| s.fg), Some(DARK.comment.fg));
+    assert_eq!(style_of(&terminal, "SELECT").map(|s| s.fg), Some(DARK.keyword.fg));
+    assert_eq!(style_of(&terminal, "name").map(|s| s.fg), Some(DARK.identifier.fg));
+    assert_eq!(style_of(&terminal, "42").map(|s| s.fg), Some(DARK.number.fg));
+    assert_eq!(style_of(&terminal, "'active'").map(|s| s.fg), Some(DARK.string.fg));
+    assert_eq!(style_of(&terminal, "$1").map(|s| s.fg), Some(DARK.parameter.fg));
+
+    // Long files are highlighted wherever they are scrolled to
+    app.ui_state.workspace.clear();
+    let sql: Vec<String> = (0..5000).map(|i| format!("SELECT {} FROM t; -- line {}", i, i)).collect();
+    app.ui_state.workspace.insert_str(sql.join("\n"));
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_eq!(style_of(&terminal, "-- line 4999").map(|s| s.fg), Some(DARK.comment.fg));
+    assert_eq!(style_of(&terminal, "4999 FROM").map(|s| s.fg), Some(DARK.number.fg));
+
+    app.navigation.activate_pane(PaneId::Workspace)?;
+    app.navigation.start_editing(PaneId::Workspace)?;
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE))?;
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_eq!(style_of(&terminal, "-- line 4987").map(|s| s.fg), Some(DARK.comment.fg));
+    assert_eq!(style_of(&terminal, "4987 FROM").map(|s| s.fg), Some(DARK.number.fg));
+
+    Ok(())
+}
+
+#[test]
+fn test_workspace_format() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+    ui.update_dimensions(&mut app, 30);
+    app.navigation.activate_pane(PaneId::Workspace)?;
+    app.navigation.start_editing(PaneId::Workspace)?;
+    let format = KeyEvent::new(KeyCode::Char('F'), KeyModifiers::ALT | KeyModifiers::SHIFT);
+
+    // Only the selection is formatted when there is one
+    app.ui_state.workspace.insert_str("select 1;
+select a,b from t where x=1 and y=2;");
+    app.ui_state.workspace.move_cursor(CursorMove::Jump(1, 0));
+    app.ui_state.workspace.start_selection();
+    app.ui_state.workspace.move_cursor(CursorMove::Jump(1, 17));
+    app.handle_key(&mut ui, format)?;
+    assert_eq!(app.ui_state.workspace.get_content(), "select 1;
+SELECT a, b
+FROM t where x=1 and y=2;");
+    assert_eq!(app.ui_state.message, "Formatted the selection");
+
+    app.handle_key(&mut ui, format)?;
+    assert_eq!(app.ui_state.workspace.get_content(), "SELECT 1;
+SELECT a, b
+FROM t
+WHERE x = 1
+    AND y = 2;");
+    assert_eq!(app.ui_state.message, "Formatted the query");
+
+    app.handle_key(&mut ui, format)?;
+    assert_eq!(app.ui_state.message, "Already formatted");
+
+    Ok(())
+}
+
 #[test]
 fn test_results_pane_with_data() -> Result<()> {
     let env = TestEnv::new();
@@ -129,17 +301,17 @@ fn test_results_pane_with_data() -> Result<()> {
     
     app.navigation.activate_pane(PaneId::Results)?;
     
-    app.query_state.query_result = QueryResult::new(
+    app.query_state.set_results(vec![QueryResult::new(
         vec!["id".to_string(), "name".to_string(), "email".to_string(), "order_count".to_string()],
         vec![
-            vec!["1".to_string(), "John Doe".to_string(), "john@example.com".to_string(), "5".to_string()],
-            vec!["2".to_string(), "Jane Smith".to_string(), "jane@example.com".to_string(), "3".to_string()],
-            vec!["3".to_string(), "Bob Johnson".to_string(), "bob@example.com".to_string(), "7".to_string()],
-            vec!["4".to_string(), "Alice Brown".to_string(), "alice@example.com".to_string(), "2".to_string()],
-            vec!["5".to_string(), "Charlie Wilson".to_string(), "charlie@example.com".to_string(), "9".to_string()],
+            vec!["1".into(), "John Doe".into(), "john@example.com".into(), "5".into()],
+            vec!["2".into(), "Jane Smith".into(), "jane@example.com".into(), "3".into()],
+            vec!["3".into(), "Bob Johnson".into(), "bob@example.com".into(), "7".into()],
+            vec!["4".into(), "Alice Brown".into(), "alice@example.com".into(), "2".into()],
+            vec!["5".into(), "Charlie Wilson".into(), "charlie@example.com".into(), "9".into()],
         ],
         Duration::from_millis(42)
-    );
+    )]);
     
     terminal.draw(|frame| ui.render(&mut app, frame))?;
     
@@ -148,6 +320,205 @@ fn test_results_pane_with_data() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_results_pane_with_notices() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 40);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    app.query_state.set_results(vec![QueryResult::new(
+        vec!["id".to_string()],
+        vec![vec!["1".into()]],
+        Duration::from_millis(3)
+    ).with_notices(vec![
+        Notice::new(NoticeSeverity::Notice, "relation \"users\" already exists, skipping"),
+        Notice::new(NoticeSeverity::Warning, "there is no transaction in progress"),
+    ])]);
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+
+    assert_snapshot!(terminal.backend());
+
+    Ok(())
+}
+
+#[test]
+fn test_results_pane_with_rows_affected() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    app.query_state.set_results(vec![
+        QueryResult::new(Vec::new(), Vec::new(), Duration::from_millis(4)).with_command_tag("UPDATE 2".to_string()).with_rows_affected(Some(2)),
+    ]);
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+
+    assert_snapshot!(terminal.backend());
+
+    Ok(())
+}
+
+#[test]
+fn test_results_pane_with_query_error() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    app.ui_state.workspace.insert_str("SELECT id,\n       nme FROM users;");
+    app.set_query_error(Some(QueryError {
+        severity: "ERROR".to_string(),
+        code: "42703".to_string(),
+        message: "column \"nme\" does not exist".to_string(),
+        detail: None,
+        hint: Some("Perhaps you meant to reference the column \"users.name\".".to_string()),
+        context: None,
+        constraint: None,
+        position: Some(ErrorPosition { line: 1, column: 7, line_text: "       nme FROM users;".to_string() }),
+    }));
+
+    // The cursor jumps to the error, and the first key typed clears the mark
+    assert_eq!(app.ui_state.workspace.cursor(), (1, 7));
+    assert_eq!(app.ui_state.workspace.selection_range(), Some(((1, 7), (1, 10))));
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+
+    assert_snapshot!(terminal.backend());
+
+    Ok(())
+}
+
+#[test]
+fn test_results_pane_with_notifications() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 40);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    let mut listen = ListenState::new("local", vec!["jobs".to_string(), "job_events".to_string()]);
+    for (second, channel, payload) in [(5, "jobs", "42"), (7, "job_events", r#"{"id": 42, "status": "done"}"#)] {
+        listen.push(Notification {
+            received_at: Local.with_ymd_and_hms(2024, 5, 1, 12, 30, second).unwrap(),
+            channel: channel.to_string(),
+            payload: payload.to_string(),
+            process_id: 4242,
+        });
+    }
+    app.query_state.listen = Some(listen);
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+
+    assert_snapshot!(terminal.backend());
+
+    Ok(())
+}
+
+#[test]
+fn test_listen_modal_reads_channels() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL))?;
+    for c in "jobs, events  audit".chars() {
+        app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))?;
+    }
+
+    let modal = app.modal_manager.get_active_modal_as::<ListenModal>().expect("listen modal is open");
+    assert_eq!(modal.get_channels(), vec!["jobs", "events", "audit"]);
+
+    // There is no connection to listen on
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))?;
+    assert_eq!(app.ui_state.message, "Select a connection to listen on");
+    assert!(app.query_state.listen.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_history_modal() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+    ui.update_dimensions(&mut app, 30);
+
+    let history = History::new(&app.fs)?;
+    let runs = [
+        ("SELECT * FROM orders\nWHERE amount > 100;", Some(2)),
+        ("SELECT * FROM users;", Some(3)),
+        ("DELETE FROM sesions;", None),
+        ("SELECT * FROM users;", Some(4)),
+    ];
+    for (minute, (sql, rows)) in runs.into_iter().enumerate() {
+        history.append(&HistoryEntry {
+            timestamp: Local.with_ymd_and_hms(2024, 5, 1, 12, minute as u32, 0).unwrap(),
+            connection: Some("local".to_string()),
+            sql: sql.to_string(),
+            duration_ms: 12,
+            rows,
+            error: rows.is_none().then(|| "relation \"sesions\" does not exist".to_string()),
+        })?;
+    }
+
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL))?;
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!(terminal.backend());
+
+    // Searching narrows the list down, and Enter inserts the best match below the cursor's line
+    for c in "ordr".chars() {
+        app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))?;
+    }
+    let modal = app.modal_manager.get_active_modal_as::<HistoryModal>().expect("history modal is open");
+    assert_eq!(modal.selected_sql(), Some("SELECT * FROM orders\nWHERE amount > 100;"));
+
+    app.ui_state.workspace.insert_str("SELECT 1;");
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))?;
+    assert!(!app.modal_manager.is_modal_active());
+    assert_eq!(app.ui_state.workspace.get_content(), "SELECT 1;\nSELECT * FROM orders\nWHERE amount > 100;");
+    assert_eq!(app.ui_state.message, "Inserted a query from the history");
+
+    Ok(())
+}
+
 #[test]
 fn test_modal_dialog() -> Result<()> {
     let env = TestEnv::new();
@@ -225,6 +596,293 @@ fn test_edit_file_modal() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_params_modal() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    let mut remembered = QueryParams::new();
+    remembered.set(Placeholder::Positional(1), "42".to_string());
+    app.query_state.param_values.insert("users/get.sql".to_string(), remembered);
+    app.ui_state.loaded_file = Some("users/get.sql".to_string());
+    // Values are remembered for the loaded file, not for whatever the tree cursor is on
+    app.ui_state.collection_state.select(vec!["products".to_string()]);
+
+    app.ui_state.workspace.insert_str("SELECT * FROM users WHERE id = $1 AND status = :status;");
+    app.query_state.pending_command = AppCommand::ExecuteQuery;
+    app.tick();
+
+    assert!(app.modal_manager.is_modal_active());
+    assert!(app.query_state.pending_async_operation.is_none());
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+
+    assert_snapshot!(terminal.backend());
+
+    Ok(())
+}
+
+#[test]
+fn test_confirm_destructive_modal() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    app.ui_state.workspace.insert_str("DELETE FROM users;\nUPDATE orders SET amount = 0 WHERE id = 1;\nDROP TABLE orders;");
+    app.query_state.pending_command = AppCommand::ExecuteQuery;
+    app.tick();
+
+    assert!(app.modal_manager.is_modal_active());
+    assert!(app.query_state.pending_async_operation.is_none());
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+
+    assert_snapshot!(terminal.backend());
+
+    // Cancel is the default choice
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))?;
+    assert!(!app.modal_manager.is_modal_active());
+    assert!(app.query_state.pending_async_operation.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_explain_plan_tree() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    let plan = ExplainPlan::from_json(&serde_json::json!([{
+        "Plan": {
+            "Node Type": "Hash Join", "Join Type": "Inner",
+            "Startup Cost": 1.07, "Total Cost": 2.2, "Plan Rows": 3, "Plan Width": 36,
+            "Actual Startup Time": 0.03, "Actual Total Time": 10.0, "Actual Rows": 3, "Actual Loops": 1,
+            "Plans": [
+                {
+                    "Node Type": "Seq Scan", "Relation Name": "orders", "Alias": "o",
+                    "Startup Cost": 0.0, "Total Cost": 1.03, "Plan Rows": 3, "Plan Width": 20,
+                    "Actual Startup Time": 0.01, "Actual Total Time": 8.0, "Actual Rows": 3000, "Actual Loops": 1
+                },
+                {
+                    "Node Type": "Hash",
+                    "Startup Cost": 1.03, "Total Cost": 1.03, "Plan Rows": 3, "Plan Width": 20,
+                    "Actual Startup Time": 0.5, "Actual Total Time": 0.5, "Actual Rows": 3, "Actual Loops": 1,
+                    "Plans": [{
+                        "Node Type": "Seq Scan", "Relation Name": "users", "Alias": "u",
+                        "Startup Cost": 0.0, "Total Cost": 1.03, "Plan Rows": 3, "Plan Width": 20,
+                        "Actual Startup Time": 0.1, "Actual Total Time": 0.4, "Actual Rows": 3, "Actual Loops": 1
+                    }]
+                }
+            ]
+        },
+        "Planning Time": 0.125,
+        "Execution Time": 10.5
+    }]))?;
+    app.set_explain_plan(plan);
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!(terminal.backend());
+
+    // Collapsing the Hash node hides its scan
+    app.navigation.activate_pane(PaneId::Results)?;
+    app.navigation.start_editing(PaneId::Results)?;
+    for code in [KeyCode::Down, KeyCode::Down, KeyCode::Left] {
+        app.handle_key(&mut ui, KeyEvent::new(code, KeyModifiers::NONE))?;
+    }
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!("explain_plan_tree_collapsed", terminal.backend());
+
+    Ok(())
+}
+
+#[test]
+fn test_schema_browser() -> Result<()> {
+    let env = TestEnv::new();
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    let column = |name: &str, data_type: &str, nullable: bool| Column {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        nullable,
+        default: None,
+    };
+    let users = Table {
+        schema: "public".to_string(),
+        name: "users".to_string(),
+        kind: TableKind::Table,
+        columns: vec![column("id", "integer", false), column("email", "text", true)],
+        constraints: vec![Constraint {
+            name: "users_pkey".to_string(),
+            kind: ConstraintKind::PrimaryKey,
+            columns: vec!["id".to_string()],
+            references: None,
+            definition: "PRIMARY KEY (id)".to_string(),
+        }],
+        indexes: vec![Index {
+            name: "users_pkey".to_string(),
+            columns: vec!["id".to_string()],
+            unique: true,
+            primary: true,
+            definition: Some("CREATE UNIQUE INDEX users_pkey ON public.users USING btree (id)".to_string()),
+        }],
+    };
+    let active_users = Table {
+        name: "active_users".to_string(),
+        kind: TableKind::View,
+        constraints: Vec::new(),
+        indexes: Vec::new(),
+        ..users.clone()
+    };
+    let schema = DatabaseSchema {
+        schemas: vec![
+            Schema { name: "audit".to_string(), ..Schema::default() },
+            Schema { name: "public".to_string(), tables: vec![active_users, users], ..Schema::default() },
+        ],
+    };
+    app.set_schema(Arc::new(schema));
+    ui.update_dimensions(&mut app, 30);
+
+    // Switch to the schema tab and open public.users
+    app.navigation.activate_pane(PaneId::Collections)?;
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE))?;
+    app.navigation.start_editing(PaneId::Collections)?;
+    for code in [KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Down, KeyCode::Enter, KeyCode::Down] {
+        // Moving through the tree goes by the nodes last drawn
+        terminal.draw(|frame| ui.render(&mut app, frame))?;
+        app.handle_key(&mut ui, KeyEvent::new(code, KeyModifiers::NONE))?;
+    }
+    assert_eq!(app.ui_state.schema_state.selected(), ["public", "users", "columns"]);
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!(terminal.backend());
+
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE))?;
+    assert_eq!(app.ui_state.workspace.get_content(), "SELECT * FROM public.users LIMIT 100;");
+
+    app.ui_state.workspace.clear();
+    app.handle_key(&mut ui, KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE))?;
+    assert!(app.modal_manager.is_modal_active());
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!("schema_browser_ddl", terminal.backend());
+
+    // Inserting the DDL closes the modal
+    for code in [KeyCode::Tab, KeyCode::Enter] {
+        app.handle_key(&mut ui, KeyEvent::new(code, KeyModifiers::NONE))?;
+    }
+    assert!(!app.modal_manager.is_modal_active());
+    assert_eq!(app.ui_state.workspace.get_content(), "\
+CREATE TABLE public.users (
+    id integer NOT NULL,
+    email text,
+    CONSTRAINT users_pkey PRIMARY KEY (id)
+);");
+
+    Ok(())
+}
+
+#[test]
+fn test_production_connection_header() -> Result<()> {
+    let env = TestEnv::new();
+
+    env.create_config(r#"
+connections:
+  - name: prod
+    conn: postgresql
+    host: prod-db.example.com
+    port: 5432
+    database: prod_db
+    user: app_user
+    read_only: true
+    production: true
+"#)?;
+
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    assert!(app.is_danger_connection());
+    assert!(app.is_read_only_connection());
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!(terminal.backend());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_session_mode_header() -> Result<()> {
+    let env = TestEnv::new();
+
+    env.create_config(r#"
+connections:
+  - name: test_db
+    conn: postgresql
+    host: localhost
+    port: 5432
+    database: testdb
+    user: postgres
+"#)?;
+
+    let settings = UserSettings::new(
+        env.temp_dir.path().join("sqli"),
+        env.temp_dir.path().join("sqli")
+    );
+
+    let backend = TestBackend::new(100, 30);
+    let mut terminal = Terminal::new(backend)?;
+    let mut app = App::with_settings(Some(settings))?;
+    let mut ui = UI::new();
+
+    app.query_state.pending_command = AppCommand::ToggleSession;
+    app.tick();
+    assert_eq!(app.query_state.transaction_status(), Some(TransactionStatus::Idle));
+
+    terminal.draw(|frame| ui.render(&mut app, frame))?;
+    assert_snapshot!(terminal.backend());
+
+    app.query_state.pending_command = AppCommand::ToggleSession;
+    app.tick();
+    assert!(app.query_state.session.is_none());
+
+    Ok(())
+}
+
 #[test]
 fn test_header_pane_with_connection() -> Result<()> {
     let env = TestEnv::new();